@@ -6,6 +6,7 @@ fn main() {
         height: 600,
         format: ImageOutputFormat::Jpeg,
         quality: 85,
+        ..ImageConfig::default()
     };
     
     let processor = ImagePreprocessor::new(config);
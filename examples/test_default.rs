@@ -7,6 +7,7 @@ fn main() {
         height: 683,
         format: ImageOutputFormat::Webp,
         quality: 85,
+        ..ImageConfig::default()
     };
     
     let config_jpg = ImageConfig {
@@ -14,6 +15,7 @@ fn main() {
         height: 683,
         format: ImageOutputFormat::Jpeg,
         quality: 85,
+        ..ImageConfig::default()
     };
     
     let processor_webp = ImagePreprocessor::new(config_webp);
@@ -29,6 +29,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 quality: 92,
                 max_dimension: Some(2048),
                 force_raw_processing: false,
+                ..soma_media::PreviewOptions::default()
             }
         );
         
@@ -56,6 +57,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 quality: 92,
                 max_dimension: Some(2048),
                 force_raw_processing: true,
+                ..soma_media::PreviewOptions::default()
             }
         );
         
@@ -12,6 +12,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         quality: 92,
         max_dimension: Some(1024),
         force_raw_processing: true,
+        ..PreviewOptions::default()
     };
     
     // Test 1: No exposure adjustment (baseline)
@@ -7,6 +7,7 @@ fn main() {
         height: 1920,
         format: ImageOutputFormat::Jpeg,
         quality: 85,
+        ..ImageConfig::default()
     };
     
     let processor = ImagePreprocessor::new(config);
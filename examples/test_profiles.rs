@@ -8,6 +8,7 @@ fn main() {
         height: 683,
         format: ImageOutputFormat::Webp,
         quality: 85,
+        ..ImageConfig::default()
     };
     
     let processor = ImagePreprocessor::new(config);
@@ -72,6 +72,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         quality: 92,
         max_dimension: Some(2048),
         force_raw_processing: true,
+        ..PreviewOptions::default()
     };
     
     let start = std::time::Instant::now();
@@ -99,6 +100,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         quality: 95,
         max_dimension: Some(2048),
         force_raw_processing: false,
+        ..PreviewOptions::default()
     };
     
     match processor.extract_preview_webp(file_path, &options_high) {
@@ -7,6 +7,7 @@ fn main() {
         height: 600,
         format: ImageOutputFormat::Webp,
         quality: 85,
+        ..ImageConfig::default()
     };
     
     let processor = ImagePreprocessor::new(config);
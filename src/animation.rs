@@ -0,0 +1,481 @@
+//! Animated image input (GIF, APNG, animated WebP)
+//!
+//! Animated stills are treated like short videos: [`probe_animation`] reads
+//! frame count, duration and loop count straight from the container without
+//! decoding, [`decode_frames`] returns fully composited RGBA frames with their
+//! delays. Callers then keep the animation ([`encode_animation`], WebP or GIF
+//! output), pick one frame ([`representative_frame`]) or sample several
+//! ([`sample_frames`]) for tensors.
+
+use crate::ffmpeg::FfmpegError;
+use image::{AnimationDecoder, Frame, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+/// Container of an animated image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimatedFormat {
+    Gif,
+    Apng,
+    Webp,
+}
+
+/// Timing of an animated image, read from its container
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnimationInfo {
+    pub format: AnimatedFormat,
+    pub frame_count: u32,
+    /// Sum of the frame delays in seconds (one play)
+    pub duration: f64,
+    /// Number of plays, 0 = loops forever
+    pub loop_count: u32,
+}
+
+impl AnimationInfo {
+    pub fn is_animated(&self) -> bool {
+        self.frame_count > 1
+    }
+}
+
+/// What to do with an animated input when writing a still-image output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnimationMode {
+    /// First frame, as a still (default)
+    #[default]
+    First,
+    /// The frame whose colours are closest to the whole animation's
+    Representative,
+    /// Re-encode every frame (WebP and GIF outputs only)
+    Keep,
+}
+
+impl AnimationMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "first" => Some(Self::First),
+            "representative" => Some(Self::Representative),
+            "keep" | "animate" => Some(Self::Keep),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::First => "first",
+            Self::Representative => "representative",
+            Self::Keep => "keep",
+        }
+    }
+}
+
+/// How an animated input ended up in a derivative
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct AnimationOutput {
+    /// Frames in the source animation
+    pub frame_count: usize,
+    /// Source frame written as a still; None when the animation was kept
+    pub frame_index: Option<usize>,
+}
+
+/// One decoded frame: the full canvas and how long it is shown
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    pub image: RgbaImage,
+    pub delay_ms: f64,
+}
+
+/// Frame count, duration and loop count of a GIF, APNG or animated WebP
+///
+/// Returns None for other files, PNGs without an animation control chunk and
+/// still WebPs. GIFs always report (a single-frame GIF has `frame_count` 1).
+pub fn probe_animation(path: impl AsRef<Path>) -> Option<AnimationInfo> {
+    // Only read the whole file for the three containers
+    let mut magic = [0u8; 12];
+    std::fs::File::open(path.as_ref()).ok()?.read_exact(&mut magic).ok()?;
+    container(&magic)?;
+    probe_bytes(&std::fs::read(path).ok()?)
+}
+
+/// True for GIF/APNG/WebP files with more than one frame
+pub fn is_animated(path: impl AsRef<Path>) -> bool {
+    probe_animation(path).is_some_and(|info| info.is_animated())
+}
+
+/// Container sniffed from the first 12 bytes (PNG may still turn out to be a still)
+fn container(magic: &[u8]) -> Option<AnimatedFormat> {
+    if magic.starts_with(b"GIF87a") || magic.starts_with(b"GIF89a") {
+        Some(AnimatedFormat::Gif)
+    } else if magic.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(AnimatedFormat::Apng)
+    } else if magic.len() >= 12 && &magic[..4] == b"RIFF" && &magic[8..12] == b"WEBP" {
+        Some(AnimatedFormat::Webp)
+    } else {
+        None
+    }
+}
+
+/// [`probe_animation`] for a file already in memory
+pub(crate) fn probe_bytes(data: &[u8]) -> Option<AnimationInfo> {
+    match container(data)? {
+        AnimatedFormat::Gif => probe_gif(data),
+        AnimatedFormat::Apng => probe_apng(data),
+        AnimatedFormat::Webp => probe_webp(data),
+    }
+}
+
+/// Walk GIF blocks: image descriptors count frames, graphic control
+/// extensions carry delays, the NETSCAPE2.0 extension the loop count
+fn probe_gif(data: &[u8]) -> Option<AnimationInfo> {
+    let mut pos = 13;
+    let flags = *data.get(10)?;
+    if flags & 0x80 != 0 {
+        pos += 3 << ((flags & 0x07) + 1);
+    }
+    
+    let (mut frames, mut centiseconds, mut delay) = (0u32, 0u64, 0u16);
+    let mut repeat: Option<u16> = None;
+    while let Some(&block) = data.get(pos) {
+        match block {
+            0x21 => {
+                let label = *data.get(pos + 1)?;
+                let body = pos + 2;
+                if label == 0xF9 && data.get(body) == Some(&4) {
+                    delay = u16::from_le_bytes([*data.get(body + 2)?, *data.get(body + 3)?]);
+                } else if label == 0xFF && data.get(body + 1..body + 12) == Some(b"NETSCAPE2.0") {
+                    let sub = body + 12;
+                    if data.get(sub) == Some(&3) && data.get(sub + 1) == Some(&1) {
+                        repeat = Some(u16::from_le_bytes([*data.get(sub + 2)?, *data.get(sub + 3)?]));
+                    }
+                }
+                pos = skip_sub_blocks(data, body)?;
+            }
+            0x2C => {
+                let packed = *data.get(pos + 9)?;
+                pos += 10;
+                if packed & 0x80 != 0 {
+                    pos += 3 << ((packed & 0x07) + 1);
+                }
+                // LZW minimum code size, then the image data sub-blocks
+                pos = skip_sub_blocks(data, pos + 1)?;
+                frames += 1;
+                centiseconds += delay as u64;
+                delay = 0;
+            }
+            _ => break,  // 0x3B trailer (or garbage)
+        }
+    }
+    
+    Some(AnimationInfo {
+        format: AnimatedFormat::Gif,
+        frame_count: frames,
+        duration: centiseconds as f64 / 100.0,
+        // NETSCAPE counts repeats after the first play; no extension plays once
+        loop_count: match repeat {
+            None => 1,
+            Some(0) => 0,
+            Some(n) => n as u32 + 1,
+        },
+    })
+}
+
+/// Position after a chain of GIF sub-blocks starting at `pos`
+fn skip_sub_blocks(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let size = *data.get(pos)? as usize;
+        pos += 1 + size;
+        if size == 0 {
+            return Some(pos);
+        }
+    }
+}
+
+/// `acTL` gives frame and play counts, each `fcTL` a frame delay
+fn probe_apng(data: &[u8]) -> Option<AnimationInfo> {
+    let mut pos = 8;
+    let mut control: Option<(u32, u32)> = None;
+    let mut seconds = 0.0;
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes(data[pos..pos + 4].try_into().ok()?) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let body = data.get(pos + 8..pos + 8 + length)?;
+        match kind {
+            b"acTL" if length >= 8 => {
+                control = Some((
+                    u32::from_be_bytes(body[0..4].try_into().ok()?),
+                    u32::from_be_bytes(body[4..8].try_into().ok()?),
+                ));
+            }
+            b"fcTL" if length >= 26 => {
+                let numerator = u16::from_be_bytes([body[20], body[21]]) as f64;
+                let denominator = match u16::from_be_bytes([body[22], body[23]]) {
+                    0 => 100.0,
+                    d => d as f64,
+                };
+                seconds += numerator / denominator;
+            }
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + length;  // length, type, data, CRC
+    }
+    
+    let (frame_count, loop_count) = control?;
+    Some(AnimationInfo { format: AnimatedFormat::Apng, frame_count, duration: seconds, loop_count })
+}
+
+/// `ANIM` gives the loop count, each `ANMF` one frame and its duration
+fn probe_webp(data: &[u8]) -> Option<AnimationInfo> {
+    let mut pos = 12;
+    let mut loop_count: Option<u32> = None;
+    let (mut frames, mut milliseconds) = (0u32, 0u64);
+    while pos + 8 <= data.len() {
+        let kind = &data[pos..pos + 4];
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().ok()?) as usize;
+        let body = &data[pos + 8..(pos + 8 + size).min(data.len())];
+        match kind {
+            b"ANIM" if body.len() >= 6 => loop_count = Some(u16::from_le_bytes([body[4], body[5]]) as u32),
+            b"ANMF" if body.len() >= 16 => {
+                frames += 1;
+                milliseconds += u32::from_le_bytes([body[12], body[13], body[14], 0]) as u64;
+            }
+            _ => {}
+        }
+        pos += 8 + size + (size & 1);  // Chunks are padded to even sizes
+    }
+    
+    Some(AnimationInfo {
+        format: AnimatedFormat::Webp,
+        frame_count: frames,
+        duration: milliseconds as f64 / 1000.0,
+        loop_count: loop_count?,
+    })
+}
+
+/// Decode every frame of a GIF, APNG or animated WebP, composited to full canvases
+pub fn decode_frames(path: impl AsRef<Path>) -> Result<Vec<AnimationFrame>, FfmpegError> {
+    let path = path.as_ref();
+    let data = std::fs::read(path)?;
+    let info = probe_bytes(&data)
+        .ok_or_else(|| FfmpegError::InvalidOutput(format!("{} is not an animated image", path.display())))?;
+    let map_err = |e: image::ImageError| FfmpegError::ExecutionFailed(format!("Failed to decode {}: {}", path.display(), e));
+    
+    let reader = Cursor::new(&data);
+    let frames = match info.format {
+        AnimatedFormat::Gif => image::codecs::gif::GifDecoder::new(reader).map_err(map_err)?.into_frames().collect_frames(),
+        AnimatedFormat::Apng => image::codecs::png::PngDecoder::new(reader)
+            .map_err(map_err)?
+            .apng()
+            .map_err(map_err)?
+            .into_frames()
+            .collect_frames(),
+        AnimatedFormat::Webp => image::codecs::webp::WebPDecoder::new(reader).map_err(map_err)?.into_frames().collect_frames(),
+    }
+    .map_err(map_err)?;
+    
+    Ok(frames.into_iter().map(AnimationFrame::from).collect())
+}
+
+impl From<Frame> for AnimationFrame {
+    fn from(frame: Frame) -> Self {
+        let (numerator, denominator) = frame.delay().numer_denom_ms();
+        Self {
+            delay_ms: numerator as f64 / denominator.max(1) as f64,
+            image: frame.into_buffer(),
+        }
+    }
+}
+
+/// Index of the frame whose colour histogram is closest to the mean of all frames
+///
+/// Avoids the blank or title frames animations often start with.
+pub fn representative_frame(frames: &[AnimationFrame]) -> usize {
+    if frames.len() < 3 {
+        return 0;
+    }
+    let histograms: Vec<Vec<f64>> = frames
+        .iter()
+        .map(|frame| crate::video::rgb_histogram(&image::DynamicImage::ImageRgba8(frame.image.clone())))
+        .collect();
+    let mut mean = vec![0.0; histograms[0].len()];
+    for histogram in &histograms {
+        for (m, h) in mean.iter_mut().zip(histogram) {
+            *m += h / histograms.len() as f64;
+        }
+    }
+    
+    histograms
+        .iter()
+        .map(|h| crate::video::histogram_distance(h, &mean))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(index, _)| index)
+}
+
+/// Indices of `count` frames spread evenly over the animation's running time
+///
+/// Each pick is the frame on screen at the middle of one of `count` equal
+/// time slices, so frames repeat when `count` exceeds the frame count.
+/// Animations without timing are sampled by index.
+pub fn sample_frames(frames: &[AnimationFrame], count: usize) -> Vec<usize> {
+    if frames.is_empty() || count == 0 {
+        return Vec::new();
+    }
+    let total: f64 = frames.iter().map(|f| f.delay_ms).sum();
+    
+    (0..count)
+        .map(|i| {
+            let position = (i as f64 + 0.5) / count as f64;
+            if total <= 0.0 {
+                return ((position * frames.len() as f64) as usize).min(frames.len() - 1);
+            }
+            let target = position * total;
+            let mut elapsed = 0.0;
+            for (index, frame) in frames.iter().enumerate() {
+                elapsed += frame.delay_ms;
+                if target < elapsed {
+                    return index;
+                }
+            }
+            frames.len() - 1
+        })
+        .collect()
+}
+
+/// Write frames (all the same size) as an animated WebP or GIF
+///
+/// `loop_count` is the number of plays, 0 loops forever. `format` is "webp"
+/// or "gif"; quality applies to WebP (GIF is palette-quantised per frame).
+pub fn encode_animation(frames: &[AnimationFrame], format: &str, quality: u8, loop_count: u32, output: &Path) -> Result<(), FfmpegError> {
+    let first = frames
+        .first()
+        .ok_or_else(|| FfmpegError::InvalidOutput("No frames to encode".to_string()))?;
+    let (width, height) = first.image.dimensions();
+    
+    match format {
+        "webp" => {
+            let mut config = webp::WebPConfig::new()
+                .map_err(|_| FfmpegError::ExecutionFailed("Failed to initialise libwebp".to_string()))?;
+            config.quality = quality as f32;
+            let mut encoder = webp::AnimEncoder::new(width, height, &config);
+            encoder.set_loop_count(loop_count.min(i32::MAX as u32) as i32);
+            let mut timestamp = 0.0f64;
+            for frame in frames {
+                encoder.add_frame(webp::AnimFrame::from_rgba(frame.image.as_raw(), width, height, timestamp.round() as i32));
+                timestamp += frame.delay_ms;
+            }
+            let webp = encoder
+                .try_encode()
+                .map_err(|e| FfmpegError::ExecutionFailed(format!("Animated WebP encoding failed: {:?}", e)))?;
+            std::fs::write(output, &*webp)?;
+        }
+        "gif" => {
+            use image::codecs::gif::{GifEncoder, Repeat};
+            
+            let map_err = |e: image::ImageError| FfmpegError::ExecutionFailed(format!("GIF encoding failed: {}", e));
+            let file = std::io::BufWriter::new(std::fs::File::create(output)?);
+            // Speed 10: NeuQuant sampling that stays fast on large frames
+            let mut encoder = GifEncoder::new_with_speed(file, 10);
+            match loop_count {
+                0 => encoder.set_repeat(Repeat::Infinite).map_err(map_err)?,
+                1 => {}  // No NETSCAPE extension: play once
+                n => encoder.set_repeat(Repeat::Finite((n - 1).min(u16::MAX as u32) as u16)).map_err(map_err)?,
+            }
+            encoder
+                .encode_frames(frames.iter().map(|frame| {
+                    let delay = image::Delay::from_numer_denom_ms(frame.delay_ms.round() as u32, 1);
+                    Frame::from_parts(frame.image.clone(), 0, 0, delay)
+                }))
+                .map_err(map_err)?;
+        }
+        other => {
+            return Err(FfmpegError::InvalidOutput(format!("Animation can only be kept in webp or gif output, not {}", other)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    fn frames(delays: &[f64]) -> Vec<AnimationFrame> {
+        delays
+            .iter()
+            .enumerate()
+            .map(|(i, &delay_ms)| AnimationFrame {
+                image: RgbaImage::from_pixel(8, 8, image::Rgba([(i * 60) as u8, 40, 200, 255])),
+                delay_ms,
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_gif_round_trip_probe() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let path = dir.join("anim.gif");
+        
+        let source = frames(&[100.0, 200.0, 300.0]);
+        encode_animation(&source, "gif", 90, 0, &path).unwrap();
+        let info = probe_animation(&path).unwrap();
+        assert_eq!(info.format, AnimatedFormat::Gif);
+        assert_eq!(info.frame_count, 3);
+        assert!((info.duration - 0.6).abs() < 1e-9);
+        assert_eq!(info.loop_count, 0);
+        assert!(is_animated(&path));
+        
+        let decoded = decode_frames(&path).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[2].delay_ms, 300.0);
+        
+        // Played three times = NETSCAPE repeat count 2
+        encode_animation(&source, "gif", 90, 3, &path).unwrap();
+        assert_eq!(probe_animation(&path).unwrap().loop_count, 3);
+        encode_animation(&source, "gif", 90, 1, &path).unwrap();
+        assert_eq!(probe_animation(&path).unwrap().loop_count, 1);
+    }
+    
+    #[test]
+    fn test_webp_probe() {
+        // VP8X with the animation flag, ANIM looping twice, two ANMF frames of 40 and 60 ms
+        let mut chunks = Vec::new();
+        let mut chunk = |kind: &[u8], body: Vec<u8>| {
+            chunks.extend_from_slice(kind);
+            chunks.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            chunks.extend_from_slice(&body);
+        };
+        chunk(b"VP8X", vec![0x02, 0, 0, 0, 7, 0, 0, 7, 0, 0]);
+        chunk(b"ANIM", vec![0, 0, 0, 0, 2, 0]);
+        for duration in [40u8, 60] {
+            chunk(b"ANMF", vec![0, 0, 0, 0, 0, 0, 7, 0, 0, 7, 0, 0, duration, 0, 0, 0]);
+        }
+        let mut data = b"RIFF".to_vec();
+        data.extend_from_slice(&(chunks.len() as u32 + 4).to_le_bytes());
+        data.extend_from_slice(b"WEBP");
+        data.extend_from_slice(&chunks);
+        
+        let info = probe_bytes(&data).unwrap();
+        assert_eq!((info.format, info.frame_count, info.loop_count), (AnimatedFormat::Webp, 2, 2));
+        assert!((info.duration - 0.1).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_frame_selection() {
+        // Time-based sampling follows the long middle frame
+        let timed = frames(&[10.0, 80.0, 10.0]);
+        assert_eq!(sample_frames(&timed, 4), [1, 1, 1, 1]);
+        assert_eq!(sample_frames(&frames(&[0.0; 4]), 2), [1, 3]);
+        assert_eq!(sample_frames(&timed, 0), Vec::<usize>::new());
+        
+        // One odd frame among similar ones is never the representative
+        let mut similar = frames(&[50.0; 5]);
+        for frame in similar.iter_mut().skip(1) {
+            frame.image = RgbaImage::from_pixel(8, 8, image::Rgba([120, 40, 200, 255]));
+        }
+        similar[0].image = RgbaImage::from_pixel(8, 8, image::Rgba([0, 0, 0, 255]));
+        assert_ne!(representative_frame(&similar), 0);
+    }
+}
@@ -1,6 +1,7 @@
 //! Audio preprocessing via FFmpeg and Rust DSP
 
 use crate::ffmpeg::{FfmpegCommand, FfmpegError};
+use std::io::{Read, Write};
 use std::path::Path;
 use rustfft::FftPlanner;
 use ndarray::Array2;
@@ -62,6 +63,31 @@ impl AudioPreprocessor {
         Ok(())
     }
     
+    /// Preprocess audio (or a video's audio track) streamed through FFmpeg's
+    /// stdin/stdout, without touching the filesystem
+    ///
+    /// WAV written to a pipe has no final chunk sizes; most readers treat it
+    /// as running to the end of the data.
+    pub fn preprocess_stream(&self, input: impl Read + Send, output: impl Write) -> Result<(), FfmpegError> {
+        FfmpegCommand::new()
+            .input("-")
+            .args(&[
+                "-vn",
+                "-ar", &self.config.sample_rate.to_string(),
+                "-ac", &self.config.channels.to_string(),
+                "-f", self.config.format.as_str(),
+            ])
+            .output("-")
+            .execute_piped(input, output)
+    }
+    
+    /// [`Self::preprocess_stream`] from and to memory
+    pub fn preprocess_from_memory(&self, input: &[u8]) -> Result<Vec<u8>, FfmpegError> {
+        let mut output = Vec::new();
+        self.preprocess_stream(input, &mut output)?;
+        Ok(output)
+    }
+    
     /// Extract audio from video file
     pub fn extract_from_video(&self, video: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<(), FfmpegError> {
         FfmpegCommand::new()
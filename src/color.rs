@@ -0,0 +1,280 @@
+//! ICC colour management
+//!
+//! Embedded ICC profiles are read from inputs and pixels are converted to a
+//! target space with moxcms (pure-Rust CMM). Outputs carry the target
+//! profile so wide-gamut images aren't shown desaturated as sRGB.
+
+use crate::ffmpeg::FfmpegError;
+use crate::raw::{ColorSpace, RawOptions};
+use image::{DynamicImage, ImageBuffer, ImageDecoder, ImageReader};
+use std::path::Path;
+use moxcms::{ColorProfile, DataColorSpace, Layout, ToneReprCurve, TransformOptions};
+
+/// Colour space images are converted to (and tagged with) on output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetColorSpace {
+    /// sRGB (web, most ML models)
+    Srgb,
+    /// Display P3 (Apple displays, iPhone photos)
+    DisplayP3,
+    /// Adobe RGB (1998) (print workflows)
+    AdobeRgb,
+}
+
+impl TargetColorSpace {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace(['-', ' '], "_").as_str() {
+            "srgb" => Some(TargetColorSpace::Srgb),
+            "display_p3" | "p3" => Some(TargetColorSpace::DisplayP3),
+            "adobe_rgb" | "adobergb" => Some(TargetColorSpace::AdobeRgb),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &str {
+        match self {
+            TargetColorSpace::Srgb => "srgb",
+            TargetColorSpace::DisplayP3 => "display_p3",
+            TargetColorSpace::AdobeRgb => "adobe_rgb",
+        }
+    }
+    
+    fn profile(&self) -> ColorProfile {
+        match self {
+            TargetColorSpace::Srgb => ColorProfile::new_srgb(),
+            TargetColorSpace::DisplayP3 => ColorProfile::new_display_p3(),
+            TargetColorSpace::AdobeRgb => ColorProfile::new_adobe_rgb(),
+        }
+    }
+    
+    /// ICC profile bytes to embed in outputs
+    pub fn icc_profile(&self) -> Vec<u8> {
+        self.profile().encode().unwrap_or_default()
+    }
+}
+
+impl RawOptions {
+    /// ICC profile matching LibRaw's output: the primaries of `color_space` with
+    /// the gamma curve actually applied (None for Raw/XYZ/Wide Gamut)
+    pub fn icc_profile(&self) -> Option<Vec<u8>> {
+        let mut profile = match self.color_space {
+            ColorSpace::SRGB => ColorProfile::new_srgb(),
+            ColorSpace::AdobeRGB => ColorProfile::new_adobe_rgb(),
+            ColorSpace::ProPhotoRGB => ColorProfile::new_pro_photo_rgb(),
+            ColorSpace::Raw | ColorSpace::WideGamutRGB | ColorSpace::XYZ => return None,
+        };
+        let (power, slope) = self.transfer_curve();
+        let trc = libraw_trc(power as f64, slope as f64);
+        profile.red_trc = Some(trc.clone());
+        profile.green_trc = Some(trc.clone());
+        profile.blue_trc = Some(trc);
+        profile.encode().ok()
+    }
+}
+
+/// ICC parametric curve (type 3: g, a, b, c, d) decoding LibRaw's gamma curve
+///
+/// Mirrors LibRaw's `gamma_curve`: a linear toe of `slope` joined to a
+/// `1/power` power segment, with the breakpoint solved by bisection.
+fn libraw_trc(power: f64, slope: f64) -> ToneReprCurve {
+    let g0 = 1.0 / power;
+    let (mut g2, mut g4) = (0.0, 0.0);
+    if slope != 0.0 && (slope - 1.0) * (g0 - 1.0) <= 0.0 {
+        let mut bounds = [0.0, 0.0];
+        bounds[(slope >= 1.0) as usize] = 1.0;
+        for _ in 0..48 {
+            g2 = (bounds[0] + bounds[1]) / 2.0;
+            let above = ((g2 / slope).powf(-g0) - 1.0) / g0 - 1.0 / g2 > -1.0;
+            bounds[above as usize] = g2;
+        }
+        g4 = g2 * (1.0 / g0 - 1.0);
+    }
+    
+    let params = [power, 1.0 / (1.0 + g4), g4 / (1.0 + g4), if slope != 0.0 { 1.0 / slope } else { 0.0 }, g2];
+    ToneReprCurve::Parametric(params.iter().map(|&p| p as f32).collect())
+}
+
+/// Decode an image file along with its embedded ICC profile
+pub(crate) fn open_with_profile(path: impl AsRef<Path>) -> Result<(DynamicImage, Option<Vec<u8>>), FfmpegError> {
+    let map_err = |e: image::ImageError| FfmpegError::InvalidOutput(format!("Failed to read {}: {}", path.as_ref().display(), e));
+    
+    let mut reader = ImageReader::open(path.as_ref())?.with_guessed_format()?;
+    reader.no_limits();
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(map_err)?;
+    let icc = decoder.icc_profile().ok().flatten();
+    let image = DynamicImage::from_decoder(decoder).map_err(map_err)?;
+    Ok((image, icc))
+}
+
+/// Convert `img` from its embedded `source_icc` profile to `target`
+///
+/// Non-RGB source profiles (gray, CMYK) and unparsable profiles leave the
+/// pixels untouched. 16-bit images are converted at 16 bits.
+pub fn convert_to_target(img: DynamicImage, source_icc: &[u8], target: TargetColorSpace) -> Result<DynamicImage, FfmpegError> {
+    let source = match ColorProfile::new_from_slice(source_icc) {
+        Ok(profile) if profile.color_space == DataColorSpace::Rgb => profile,
+        Ok(_) => return Ok(img),
+        Err(e) => {
+            tracing::debug!("Ignoring unreadable ICC profile: {:?}", e);
+            return Ok(img);
+        }
+    };
+    
+    // Already tagged with the target profile
+    if source_icc == target.icc_profile().as_slice() {
+        return Ok(img);
+    }
+    
+    let destination = target.profile();
+    let map_err = |e: moxcms::CmsError| FfmpegError::ExecutionFailed(format!("Colour conversion failed: {:?}", e));
+    let options = TransformOptions::default();
+    let (width, height) = (img.width(), img.height());
+    
+    let converted = match img {
+        DynamicImage::ImageRgb16(_) | DynamicImage::ImageRgba16(_) => {
+            let alpha = img.color().has_alpha();
+            let (layout, src) = if alpha {
+                (Layout::Rgba, img.into_rgba16().into_raw())
+            } else {
+                (Layout::Rgb, img.into_rgb16().into_raw())
+            };
+            let transform = source.create_transform_16bit(layout, &destination, layout, options).map_err(map_err)?;
+            let mut dst = vec![0u16; src.len()];
+            transform.transform(&src, &mut dst).map_err(map_err)?;
+            
+            if alpha {
+                ImageBuffer::from_raw(width, height, dst).map(DynamicImage::ImageRgba16)
+            } else {
+                ImageBuffer::from_raw(width, height, dst).map(DynamicImage::ImageRgb16)
+            }
+        }
+        img => {
+            let alpha = img.color().has_alpha();
+            let (layout, src) = if alpha {
+                (Layout::Rgba, img.into_rgba8().into_raw())
+            } else {
+                (Layout::Rgb, img.into_rgb8().into_raw())
+            };
+            let transform = source.create_transform_8bit(layout, &destination, layout, options).map_err(map_err)?;
+            let mut dst = vec![0u8; src.len()];
+            transform.transform(&src, &mut dst).map_err(map_err)?;
+            
+            if alpha {
+                ImageBuffer::from_raw(width, height, dst).map(DynamicImage::ImageRgba8)
+            } else {
+                ImageBuffer::from_raw(width, height, dst).map(DynamicImage::ImageRgb8)
+            }
+        }
+    };
+    
+    converted.ok_or_else(|| FfmpegError::ExecutionFailed("Colour conversion changed buffer size".to_string()))
+}
+
+/// Insert an `ICCP` chunk into a WebP file (libwebp's simple encoder can't)
+///
+/// Simple-format files (`VP8 `/`VP8L` only) are promoted to the extended
+/// format with a `VP8X` header; `width`/`height` are the canvas size.
+pub(crate) fn embed_webp_icc(webp: &[u8], icc: &[u8], width: u32, height: u32, alpha: bool) -> Vec<u8> {
+    if webp.len() < 20 || &webp[0..4] != b"RIFF" || &webp[8..12] != b"WEBP" {
+        return webp.to_vec();
+    }
+    
+    const ICC_FLAG: u8 = 0x20;
+    const ALPHA_FLAG: u8 = 0x10;
+    let chunks = &webp[12..];
+    
+    let mut body = Vec::with_capacity(chunks.len() + icc.len() + 32);
+    let rest = if &chunks[0..4] == b"VP8X" {
+        // Extended already: set the ICC flag and put ICCP right after VP8X
+        let vp8x_len = 8 + 10;
+        let mut vp8x = chunks[..vp8x_len.min(chunks.len())].to_vec();
+        vp8x[8] |= ICC_FLAG;
+        body.extend_from_slice(&vp8x);
+        &chunks[vp8x_len.min(chunks.len())..]
+    } else {
+        let mut flags = ICC_FLAG;
+        if alpha {
+            flags |= ALPHA_FLAG;
+        }
+        body.extend_from_slice(b"VP8X");
+        body.extend_from_slice(&10u32.to_le_bytes());
+        body.extend_from_slice(&[flags, 0, 0, 0]);
+        body.extend_from_slice(&(width.saturating_sub(1)).to_le_bytes()[..3]);
+        body.extend_from_slice(&(height.saturating_sub(1)).to_le_bytes()[..3]);
+        chunks
+    };
+    
+    body.extend_from_slice(b"ICCP");
+    body.extend_from_slice(&(icc.len() as u32).to_le_bytes());
+    body.extend_from_slice(icc);
+    if icc.len() % 2 == 1 {
+        body.push(0);  // Chunks are padded to even length
+    }
+    body.extend_from_slice(rest);
+    
+    let mut out = Vec::with_capacity(12 + body.len());
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&((4 + body.len()) as u32).to_le_bytes());
+    out.extend_from_slice(b"WEBP");
+    out.extend_from_slice(&body);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    
+    #[test]
+    fn test_srgb_to_display_p3() {
+        let red = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([255, 0, 0])));
+        let srgb = TargetColorSpace::Srgb.icc_profile();
+        
+        // Same profile is a no-op
+        let same = convert_to_target(red.clone(), &srgb, TargetColorSpace::Srgb).unwrap();
+        assert_eq!(same.to_rgb8().get_pixel(0, 0), &Rgb([255, 0, 0]));
+        
+        // sRGB red sits inside the wider P3 gamut (≈ 234, 51, 35)
+        let p3 = convert_to_target(red, &srgb, TargetColorSpace::DisplayP3).unwrap();
+        let pixel = p3.to_rgb8().get_pixel(0, 0).0;
+        assert!(pixel[0] < 245 && pixel[0] > 220, "{:?}", pixel);
+        assert!(pixel[1] > 30 && pixel[1] < 70, "{:?}", pixel);
+    }
+    
+    #[test]
+    fn test_libraw_trc_matches_profile() {
+        // LibRaw's (2.4, 12.92) curve is the sRGB transfer function (its
+        // breakpoint is solved for continuity, so sits just below 0.04045)
+        let ToneReprCurve::Parametric(srgb) = libraw_trc(2.4, 12.92) else { unreachable!() };
+        for (got, want) in srgb.iter().zip([2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045]) {
+            assert!((got - want).abs() < 2e-3, "{:?}", srgb);
+        }
+        
+        // Slope 0 is a pure power curve
+        let ToneReprCurve::Parametric(pro_photo) = libraw_trc(1.8, 0.0) else { unreachable!() };
+        assert_eq!(pro_photo, vec![1.8, 1.0, 0.0, 0.0, 0.0]);
+        
+        // Tagged with the curve LibRaw renders, not the stock profile
+        let options = RawOptions::maximum();
+        let profile = ColorProfile::new_from_slice(&options.icc_profile().unwrap()).unwrap();
+        let Some(ToneReprCurve::Parametric(trc)) = profile.red_trc else { panic!("{:?}", profile.red_trc) };
+        assert!((trc[0] - 1.8).abs() < 1e-4 && trc[1..] == [1.0, 0.0, 0.0, 0.0], "{:?}", trc);
+        
+        let custom = RawOptions { gamma: Some((1.0 / 0.45, 4.5)), ..RawOptions::default() };
+        assert_ne!(custom.icc_profile(), RawOptions::default().icc_profile());
+    }
+    
+    #[test]
+    fn test_embed_webp_icc() {
+        let pixels = vec![128u8; 8 * 6 * 3];
+        let webp = webp::Encoder::from_rgb(&pixels, 8, 6).encode(80.0);
+        let icc = TargetColorSpace::DisplayP3.icc_profile();
+        
+        let tagged = embed_webp_icc(&webp, &icc, 8, 6, false);
+        let mut decoder = image::codecs::webp::WebPDecoder::new(std::io::Cursor::new(&tagged)).unwrap();
+        assert_eq!(decoder.dimensions(), (8, 6));
+        assert_eq!(decoder.icc_profile().unwrap(), Some(icc));
+    }
+}
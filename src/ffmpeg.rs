@@ -1,7 +1,10 @@
 //! FFmpeg command wrapper utilities
 
-use std::process::{Command, Output};
-use std::path::Path;
+use std::io::{Read, Write};
+use std::process::{Child, ChildStdin, Command, Output, Stdio};
+use std::thread::JoinHandle;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -62,6 +65,136 @@ impl FfmpegCommand {
         
         Ok(output)
     }
+    
+    /// Spawn FFmpeg with a piped stdin (for `-i -` inputs such as raw frames)
+    pub fn spawn_with_stdin(self) -> Result<FfmpegProcess, FfmpegError> {
+        if !is_ffmpeg_installed() {
+            return Err(FfmpegError::NotInstalled);
+        }
+        
+        let mut child = Command::new("ffmpeg")
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| FfmpegError::ExecutionFailed(e.to_string()))?;
+        
+        // Drain stderr on a thread so a chatty FFmpeg cannot block on a full pipe
+        let mut stderr = child.stderr.take();
+        let stderr_reader = std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(stderr) = stderr.as_mut() {
+                let _ = stderr.read_to_end(&mut buf);
+            }
+            buf
+        });
+        
+        let stdin = child.stdin.take();
+        Ok(FfmpegProcess { child, stdin, stderr_reader })
+    }
+    
+    /// Run FFmpeg reading `input` from stdin (`.input("-")`) and copying its
+    /// stdout (`.output("-")` with an explicit `-f`) into `output`
+    ///
+    /// Nothing is staged on disk. Inputs that need seeking (e.g. MP4 with the
+    /// `moov` atom at the end) can't be read from a pipe.
+    pub fn execute_piped(self, input: impl Read + Send, output: impl Write) -> Result<(), FfmpegError> {
+        if !is_ffmpeg_installed() {
+            return Err(FfmpegError::NotInstalled);
+        }
+        
+        let mut command = Command::new("ffmpeg");
+        command.args(&self.args);
+        pipe_command(command, input, output)
+    }
+    
+    /// [`Self::execute_piped`] from and to memory
+    pub fn execute_with_input(self, input: &[u8]) -> Result<Vec<u8>, FfmpegError> {
+        let mut output = Vec::new();
+        self.execute_piped(input, &mut output)?;
+        Ok(output)
+    }
+}
+
+/// Run `command` with `input` fed to stdin and stdout copied to `output`
+///
+/// stdin is fed and stderr drained on their own threads, so neither side
+/// can block on a full pipe. A tool that stops reading early (e.g. after
+/// the first frame) is not an error.
+pub(crate) fn pipe_command(mut command: Command, mut input: impl Read + Send, mut output: impl Write) -> Result<(), FfmpegError> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| FfmpegError::ExecutionFailed(e.to_string()))?;
+    let (Some(mut stdin), Some(mut stdout), Some(mut stderr)) = (child.stdin.take(), child.stdout.take(), child.stderr.take()) else {
+        return Err(FfmpegError::ExecutionFailed("Failed to open pipes".into()));
+    };
+    
+    let (copied, status, fed, stderr) = std::thread::scope(|scope| {
+        let feeder = scope.spawn(move || std::io::copy(&mut input, &mut stdin));
+        let errors = scope.spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        });
+        
+        let copied = std::io::copy(&mut stdout, &mut output);
+        if copied.is_err() {
+            // Don't leave the child blocked on a stdout nobody reads
+            let _ = child.kill();
+        }
+        drop(stdout);
+        let status = child.wait();
+        (copied, status, feeder.join(), errors.join().unwrap_or_default())
+    });
+    
+    if !status?.success() {
+        return Err(FfmpegError::ExecutionFailed(String::from_utf8_lossy(&stderr).to_string()));
+    }
+    copied?;
+    match fed {
+        Ok(Err(e)) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(FfmpegError::Io(e)),
+        Ok(_) => Ok(()),
+        Err(_) => Err(FfmpegError::ExecutionFailed("stdin writer panicked".into())),
+    }
+}
+
+/// Running FFmpeg process fed through stdin
+pub struct FfmpegProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stderr_reader: JoinHandle<Vec<u8>>,
+}
+
+impl FfmpegProcess {
+    /// Write bytes to FFmpeg's stdin
+    pub fn write(&mut self, data: &[u8]) -> Result<(), FfmpegError> {
+        match self.stdin.as_mut() {
+            Some(stdin) => stdin.write_all(data).map_err(FfmpegError::Io),
+            None => Err(FfmpegError::ExecutionFailed("FFmpeg stdin already closed".into())),
+        }
+    }
+    
+    /// Close stdin and wait for FFmpeg to exit
+    pub fn finish(mut self) -> Result<Output, FfmpegError> {
+        drop(self.stdin.take());
+        
+        let mut stdout = Vec::new();
+        if let Some(out) = self.child.stdout.as_mut() {
+            out.read_to_end(&mut stdout)?;
+        }
+        let status = self.child.wait()?;
+        let stderr = self.stderr_reader.join().unwrap_or_default();
+        
+        if !status.success() {
+            return Err(FfmpegError::ExecutionFailed(String::from_utf8_lossy(&stderr).to_string()));
+        }
+        
+        Ok(Output { status, stdout, stderr })
+    }
 }
 
 impl Default for FfmpegCommand {
@@ -77,6 +210,40 @@ fn is_ffmpeg_installed() -> bool {
         .is_ok()
 }
 
+/// Check whether the installed FFmpeg build provides a filter
+/// (e.g. `zscale` is only present when built with libzimg)
+pub fn has_filter(name: &str) -> bool {
+    let output = match Command::new("ffmpeg").args(["-hide_banner", "-filters"]).output() {
+        Ok(output) => output,
+        Err(_) => return false,
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .any(|line| line.split_whitespace().nth(1) == Some(name))
+}
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Fresh path in the temp dir for an intermediate file handed to an
+/// external tool: `soma_media_<kind>_<pid>_<n>.<extension>`
+///
+/// The counter keeps paths distinct across threads and calls. An empty
+/// `extension` gives a bare name, for scratch directories.
+pub(crate) fn temp_path(kind: &str, extension: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "soma_media_{}_{}_{}",
+        kind,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    if extension.is_empty() {
+        path
+    } else {
+        path.with_extension(extension)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,10 +258,32 @@ mod tests {
         assert!(is_ffmpeg_installed());
     }
     
+    #[test]
+    fn test_pipe_command_round_trip() {
+        // Larger than a pipe buffer both ways, so a serial write-then-read would block
+        let input: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+        let mut output = Vec::new();
+        pipe_command(Command::new("cat"), &input[..], &mut output).unwrap();
+        assert_eq!(output, input);
+        
+        // Early exit without reading stdin is fine; a failing tool is not
+        pipe_command(Command::new("true"), &input[..], Vec::new()).unwrap();
+        assert!(pipe_command(Command::new("false"), &input[..], Vec::new()).is_err());
+    }
+    
     #[test]
     fn test_ffmpeg_detection() {
         // This test always passes - just checks the detection logic
         let installed = is_ffmpeg_installed();
         println!("FFmpeg installed: {}", installed);
     }
+    
+    #[test]
+    fn test_temp_path_unique() {
+        let a = temp_path("test", "png");
+        let b = temp_path("test", "png");
+        assert_ne!(a, b);
+        assert_eq!(a.extension().unwrap(), "png");
+        assert!(temp_path("test", "").extension().is_none());
+    }
 }
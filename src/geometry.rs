@@ -0,0 +1,150 @@
+//! Frame geometry shared by the image, RAW, video and sequence pipelines
+//!
+//! [`ResizeMode`] describes how a source is fitted to a target size and
+//! [`CropRect`] is a pixel rectangle (detected borders, smart crops). Both
+//! live here so the decoders don't depend on the video or sequence modules.
+
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbImage};
+use serde::{Deserialize, Serialize};
+
+/// How source images are fitted to the output resolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeMode {
+    /// Scale to fit inside the frame, pad with black
+    Fit,
+    /// Scale to cover the frame, crop the overflow
+    Fill,
+    /// Scale to the exact frame size, ignoring aspect ratio
+    Stretch,
+    /// Scale to cover the frame, cropping around the most salient region
+    /// (edges, colour contrast, focus hints) instead of the centre
+    Smart,
+}
+
+impl ResizeMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "fit" | "contain" => Some(ResizeMode::Fit),
+            "fill" | "cover" => Some(ResizeMode::Fill),
+            "stretch" => Some(ResizeMode::Stretch),
+            "smart" | "attention" => Some(ResizeMode::Smart),
+            _ => None,
+        }
+    }
+}
+
+/// Crop rectangle in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CropRect {
+    /// Smallest rectangle containing both rectangles
+    pub fn union(&self, other: &CropRect) -> CropRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        CropRect { x, y, width: right - x, height: bottom - y }
+    }
+    
+    /// FFmpeg `crop` filter for this rectangle
+    pub fn to_filter(&self) -> String {
+        format!("crop={}:{}:{}:{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+/// Active picture area of a decoded frame, trimming letterbox/pillarbox bars
+///
+/// Rows and columns whose mean luma is at most `limit` count as black (the
+/// same rule as FFmpeg's `cropdetect`); the result is rounded to even sizes.
+/// A frame without bars, or one that is black throughout, returns the full
+/// frame.
+pub(crate) fn active_area(image: &RgbImage, limit: u8) -> CropRect {
+    let (width, height) = image.dimensions();
+    let full = CropRect { x: 0, y: 0, width, height };
+    let luma = |x: u32, y: u32| {
+        let [r, g, b] = image.get_pixel(x, y).0;
+        (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000
+    };
+    let black_row = |y: u32| (0..width).map(|x| luma(x, y)).sum::<u32>() <= limit as u32 * width;
+    let black_col = |x: u32, top: u32, bottom: u32| {
+        (top..bottom).map(|y| luma(x, y)).sum::<u32>() <= limit as u32 * (bottom - top)
+    };
+    
+    let Some(top) = (0..height).find(|&y| !black_row(y)) else {
+        return full;
+    };
+    let bottom = (top..height).rev().find(|&y| !black_row(y)).unwrap_or(top) + 1;
+    let left = (0..width).find(|&x| !black_col(x, top, bottom)).unwrap_or(0);
+    let right = (left..width).rev().find(|&x| !black_col(x, top, bottom)).unwrap_or(left) + 1;
+    
+    // Even sizes keep chroma-subsampled encoders happy
+    let crop_w = ((right - left) & !1).max(2).min(width);
+    let crop_h = ((bottom - top) & !1).max(2).min(height);
+    CropRect { x: left.min(width - crop_w), y: top.min(height - crop_h), width: crop_w, height: crop_h }
+}
+
+/// Fit an image to `width`x`height` according to `mode`
+pub(crate) fn fit_image(image: &DynamicImage, width: u32, height: u32, mode: ResizeMode) -> RgbImage {
+    match mode {
+        ResizeMode::Stretch => image.resize_exact(width, height, FilterType::Lanczos3).to_rgb8(),
+        ResizeMode::Fill => image.resize_to_fill(width, height, FilterType::Lanczos3).to_rgb8(),
+        ResizeMode::Smart => crate::smartcrop::smart_crop(image, width, height, &[]).0.to_rgb8(),
+        ResizeMode::Fit => {
+            let scaled = image.resize(width, height, FilterType::Lanczos3).to_rgb8();
+            let mut canvas = RgbImage::new(width, height);
+            let x = (width - scaled.width()) / 2;
+            let y = (height - scaled.height()) / 2;
+            image::imageops::replace(&mut canvas, &scaled, x as i64, y as i64);
+            canvas
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_fit_image_modes() {
+        let wide = DynamicImage::ImageRgb8(RgbImage::from_pixel(400, 100, image::Rgb([200, 200, 200])));
+        
+        let fit = fit_image(&wide, 160, 90, ResizeMode::Fit);
+        assert_eq!(fit.dimensions(), (160, 90));
+        assert_eq!(fit.get_pixel(80, 0).0, [0, 0, 0]);  // Letterbox bar
+        assert_eq!(fit.get_pixel(80, 45).0, [200, 200, 200]);
+        
+        let fill = fit_image(&wide, 160, 90, ResizeMode::Fill);
+        assert_eq!(fill.dimensions(), (160, 90));
+        assert_eq!(fill.get_pixel(80, 0).0, [200, 200, 200]);
+    }
+    
+    #[test]
+    fn test_active_area() {
+        // 200x100 picture letterboxed into 200x140
+        let mut frame = RgbImage::new(200, 140);
+        for y in 20..120 {
+            for x in 0..200 {
+                frame.put_pixel(x, y, image::Rgb([180, 120, 90]));
+            }
+        }
+        assert_eq!(active_area(&frame, 24), CropRect { x: 0, y: 20, width: 200, height: 100 });
+        
+        let black = RgbImage::new(64, 48);
+        assert_eq!(active_area(&black, 24), CropRect { x: 0, y: 0, width: 64, height: 48 });
+    }
+    
+    #[test]
+    fn test_crop_rect_union() {
+        let a = CropRect { x: 10, y: 20, width: 100, height: 50 };
+        let b = CropRect { x: 0, y: 30, width: 50, height: 100 };
+        assert_eq!(a.union(&b), CropRect { x: 0, y: 20, width: 110, height: 110 });
+        assert_eq!(a.to_filter(), "crop=100:50:10:20");
+    }
+}
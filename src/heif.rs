@@ -0,0 +1,242 @@
+//! HEIC/HEIF and AVIF decoding
+//!
+//! Decodes via libheif's `heif-dec` CLI (`heif-convert` on older installs),
+//! shelled out like FFmpeg so nothing is linked. `heif-dec` also writes the
+//! auxiliary images (depth maps, Apple HDR gain maps) next to the primary
+//! image. When it is not installed, FFmpeg decodes the primary image only.
+//!
+//! Orientation: HEIF stores rotation/mirroring in `irot`/`imir` boxes, which
+//! libheif applies while decoding. The FFmpeg fallback decodes unrotated and
+//! applies the EXIF orientation instead (iPhones write both consistently).
+
+use crate::ffmpeg::{temp_path, FfmpegCommand, FfmpegError};
+use crate::image::ImageBackend;
+use image::DynamicImage;
+use image::metadata::Orientation;
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// `ftyp` brands of HEIF-family still images (HEVC and AV1 coded)
+const HEIF_BRANDS: &[&[u8; 4]] = &[
+    b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1", b"msf1", b"avif", b"avis",
+];
+
+/// A decoded HEIF/AVIF file
+#[derive(Debug, Clone)]
+pub struct HeifImage {
+    /// Primary image, oriented when requested
+    pub image: DynamicImage,
+    /// Depth map (portrait mode photos), libheif backend only
+    pub depth: Option<DynamicImage>,
+    /// HDR gain map (iPhone 12+), libheif backend only
+    pub gain_map: Option<DynamicImage>,
+    /// Embedded ICC profile of the primary image (usually Display P3), libheif backend only
+    pub icc_profile: Option<Vec<u8>>,
+    /// Decoder that produced the image
+    pub backend: ImageBackend,
+}
+
+/// Check whether a file is HEIC/HEIF/AVIF by its `ftyp` box
+pub fn is_heif(path: impl AsRef<Path>) -> bool {
+    let mut header = [0u8; 64];
+    match std::fs::File::open(path.as_ref()).and_then(|mut f| f.read(&mut header)) {
+        Ok(read) => is_heif_data(&header[..read]),
+        Err(_) => false,
+    }
+}
+
+/// [`is_heif`] on bytes already in memory (only the `ftyp` box is read)
+pub(crate) fn is_heif_data(data: &[u8]) -> bool {
+    let header = &data[..data.len().min(64)];
+    if header.len() < 16 || &header[4..8] != b"ftyp" {
+        return false;
+    }
+    
+    // Major brand at 8..12, compatible brands from 16 to the end of the box
+    let box_size = (u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize).min(header.len());
+    std::iter::once(&header[8..12])
+        .chain(header[16..box_size.max(16)].chunks_exact(4))
+        .any(|brand| HEIF_BRANDS.iter().any(|known| brand == &known[..]))
+}
+
+/// Name of the installed libheif decoder CLI, if any (probed once)
+fn heif_dec_binary() -> Option<&'static str> {
+    static BINARY: OnceLock<Option<&'static str>> = OnceLock::new();
+    *BINARY.get_or_init(|| {
+        ["heif-dec", "heif-convert"].into_iter().find(|bin| {
+            Command::new(bin)
+                .arg("--version")
+                .output()
+                .map(|o| o.status.success())
+                .unwrap_or(false)
+        })
+    })
+}
+
+/// Check if libheif's decoder CLI is available on the system
+pub fn heif_dec_available() -> bool {
+    heif_dec_binary().is_some()
+}
+
+/// Decode a HEIC/HEIF/AVIF file with its depth and gain map images
+///
+/// `auto_orient` applies the stored rotation/mirroring to the primary image.
+pub fn decode_heif(path: impl AsRef<Path>, auto_orient: bool) -> Result<HeifImage, FfmpegError> {
+    let path = path.as_ref();
+    
+    match heif_dec_binary() {
+        Some(bin) => decode_libheif(bin, path, auto_orient),
+        None => {
+            tracing::debug!("heif-dec not found, decoding {} with FFmpeg", path.display());
+            decode_ffmpeg(path, auto_orient)
+        }
+    }
+}
+
+/// [`decode_heif`] for a file held in memory
+///
+/// Both decoders need a seekable file, so the bytes are staged in a temporary
+/// file for the duration of the decode.
+pub fn decode_heif_from_memory(data: &[u8], auto_orient: bool) -> Result<HeifImage, FfmpegError> {
+    let path = temp_path("heif", "heic");
+    std::fs::write(&path, data)?;
+    let result = decode_heif(&path, auto_orient);
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Decode with `heif-dec`, collecting auxiliary images from its output directory
+fn decode_libheif(bin: &str, path: &Path, auto_orient: bool) -> Result<HeifImage, FfmpegError> {
+    let dir = temp_path("heif", "");
+    std::fs::create_dir_all(&dir)?;
+    
+    let result = (|| {
+        let primary = dir.join("primary.png");
+        let mut cmd = Command::new(bin);
+        cmd.args(["--with-aux", "--no-colons"]);
+        if !auto_orient {
+            cmd.arg("--ignore-transformations");
+        }
+        let output = cmd.arg(path).arg(&primary).output()?;
+        
+        if !output.status.success() {
+            return Err(FfmpegError::ExecutionFailed(format!(
+                "{} failed: {}",
+                bin,
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        
+        let load = |p: &Path| {
+            image::open(p).map_err(|e| FfmpegError::InvalidOutput(format!("Failed to read {}: {}", p.display(), e)))
+        };
+        // heif-dec copies the colour profile into the PNG's iCCP chunk
+        let (image, icc_profile) = crate::color::open_with_profile(&primary)?;
+        let mut decoded = HeifImage {
+            image,
+            depth: None,
+            gain_map: None,
+            icc_profile,
+            backend: ImageBackend::Libheif,
+        };
+        
+        // Auxiliary images are written as primary-<type>.png
+        for entry in std::fs::read_dir(&dir)? {
+            let aux = entry?.path();
+            let name = aux.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_lowercase();
+            if !name.starts_with("primary-") {
+                continue;
+            }
+            if name.contains("depth") {
+                decoded.depth = Some(load(&aux)?);
+            } else if name.contains("gainmap") {
+                decoded.gain_map = Some(load(&aux)?);
+            }
+        }
+        
+        Ok(decoded)
+    })();
+    
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+/// Decode the primary image with FFmpeg and apply the EXIF orientation
+fn decode_ffmpeg(path: &Path, auto_orient: bool) -> Result<HeifImage, FfmpegError> {
+    let output = FfmpegCommand::new()
+        .args(&["-noautorotate"])
+        .input(path)
+        .args(&["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png"])
+        .output("-")
+        .execute()?;
+    
+    let mut image = image::load_from_memory_with_format(&output.stdout, image::ImageFormat::Png)
+        .map_err(|e| FfmpegError::InvalidOutput(format!("Failed to decode {}: {}", path.display(), e)))?;
+    
+    if auto_orient {
+        if let Some(orientation) = exif_orientation(path) {
+            image.apply_orientation(orientation);
+        }
+    }
+    
+    Ok(HeifImage {
+        image,
+        depth: None,
+        gain_map: None,
+        icc_profile: None,
+        backend: ImageBackend::Ffmpeg,
+    })
+}
+
+/// EXIF orientation stored in the HEIF `Exif` item
+fn exif_orientation(path: &Path) -> Option<Orientation> {
+    let file = std::fs::File::open(path).ok()?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut std::io::BufReader::new(file))
+        .ok()?;
+    let value = exif
+        .get_field(exif::Tag::Orientation, exif::In::PRIMARY)?
+        .value
+        .get_uint(0)?;
+    Orientation::from_exif(value as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    fn ftyp(major: &[u8; 4], compatible: &[&[u8; 4]]) -> Vec<u8> {
+        let size = 16 + compatible.len() * 4;
+        let mut bytes = (size as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(b"ftyp");
+        bytes.extend_from_slice(major);
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        for brand in compatible {
+            bytes.extend_from_slice(&brand[..]);
+        }
+        bytes.extend_from_slice(&[0u8; 32]);
+        bytes
+    }
+    
+    #[test]
+    fn test_is_heif_brands() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        
+        let cases: [(&str, Vec<u8>, bool); 4] = [
+            ("iphone.heic", ftyp(b"heic", &[b"mif1", b"heic"]), true),
+            ("photo.avif", ftyp(b"avif", &[b"mif1", b"miaf"]), true),
+            ("generic.heif", ftyp(b"miaf", &[b"mif1"]), true),
+            ("clip.mp4", ftyp(b"isom", &[b"iso2", b"mp41"]), false),
+        ];
+        for (name, bytes, expected) in cases {
+            let path = dir.join(name);
+            std::fs::write(&path, bytes).unwrap();
+            assert_eq!(is_heif(&path), expected, "{}", name);
+            assert_eq!(is_heif_data(&std::fs::read(&path).unwrap()), expected, "{}", name);
+        }
+    }
+}
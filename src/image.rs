@@ -1,15 +1,45 @@
 //! Image preprocessing
 
 use crate::ffmpeg::{FfmpegCommand, FfmpegError};
-use crate::raw::{RawProcessor, RawOptions};
-use std::path::Path;
-use image::{DynamicImage, ImageFormat};
+use crate::animation::{self, AnimationFrame, AnimationInfo, AnimationMode, AnimationOutput};
+use crate::color::{self, TargetColorSpace};
+use crate::heif;
+use crate::jxl;
+use crate::metadata_policy::{apply_metadata_policy, MetadataPolicy};
+use crate::placeholder::{self, Placeholders};
+use crate::raw::{PreviewOptions, RawDecode, RawProcessor, RawOptions};
+use crate::smartcrop::{self, FocusRegion};
+use crate::geometry::{CropRect, ResizeMode};
+use crate::watermark::{self, Watermark};
+use rayon::prelude::*;
+use std::io::{BufRead, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use image::{DynamicImage, ImageDecoder, ImageEncoder, ImageFormat, ImageReader};
 
 pub struct ImageConfig {
     pub width: u32,
     pub height: u32,
     pub format: ImageOutputFormat,
     pub quality: u8, // 1-100 for JPEG/WEBP
+    /// Apply EXIF orientation so portrait photos come out upright (native backend)
+    pub auto_orient: bool,
+    /// Space inputs with an embedded ICC profile are converted to (native backend)
+    pub color_space: TargetColorSpace,
+    /// Embed the `color_space` ICC profile in JPEG/PNG/TIFF/WebP/JPEG XL outputs
+    pub embed_icc: bool,
+    /// How the image is fitted to `width`x`height`; `Fit` keeps the aspect
+    /// ratio without padding or upscaling
+    pub resize: ResizeMode,
+    /// Regions `ResizeMode::Smart` keeps in frame (see `focus_hints`)
+    pub focus: Vec<FocusRegion>,
+    /// Compute BlurHash/ThumbHash placeholders from the resized output
+    pub placeholders: bool,
+    /// Source EXIF/XMP/IPTC carried over to outputs (ICC follows `embed_icc`)
+    pub metadata: MetadataPolicy,
+    /// Logo or text composited onto every output after resizing
+    pub watermark: Option<Watermark>,
+    /// Frame used from GIF/APNG/animated WebP inputs, or keep the animation
+    pub animation: AnimationMode,
 }
 
 impl Default for ImageConfig {
@@ -19,6 +49,39 @@ impl Default for ImageConfig {
             height: 336,
             format: ImageOutputFormat::Jpeg,
             quality: 90,
+            auto_orient: true,
+            color_space: TargetColorSpace::Srgb,
+            embed_icc: true,
+            resize: ResizeMode::Stretch,  // Exact model input size
+            focus: Vec::new(),
+            placeholders: false,
+            metadata: MetadataPolicy::Strip,
+            watermark: None,
+            animation: AnimationMode::First,
+        }
+    }
+}
+
+/// Backend that produced a preprocessed image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageBackend {
+    /// In-process decode (`image`), resize (`fast_image_resize`) and encode
+    Native,
+    /// FFmpeg subprocess (exotic inputs or native decode failure)
+    Ffmpeg,
+    /// libheif `heif-dec` decode (HEIC/HEIF/AVIF), native resize and encode
+    Libheif,
+    /// libjxl `djxl` decode, or lossless JPEG → JPEG XL recompression
+    Libjxl,
+}
+
+impl ImageBackend {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ImageBackend::Native => "native",
+            ImageBackend::Ffmpeg => "ffmpeg",
+            ImageBackend::Libheif => "libheif",
+            ImageBackend::Libjxl => "libjxl",
         }
     }
 }
@@ -29,15 +92,37 @@ pub enum ImageOutputFormat {
     Png,
     Webp,
     Avif,
+    /// JPEG XL via libjxl (`cjxl`)
+    Jxl,
+    /// Palette GIF (for animations kept from GIF inputs)
+    Gif,
+    /// Uncompressed TIFF (16 bits per sample from 16-bit RAW conversions)
+    Tiff,
 }
 
 impl ImageOutputFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(ImageOutputFormat::Jpeg),
+            "png" => Some(ImageOutputFormat::Png),
+            "webp" => Some(ImageOutputFormat::Webp),
+            "avif" => Some(ImageOutputFormat::Avif),
+            "jxl" => Some(ImageOutputFormat::Jxl),
+            "gif" => Some(ImageOutputFormat::Gif),
+            "tif" | "tiff" => Some(ImageOutputFormat::Tiff),
+            _ => None,
+        }
+    }
+    
     pub fn as_str(&self) -> &str {
         match self {
             ImageOutputFormat::Jpeg => "jpg",
             ImageOutputFormat::Png => "png",
             ImageOutputFormat::Webp => "webp",
             ImageOutputFormat::Avif => "avif",
+            ImageOutputFormat::Jxl => "jxl",
+            ImageOutputFormat::Gif => "gif",
+            ImageOutputFormat::Tiff => "tiff",
         }
     }
     
@@ -47,10 +132,55 @@ impl ImageOutputFormat {
             ImageOutputFormat::Png => "png",
             ImageOutputFormat::Webp => "webp",
             ImageOutputFormat::Avif => "avif",
+            ImageOutputFormat::Jxl => "jxl",
+            ImageOutputFormat::Gif => "gif",
+            ImageOutputFormat::Tiff => "tif",
         }
     }
 }
 
+/// Result of [`ImagePreprocessor::preprocess_detailed`] and [`ImagePreprocessor::write_image`]
+#[derive(Debug, Clone)]
+pub struct PreprocessReport {
+    pub backend: ImageBackend,
+    /// Source window kept by `Fill`/`Smart` resizing (in-process backends only)
+    pub crop: Option<CropRect>,
+    /// Set when `ImageConfig::placeholders` is on (in-process backends only)
+    pub placeholders: Option<Placeholders>,
+    /// Set for animated inputs: the frame used, or that all frames were kept
+    pub animation: Option<AnimationOutput>,
+}
+
+/// One derivative written by [`ImagePreprocessor::variants`]
+#[derive(Debug, Clone)]
+pub struct VariantSpec {
+    /// Label reported back (e.g. "thumb", "grid", "detail")
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// `Fit` scales inside the box without padding or upscaling, `Fill` crops
+    /// to the box around the centre, `Smart` around the most salient region,
+    /// `Stretch` ignores the aspect ratio
+    pub resize: ResizeMode,
+    pub format: ImageOutputFormat,
+    pub quality: u8,
+    pub output: PathBuf,
+}
+
+/// A derivative written by [`ImagePreprocessor::variants`]
+#[derive(Debug, Clone)]
+pub struct VariantOutput {
+    pub name: String,
+    pub output: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub format: ImageOutputFormat,
+    /// Encoded file size
+    pub bytes: u64,
+    /// Source window kept by `Fill`/`Smart`, in decoded source pixels
+    pub crop: Option<CropRect>,
+}
+
 pub struct ImagePreprocessor {
     config: ImageConfig,
     raw: Option<RawProcessor>,
@@ -63,13 +193,582 @@ impl ImagePreprocessor {
         Self { config, raw }
     }
     
-    /// Resize and convert image using FFmpeg (supports more formats than image crate)
+    /// Resize and convert an image
+    ///
+    /// Common formats (JPEG, PNG, WebP, GIF, BMP, TIFF) are handled in process
+    /// with EXIF auto-orientation, HEIC/HEIF/AVIF are decoded via libheif;
+    /// anything else goes through FFmpeg.
     pub fn preprocess(&self, input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<(), FfmpegError> {
+        self.preprocess_with_report(input, output).map(|_| ())
+    }
+    
+    /// Resize and convert an image, reporting which backend was used
+    pub fn preprocess_with_report(&self, input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<ImageBackend, FfmpegError> {
+        self.preprocess_detailed(input, output).map(|report| report.backend)
+    }
+    
+    /// Resize and convert an image, reporting the backend, the source window
+    /// kept by `Fill`/`Smart` resizing and the output's placeholders
+    pub fn preprocess_detailed(&self, input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<PreprocessReport, FfmpegError> {
+        let report = self.preprocess_pixels(input.as_ref(), output.as_ref())?;
+        self.copy_metadata(input, output)?;
+        Ok(report)
+    }
+    
+    /// Apply the configured metadata policy, copying from `source` into `output`
+    pub fn copy_metadata(&self, source: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<(), FfmpegError> {
+        apply_metadata_policy(source.as_ref(), output.as_ref(), self.config.metadata, self.config.auto_orient)
+    }
+    
+    fn preprocess_pixels(&self, input: &Path, output: &Path) -> Result<PreprocessReport, FfmpegError> {
+        match animation::probe_animation(input).filter(AnimationInfo::is_animated) {
+            Some(info) if self.config.animation != AnimationMode::First => self.preprocess_animation(input, output, &info),
+            Some(info) => {
+                // Every decoder below yields the first frame
+                let report = self.preprocess_still(input, output)?;
+                let animation = Some(AnimationOutput { frame_count: info.frame_count as usize, frame_index: Some(0) });
+                Ok(PreprocessReport { animation, ..report })
+            }
+            None => self.preprocess_still(input, output),
+        }
+    }
+    
+    fn preprocess_still(&self, input: &Path, output: &Path) -> Result<PreprocessReport, FfmpegError> {
+        if heif::is_heif(input) {
+            let decoded = heif::decode_heif(input, self.config.auto_orient)?;
+            let image = self.to_target_space(decoded.image, decoded.icc_profile)?;
+            let report = self.write_image(&image, output)?;
+            return Ok(PreprocessReport { backend: decoded.backend, ..report });
+        }
+        
+        if jxl::is_jxl(input) {
+            let (image, icc) = jxl::decode_jxl_with_profile(input)?;
+            let report = self.write_image(&self.to_target_space(image, icc)?, output)?;
+            return Ok(PreprocessReport { backend: ImageBackend::Libjxl, ..report });
+        }
+        
+        if is_native_input(input) {
+            match self.preprocess_native(input, output) {
+                Ok(report) => return Ok(report),
+                Err(e) => tracing::debug!("Native preprocess failed for {}, using FFmpeg: {}", input.display(), e),
+            }
+        }
+        
+        // The watermark is composited in process, so FFmpeg only decodes
+        if self.config.watermark.is_some() {
+            let report = self.write_image(&decode_ffmpeg(input)?, output)?;
+            return Ok(PreprocessReport { backend: ImageBackend::Ffmpeg, ..report });
+        }
+        self.preprocess_ffmpeg(input, output)?;
+        Ok(PreprocessReport { backend: ImageBackend::Ffmpeg, crop: None, placeholders: None, animation: None })
+    }
+    
+    /// GIF/APNG/animated WebP input: write one frame as a still, or every
+    /// frame as an animation per `ImageConfig::animation`
+    fn preprocess_animation(&self, input: &Path, output: &Path, info: &AnimationInfo) -> Result<PreprocessReport, FfmpegError> {
+        let keep = self.config.animation == AnimationMode::Keep;
+        if keep && !matches!(self.config.format, ImageOutputFormat::Webp | ImageOutputFormat::Gif) {
+            return Err(FfmpegError::InvalidOutput(format!(
+                "Animation can only be kept in webp or gif output, not {}",
+                self.config.format.as_str()
+            )));
+        }
+        
+        let mut frames = animation::decode_frames(input)?;
+        if frames.is_empty() {
+            return Err(FfmpegError::InvalidOutput(format!("{} has no frames", input.display())));
+        }
+        let frame_count = frames.len();
+        let index = match self.config.animation {
+            AnimationMode::First => 0,
+            AnimationMode::Representative | AnimationMode::Keep => animation::representative_frame(&frames),
+        };
+        
+        if !keep {
+            let image = DynamicImage::ImageRgba8(frames.swap_remove(index).image);
+            let report = self.write_image(&image, output)?;
+            let animation = Some(AnimationOutput { frame_count, frame_index: Some(index) });
+            return Ok(PreprocessReport { animation, ..report });
+        }
+        
+        // Every frame gets the same size, crop (chosen on the representative frame) and watermark
+        let (source_width, source_height) = frames[0].image.dimensions();
+        let (width, height) = fitted_size(source_width, source_height, self.config.width, self.config.height, self.config.resize);
+        let crop = match self.config.resize {
+            ResizeMode::Fill => Some(smartcrop::cover_rect(source_width, source_height, width, height)),
+            ResizeMode::Smart => Some(smartcrop::smart_crop_rect(
+                &DynamicImage::ImageRgba8(frames[index].image.clone()),
+                width,
+                height,
+                &self.config.focus,
+            )),
+            ResizeMode::Fit | ResizeMode::Stretch => None,
+        };
+        let options = crop.map(|rect| crop_options(rect, 1.0));
+        let watermark = self.config.watermark.as_ref().map(Watermark::render).transpose()?;
+        
+        let resized = frames
+            .par_iter()
+            .map(|frame| {
+                let mut pixels = resize_buffer(
+                    frame.image.as_raw(),
+                    source_width,
+                    source_height,
+                    fast_image_resize::PixelType::U8x4,
+                    width,
+                    height,
+                    options.as_ref(),
+                )?;
+                if let Some(watermark) = &watermark {
+                    watermark.apply_buffer(&mut pixels, width, height, 4)?;
+                }
+                let image = image::RgbaImage::from_raw(width, height, pixels)
+                    .ok_or_else(|| FfmpegError::ExecutionFailed("Pixel buffer does not match dimensions".to_string()))?;
+                Ok(AnimationFrame { image, delay_ms: frame.delay_ms })
+            })
+            .collect::<Result<Vec<_>, FfmpegError>>()?;
+        drop(frames);
+        
+        animation::encode_animation(&resized, self.config.format.as_str(), self.config.quality, info.loop_count, output)?;
+        let placeholders = self.config.placeholders
+            .then(|| placeholder::placeholders(&DynamicImage::ImageRgba8(resized[0].image.clone())));
+        Ok(PreprocessReport {
+            backend: ImageBackend::Native,
+            crop,
+            placeholders,
+            animation: Some(AnimationOutput { frame_count, frame_index: None }),
+        })
+    }
+    
+    /// Decode, orient, resize and encode without leaving the process
+    fn preprocess_native(&self, input: &Path, output: &Path) -> Result<PreprocessReport, FfmpegError> {
+        let img = self.load_oriented(input)?;
+        self.write_image(&img, output)
+    }
+    
+    /// Resize a decoded image to the configured size and encode it to `output`
+    ///
+    /// The watermark, if any, is composited after resizing. The report carries
+    /// the window of `img` kept by `Fill`/`Smart` resizing and, if enabled,
+    /// placeholders of the output pixels; its backend is always `Native`.
+    pub fn write_image(&self, img: &DynamicImage, output: impl AsRef<Path>) -> Result<PreprocessReport, FfmpegError> {
+        let (encoded, report) = self.encode_image(img)?;
+        std::fs::write(output, encoded)?;
+        Ok(report)
+    }
+    
+    /// [`Self::write_image`] into memory, returning the encoded output
+    pub fn encode_image(&self, img: &DynamicImage) -> Result<(Vec<u8>, PreprocessReport), FfmpegError> {
+        // Keep alpha only where the output format can store it
+        let keep_alpha = img.color().has_alpha() && !matches!(self.config.format, ImageOutputFormat::Jpeg);
+        let (buffer, pixel_type) = if keep_alpha {
+            (img.to_rgba8().into_raw(), fast_image_resize::PixelType::U8x4)
+        } else {
+            (img.to_rgb8().into_raw(), fast_image_resize::PixelType::U8x3)
+        };
+        
+        let (width, height) = fitted_size(img.width(), img.height(), self.config.width, self.config.height, self.config.resize);
+        let crop = match self.config.resize {
+            ResizeMode::Fill => Some(smartcrop::cover_rect(img.width(), img.height(), width, height)),
+            ResizeMode::Smart => Some(smartcrop::smart_crop_rect(img, width, height, &self.config.focus)),
+            ResizeMode::Fit | ResizeMode::Stretch => None,
+        };
+        let options = crop.map(|rect| crop_options(rect, 1.0));
+        
+        let mut resized = resize_buffer(&buffer, img.width(), img.height(), pixel_type, width, height, options.as_ref())?;
+        if let Some(watermark) = &self.config.watermark {
+            watermark.render()?.apply_buffer(&mut resized, width, height, if keep_alpha { 4 } else { 3 })?;
+        }
+        let icc = self.config.embed_icc.then(|| self.config.color_space.icc_profile());
+        let encoded = self.encode_pixels(&resized, width, height, keep_alpha, icc.as_deref())?;
+        
+        let placeholders = if self.config.placeholders {
+            let image = if keep_alpha {
+                image::RgbaImage::from_raw(width, height, resized).map(DynamicImage::ImageRgba8)
+            } else {
+                image::RgbImage::from_raw(width, height, resized).map(DynamicImage::ImageRgb8)
+            };
+            image.map(|image| placeholder::placeholders(&image))
+        } else {
+            None
+        };
+        Ok((encoded, PreprocessReport { backend: ImageBackend::Native, crop, placeholders, animation: None }))
+    }
+    
+    /// Resize and convert an image held in memory, returning the encoded output
+    ///
+    /// JPEG, PNG, WebP, GIF, BMP and TIFF decode in process and RAW data via
+    /// its preview; HEIC/HEIF/AVIF are staged in a temporary file for libheif
+    /// (see `decode_heif`); anything else (JPEG XL, ...) is piped through
+    /// FFmpeg's stdin/stdout. Animated inputs give their first frame. The
+    /// metadata policy needs files, so no source EXIF/XMP is carried over
+    /// (the ICC profile still follows `embed_icc`).
+    pub fn preprocess_from_memory(&self, input: &[u8]) -> Result<(Vec<u8>, PreprocessReport), FfmpegError> {
+        let (image, backend) = self.decode_from_memory(input)?;
+        let (encoded, report) = self.encode_image(&image)?;
+        Ok((encoded, PreprocessReport { backend, ..report }))
+    }
+    
+    /// [`Self::preprocess_from_memory`] reading `input` to the end and writing to `output`
+    pub fn preprocess_stream(&self, mut input: impl Read, mut output: impl Write) -> Result<PreprocessReport, FfmpegError> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let (encoded, report) = self.preprocess_from_memory(&data)?;
+        output.write_all(&encoded)?;
+        Ok(report)
+    }
+    
+    /// Decode an image held in memory, oriented and converted to `color_space`
+    /// (see [`Self::preprocess_from_memory`] for the formats handled)
+    pub fn load_from_memory(&self, input: &[u8]) -> Result<DynamicImage, FfmpegError> {
+        self.decode_from_memory(input).map(|(image, _)| image)
+    }
+    
+    fn decode_from_memory(&self, input: &[u8]) -> Result<(DynamicImage, ImageBackend), FfmpegError> {
+        let format = image::guess_format(input).ok();
+        let native = matches!(format, Some(ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP | ImageFormat::Gif | ImageFormat::Bmp));
+        
+        // TIFF-based RAW files are tried as RAW before plain TIFF
+        if !native {
+            if let Some(raw) = self.raw.as_ref().filter(|raw| raw.is_raw_data(input)) {
+                let preview = raw
+                    .preview_image_from_memory(input, &PreviewOptions { max_dimension: None, ..PreviewOptions::default() })
+                    .map_err(|e| FfmpegError::ExecutionFailed(format!("RAW decode failed: {}", e)))?;
+                let image = self.to_target_space(preview, Some(TargetColorSpace::Srgb.icc_profile()))?;
+                return Ok((image, ImageBackend::Native));
+            }
+        }
+        
+        if native || format == Some(ImageFormat::Tiff) {
+            let reader = ImageReader::with_format(std::io::Cursor::new(input), format.unwrap_or(ImageFormat::Tiff));
+            match self.decode_oriented(reader) {
+                Ok(image) => return Ok((image, ImageBackend::Native)),
+                Err(e) => tracing::debug!("Native decode from memory failed, using FFmpeg: {}", e),
+            }
+        }
+        
+        // HEIF needs a seekable file (FFmpeg can't demux it from a pipe)
+        if heif::is_heif_data(input) {
+            let decoded = heif::decode_heif_from_memory(input, self.config.auto_orient)?;
+            let backend = decoded.backend;
+            return Ok((self.to_target_space(decoded.image, decoded.icc_profile)?, backend));
+        }
+        
+        let output = FfmpegCommand::new()
+            .input("-")
+            .args(&["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png"])
+            .output("-")
+            .execute_with_input(input)?;
+        let image = image::load_from_memory_with_format(&output, ImageFormat::Png)
+            .map_err(|e| FfmpegError::InvalidOutput(format!("Failed to decode FFmpeg output: {}", e)))?;
+        Ok((image, ImageBackend::Ffmpeg))
+    }
+    
+    /// Decode `input` once and write every derivative in `specs`
+    ///
+    /// Variants are resized largest first with `fast_image_resize`; a `Fit`
+    /// result at least twice the size of a later variant becomes its source, so
+    /// thumbnails aren't resampled from the full decode. Encoding runs in
+    /// parallel. Size, format and quality come from each spec; orientation,
+    /// colour handling, metadata and watermark from this preprocessor's config.
+    pub fn variants(&self, input: impl AsRef<Path>, specs: &[VariantSpec], raw_decode: &RawDecode) -> Result<Vec<VariantOutput>, FfmpegError> {
+        let source = self.load_source(input.as_ref(), raw_decode)?;
+        // Rendered once, scaled to each variant
+        let watermark = self.config.watermark.as_ref().map(Watermark::render).transpose()?;
+        let alpha = source.color().has_alpha();
+        let (buffer, pixel_type) = if alpha {
+            (source.to_rgba8().into_raw(), fast_image_resize::PixelType::U8x4)
+        } else {
+            (source.to_rgb8().into_raw(), fast_image_resize::PixelType::U8x3)
+        };
+        let (width, height) = (source.width(), source.height());
+        
+        // Crop windows are chosen on the full source
+        let crops: Vec<Option<CropRect>> = specs
+            .iter()
+            .map(|spec| match spec.resize {
+                ResizeMode::Fill => Some(smartcrop::cover_rect(width, height, spec.width, spec.height)),
+                ResizeMode::Smart => Some(smartcrop::smart_crop_rect(&source, spec.width, spec.height, &self.config.focus)),
+                ResizeMode::Fit | ResizeMode::Stretch => None,
+            })
+            .collect();
+        drop(source);
+        
+        let mut order: Vec<usize> = (0..specs.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(specs[i].width as u64 * specs[i].height as u64));
+        
+        // (buffer, width, height) of Fit results, reusable as smaller sources
+        let mut pyramid: Vec<(Vec<u8>, u32, u32)> = Vec::new();
+        let mut resized: Vec<Option<(Vec<u8>, u32, u32)>> = vec![None; specs.len()];
+        for index in order {
+            let spec = &specs[index];
+            let (dst_width, dst_height) = fitted_size(width, height, spec.width, spec.height, spec.resize);
+            let (src, src_width, src_height) = pyramid
+                .iter()
+                .rev()
+                .find(|(_, w, h)| *w >= dst_width * 2 && *h >= dst_height * 2)
+                .map(|(b, w, h)| (b, *w, *h))
+                .unwrap_or((&buffer, width, height));
+            
+            let options = crops[index].map(|rect| crop_options(rect, src_width as f64 / width as f64));
+            let pixels = resize_buffer(src, src_width, src_height, pixel_type, dst_width, dst_height, options.as_ref())?;
+            
+            if matches!(spec.resize, ResizeMode::Fit) {
+                pyramid.push((pixels.clone(), dst_width, dst_height));
+            }
+            resized[index] = Some((pixels, dst_width, dst_height));
+        }
+        
+        let (auto_orient, color_space, embed_icc, metadata) = (self.config.auto_orient, self.config.color_space, self.config.embed_icc, self.config.metadata);
+        let input = input.as_ref();
+        specs
+            .par_iter()
+            .zip(resized.into_par_iter())
+            .zip(crops.into_par_iter())
+            .map(|((spec, resized), crop)| {
+                let (mut pixels, width, height) = resized.unwrap_or_default();
+                if let Some(watermark) = &watermark {
+                    watermark.apply_buffer(&mut pixels, width, height, if alpha { 4 } else { 3 })?;
+                }
+                // Drop alpha where the output format can't store it
+                let keep_alpha = alpha && !matches!(spec.format, ImageOutputFormat::Jpeg);
+                let pixels = if alpha && !keep_alpha {
+                    pixels.chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect()
+                } else {
+                    pixels
+                };
+                
+                let encoder = ImagePreprocessor::encoder(ImageConfig {
+                    width,
+                    height,
+                    format: spec.format,
+                    quality: spec.quality,
+                    auto_orient,
+                    color_space,
+                    embed_icc,
+                    resize: spec.resize,
+                    focus: Vec::new(),
+                    placeholders: false,
+                    metadata,
+                    watermark: None,
+                    animation: AnimationMode::First,
+                });
+                let icc = embed_icc.then(|| color_space.icc_profile());
+                encoder.encode_native(&pixels, width, height, keep_alpha, &spec.output, icc.as_deref())?;
+                encoder.copy_metadata(input, &spec.output)?;
+                
+                Ok(VariantOutput {
+                    name: spec.name.clone(),
+                    output: spec.output.clone(),
+                    width,
+                    height,
+                    format: spec.format,
+                    bytes: std::fs::metadata(&spec.output)?.len(),
+                    crop,
+                })
+            })
+            .collect()
+    }
+    
+    /// Decode a full source image: RAW files per `raw_decode`, everything else
+    /// oriented and colour-managed, with FFmpeg as the last resort
+    pub(crate) fn load_source(&self, input: &Path, raw_decode: &RawDecode) -> Result<DynamicImage, FfmpegError> {
+        if let Some(raw) = &self.raw {
+            if RawProcessor::is_raw_format(input) && !is_tiff_image(input) && !heif::is_heif(input) {
+                // Previews are rendered in sRGB, full decodes in the requested space
+                let (decoded, icc) = match raw_decode {
+                    RawDecode::Preview => (
+                        raw.preview_image(input, &PreviewOptions { max_dimension: None, ..PreviewOptions::default() }),
+                        Some(TargetColorSpace::Srgb.icc_profile()),
+                    ),
+                    RawDecode::Full(options) => (raw.decode_image(input, options), options.icc_profile()),
+                };
+                let image = decoded.map_err(|e| FfmpegError::ExecutionFailed(format!("RAW decode failed: {}", e)))?;
+                return self.to_target_space(image, icc);
+            }
+        }
+        
+        match self.load_oriented(input) {
+            Ok(image) => Ok(image),
+            Err(e) => {
+                tracing::debug!("Native decode failed for {}, using FFmpeg: {}", input.display(), e);
+                decode_ffmpeg(input)
+            }
+        }
+    }
+    
+    /// Convert from the input's embedded ICC profile (if any) to the configured space
+    fn to_target_space(&self, img: DynamicImage, icc: Option<Vec<u8>>) -> Result<DynamicImage, FfmpegError> {
+        match icc {
+            Some(icc) => color::convert_to_target(img, &icc, self.config.color_space),
+            None => Ok(img),  // Untagged inputs are assumed to be sRGB
+        }
+    }
+    
+    /// Load an image with its EXIF orientation applied (unless `auto_orient` is off)
+    /// and converted from its embedded ICC profile to `color_space`
+    ///
+    /// HEIC/HEIF/AVIF inputs are decoded via libheif (see `decode_heif`), JPEG XL
+    /// via libjxl (see `decode_jxl`).
+    pub fn load_oriented(&self, path: impl AsRef<Path>) -> Result<DynamicImage, FfmpegError> {
+        if heif::is_heif(path.as_ref()) {
+            let decoded = heif::decode_heif(path, self.config.auto_orient)?;
+            return self.to_target_space(decoded.image, decoded.icc_profile);
+        }
+        if jxl::is_jxl(path.as_ref()) {
+            let (image, icc) = jxl::decode_jxl_with_profile(path)?;
+            return self.to_target_space(image, icc);
+        }
+        
+        self.decode_oriented(ImageReader::open(path.as_ref())?.with_guessed_format()?)
+    }
+    
+    /// Decode with `load_oriented`'s orientation and colour handling
+    fn decode_oriented<R: BufRead + Seek>(&self, mut reader: ImageReader<R>) -> Result<DynamicImage, FfmpegError> {
+        let map_err = |e: image::ImageError| FfmpegError::ExecutionFailed(format!("Failed to load image: {}", e));
+        
+        // Gigapixel scans exceed the default 512 MiB allocation limit
+        reader.no_limits();
+        let mut decoder = reader
+            .into_decoder()
+            .map_err(map_err)?;
+        let orientation = decoder.orientation().ok();
+        let icc = decoder.icc_profile().ok().flatten();
+        
+        let mut img = DynamicImage::from_decoder(decoder).map_err(map_err)?;
+        if self.config.auto_orient {
+            if let Some(orientation) = orientation {
+                img.apply_orientation(orientation);
+            }
+        }
+        self.to_target_space(img, icc)
+    }
+    
+    /// Preprocessor used only to encode pixel buffers (no RAW processor)
+    pub(crate) fn encoder(config: ImageConfig) -> Self {
+        Self { config, raw: None }
+    }
+    
+    /// Encode resized pixels to the configured output format, tagged with `icc`
+    ///
+    /// AVIF output is not tagged (the `image` AVIF encoder has no ICC support).
+    pub(crate) fn encode_native(&self, pixels: &[u8], width: u32, height: u32, alpha: bool, output: &Path, icc: Option<&[u8]>) -> Result<(), FfmpegError> {
+        std::fs::write(output, self.encode_pixels(pixels, width, height, alpha, icc)?)?;
+        Ok(())
+    }
+    
+    /// [`Self::encode_native`] into memory
+    pub(crate) fn encode_pixels(&self, pixels: &[u8], width: u32, height: u32, alpha: bool, icc: Option<&[u8]>) -> Result<Vec<u8>, FfmpegError> {
+        let color = if alpha { image::ExtendedColorType::Rgba8 } else { image::ExtendedColorType::Rgb8 };
+        let map_err = |e: image::ImageError| FfmpegError::ExecutionFailed(format!("Failed to encode image: {}", e));
+        let icc_err = |e: image::error::UnsupportedError| FfmpegError::ExecutionFailed(format!("Failed to embed ICC profile: {}", e));
+        let mut encoded = Vec::new();
+        
+        match self.config.format {
+            ImageOutputFormat::Webp => {
+                // libwebp lossy encoding (image crate only writes lossless WebP)
+                let encoder = if alpha {
+                    webp::Encoder::from_rgba(pixels, width, height)
+                } else {
+                    webp::Encoder::from_rgb(pixels, width, height)
+                };
+                let webp = encoder.encode(self.config.quality as f32);
+                encoded = match icc {
+                    Some(icc) => color::embed_webp_icc(&webp, icc, width, height, alpha),
+                    None => webp.to_vec(),
+                };
+            }
+            ImageOutputFormat::Jpeg => {
+                let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut encoded, self.config.quality);
+                if let Some(icc) = icc {
+                    encoder.set_icc_profile(icc.to_vec()).map_err(icc_err)?;
+                }
+                encoder.write_image(pixels, width, height, color).map_err(map_err)?;
+            }
+            ImageOutputFormat::Png => {
+                let mut encoder = image::codecs::png::PngEncoder::new(&mut encoded);
+                if let Some(icc) = icc {
+                    encoder.set_icc_profile(icc.to_vec()).map_err(icc_err)?;
+                }
+                encoder.write_image(pixels, width, height, color).map_err(map_err)?;
+            }
+            ImageOutputFormat::Avif => {
+                image::codecs::avif::AvifEncoder::new_with_speed_quality(&mut encoded, 6, self.config.quality)
+                    .write_image(pixels, width, height, color)
+                    .map_err(map_err)?;
+            }
+            ImageOutputFormat::Jxl => {
+                let img = if alpha {
+                    image::RgbaImage::from_raw(width, height, pixels.to_vec()).map(DynamicImage::ImageRgba8)
+                } else {
+                    image::RgbImage::from_raw(width, height, pixels.to_vec()).map(DynamicImage::ImageRgb8)
+                }
+                .ok_or_else(|| FfmpegError::ExecutionFailed("Pixel buffer does not match dimensions".to_string()))?;
+                encoded = jxl::encode_jxl_with_icc(&img, self.config.quality, icc)?;
+            }
+            ImageOutputFormat::Gif => {
+                image::codecs::gif::GifEncoder::new_with_speed(&mut encoded, 10)
+                    .encode(pixels, width, height, color)
+                    .map_err(map_err)?;
+            }
+            ImageOutputFormat::Tiff => {
+                encoded = if alpha {
+                    encode_tiff::<tiff::encoder::colortype::RGBA8>(pixels, width, height, icc)?
+                } else {
+                    encode_tiff::<tiff::encoder::colortype::RGB8>(pixels, width, height, icc)?
+                };
+            }
+        }
+        
+        Ok(encoded)
+    }
+    
+    /// Encode 16-bit RGB samples, tagged with `icc`
+    ///
+    /// TIFF and PNG keep all 16 bits; other formats are 8-bit only and get
+    /// the samples rounded down to 8 bits.
+    pub(crate) fn encode_native_16(&self, pixels: &[u16], width: u32, height: u32, output: &Path, icc: Option<&[u8]>) -> Result<(), FfmpegError> {
+        match self.config.format {
+            ImageOutputFormat::Tiff => {
+                std::fs::write(output, encode_tiff::<tiff::encoder::colortype::RGB16>(pixels, width, height, icc)?)?;
+                Ok(())
+            }
+            ImageOutputFormat::Png => {
+                let file = std::io::BufWriter::new(std::fs::File::create(output)?);
+                let mut encoder = image::codecs::png::PngEncoder::new(file);
+                if let Some(icc) = icc {
+                    encoder
+                        .set_icc_profile(icc.to_vec())
+                        .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to embed ICC profile: {}", e)))?;
+                }
+                // Native-endian samples; the encoder writes them big-endian
+                let bytes: Vec<u8> = pixels.iter().flat_map(|sample| sample.to_ne_bytes()).collect();
+                encoder
+                    .write_image(&bytes, width, height, image::ExtendedColorType::Rgb16)
+                    .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to encode image: {}", e)))
+            }
+            _ => {
+                let rgb: Vec<u8> = pixels.iter().map(|&sample| (sample >> 8) as u8).collect();
+                self.encode_native(&rgb, width, height, false, output, icc)
+            }
+        }
+    }
+    
+    /// Resize and convert image using FFmpeg (supports more formats than image crate)
+    fn preprocess_ffmpeg(&self, input: &Path, output: &Path) -> Result<(), FfmpegError> {
+        let (width, height) = (self.config.width, self.config.height);
+        let scale = match self.config.resize {
+            ResizeMode::Stretch => format!("scale={}:{}", width, height),
+            ResizeMode::Fit => format!("scale={}:{}:force_original_aspect_ratio=decrease", width, height),
+            // No saliency map on this path: Smart falls back to a centre crop
+            ResizeMode::Fill | ResizeMode::Smart => format!(
+                "scale={}:{}:force_original_aspect_ratio=increase,crop={}:{}",
+                width, height, width, height
+            ),
+        };
         let mut cmd = FfmpegCommand::new()
             .input(input)
-            .args(&[
-                "-vf", &format!("scale={}:{}", self.config.width, self.config.height),
-            ]);
+            .args(&["-vf", &scale]);
         
         // Add format-specific options
         match self.config.format {
@@ -82,8 +781,11 @@ impl ImagePreprocessor {
             ImageOutputFormat::Avif => {
                 cmd = cmd.args(&["-crf", &((100 - self.config.quality) / 2).to_string()]);
             }
-            ImageOutputFormat::Png => {
-                // PNG is lossless, no quality setting
+            ImageOutputFormat::Jxl => {
+                cmd = cmd.args(&["-c:v", "libjxl", "-distance", &format!("{:.2}", jxl::jxl_distance(self.config.quality))]);
+            }
+            ImageOutputFormat::Png | ImageOutputFormat::Gif | ImageOutputFormat::Tiff => {
+                // Lossless/palette formats, no quality setting
             }
         }
         
@@ -114,6 +816,12 @@ impl ImagePreprocessor {
             ImageOutputFormat::Png => ImageFormat::Png,
             ImageOutputFormat::Webp => ImageFormat::WebP,
             ImageOutputFormat::Avif => ImageFormat::Avif,
+            ImageOutputFormat::Gif => ImageFormat::Gif,
+            ImageOutputFormat::Tiff => ImageFormat::Tiff,
+            ImageOutputFormat::Jxl => {
+                std::fs::write(path, jxl::encode_jxl(img, self.config.quality)?)?;
+                return Ok(());
+            }
         };
         
         img.save_with_format(path, format)
@@ -149,22 +857,30 @@ impl ImagePreprocessor {
         if is_raw {
             // Use libraw FFI for all RAW formats
             if let Some(raw_proc) = &self.raw {
+                // Tagged with the profile LibRaw rendered into
+                let icc = if self.config.embed_icc { raw_options.icc_profile() } else { None };
+                
+                if raw_options.bit_depth == 16 {
+                    // Keep the full tonal range through resize and encode (TIFF/PNG)
+                    let (rgb, width, height) = raw_proc.process_raw_from_memory_16(&file_data, raw_options)
+                        .map_err(|e| FfmpegError::ExecutionFailed(format!("libraw failed: {}", e)))?;
+                    let (target_width, target_height) = self.raw_target_size(width, height);
+                    
+                    let mut rgb = resize_buffer_u16(&rgb, width, height, target_width, target_height)?;
+                    if let Some(watermark) = &self.config.watermark {
+                        watermark.render()?.apply_buffer_u16(&mut rgb, target_width, target_height)?;
+                    }
+                    
+                    self.encode_native_16(&rgb, target_width, target_height, output_path, icc.as_deref())?;
+                    return apply_metadata_policy(input_path, output_path, self.config.metadata, true);
+                }
+                
                 // Process from memory (file already read) - returns (data, width, height)
                 let (rgb_data, width, height) = raw_proc.process_raw_from_memory(&file_data, raw_options)
                     .map_err(|e| FfmpegError::ExecutionFailed(format!("libraw failed: {}", e)))?;
                 
                 // PPM writer already returns correctly interleaved RGB data
-                // Calculate target dimensions preserving aspect ratio
-                let src_aspect = width as f32 / height as f32;
-                let dst_aspect = self.config.width as f32 / self.config.height as f32;
-                
-                let (target_width, target_height) = if src_aspect > dst_aspect {
-                    // Source is wider - fit to width
-                    (self.config.width, (self.config.width as f32 / src_aspect) as u32)
-                } else {
-                    // Source is taller - fit to height
-                    ((self.config.height as f32 * src_aspect) as u32, self.config.height)
-                };
+                let (target_width, target_height) = self.raw_target_size(width, height);
                 
                 // Fast SIMD resize using fast_image_resize
                 use fast_image_resize as fr;
@@ -187,38 +903,15 @@ impl ImagePreprocessor {
                 resizer.resize(&src_image, &mut dst_image, None)
                     .map_err(|e| FfmpegError::ExecutionFailed(format!("Resize failed: {:?}", e)))?;
                 
-                let rgb_bytes = dst_image.buffer().to_vec();
-                
-                // Fast direct encoding via FFI (no subprocess, no temp files)
-                match self.config.format {
-                    ImageOutputFormat::Webp => {
-                        // Direct libwebp FFI encoding
-                        let encoder = webp::Encoder::from_rgb(&rgb_bytes, target_width, target_height);
-                        let webp_data = encoder.encode(self.config.quality as f32);
-                        
-                        std::fs::write(output_path, &*webp_data)
-                            .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to write WEBP: {}", e)))?;
-                    }
-                    ImageOutputFormat::Jpeg | ImageOutputFormat::Png | ImageOutputFormat::Avif => {
-                        // Fall back to image crate for other formats
-                        // Recreate image from resized RGB bytes
-                        let img = image::RgbImage::from_raw(target_width, target_height, rgb_bytes.clone())
-                            .ok_or_else(|| FfmpegError::ExecutionFailed("Failed to create image from resized data".to_string()))?;
-                        let dynamic_img = image::DynamicImage::ImageRgb8(img);
-                        
-                        let format = match self.config.format {
-                            ImageOutputFormat::Jpeg => ImageFormat::Jpeg,
-                            ImageOutputFormat::Png => ImageFormat::Png,
-                            ImageOutputFormat::Avif => ImageFormat::Avif,
-                            _ => unreachable!(),
-                        };
-                        
-                        dynamic_img.save_with_format(output_path, format)
-                            .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to save image: {}", e)))?;
-                    }
+                let mut rgb_bytes = dst_image.buffer().to_vec();
+                if let Some(watermark) = &self.config.watermark {
+                    watermark.render()?.apply_buffer(&mut rgb_bytes, target_width, target_height, 3)?;
                 }
                 
-                Ok(())
+                // Fast direct encoding via FFI
+                self.encode_native(&rgb_bytes, target_width, target_height, false, output_path, icc.as_deref())?;
+                // LibRaw rendered the frame upright
+                apply_metadata_policy(input_path, output_path, self.config.metadata, true)
             } else {
                 Err(FfmpegError::ExecutionFailed(
                     "libraw not available - ensure rsraw crate is properly linked".to_string()
@@ -226,16 +919,313 @@ impl ImagePreprocessor {
             }
         } else {
             // Use FFmpeg for standard image formats (JPEG, PNG, TIFF, etc.)
-            FfmpegCommand::new()
-                .input(input_path)
-                .args(&[
-                    "-vf", &format!("scale={}:{}", self.config.width, self.config.height),
-                    "-pix_fmt", "rgb24",
-                ])
-                .output(output_path)
-                .execute()?;
+            let scale = format!("scale={}:{}", self.config.width, self.config.height);
+            match &self.config.watermark {
+                Some(watermark) => {
+                    let overlay = watermark.render()?.overlay_file(self.config.width, self.config.height)?;
+                    FfmpegCommand::new()
+                        .input(input_path)
+                        .input(overlay.path())
+                        .args(&[
+                            "-filter_complex", &watermark::overlay_graph(&[scale], &[]),
+                            "-map", "[v]",
+                            "-pix_fmt", "rgb24",
+                        ])
+                        .output(output_path)
+                        .execute()?;
+                }
+                None => {
+                    FfmpegCommand::new()
+                        .input(input_path)
+                        .args(&["-vf", &scale, "-pix_fmt", "rgb24"])
+                        .output(output_path)
+                        .execute()?;
+                }
+            }
             
-            Ok(())
+            self.copy_metadata(input_path, output_path)
+        }
+    }
+    
+    /// Size a `width`x`height` RAW render to fit the configured box, preserving aspect ratio
+    fn raw_target_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let src_aspect = width as f32 / height as f32;
+        let dst_aspect = self.config.width as f32 / self.config.height as f32;
+        
+        if src_aspect > dst_aspect {
+            // Source is wider - fit to width
+            (self.config.width, (self.config.width as f32 / src_aspect) as u32)
+        } else {
+            // Source is taller - fit to height
+            ((self.config.height as f32 * src_aspect) as u32, self.config.height)
+        }
+    }
+}
+
+/// Decode any supported still image: RAW files via their preview (`preview`
+/// options), everything else oriented and colour-managed to sRGB
+pub(crate) fn load_any_image(path: &Path, preview: &PreviewOptions) -> Result<DynamicImage, FfmpegError> {
+    if RawProcessor::is_raw_format(path) && !is_tiff_image(path) {
+        let raw = RawProcessor::new()
+            .map_err(|e| FfmpegError::ExecutionFailed(e.to_string()))?;
+        raw.preview_image(path, preview)
+            .map_err(|e| FfmpegError::ExecutionFailed(format!("RAW preview failed: {}", e)))
+    } else {
+        ImagePreprocessor::new(ImageConfig::default()).load_oriented(path)
+    }
+}
+
+/// Decode the first frame of anything FFmpeg reads
+fn decode_ffmpeg(input: &Path) -> Result<DynamicImage, FfmpegError> {
+    let output = FfmpegCommand::new()
+        .input(input)
+        .args(&["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png"])
+        .output("-")
+        .execute()?;
+    image::load_from_memory_with_format(&output.stdout, ImageFormat::Png)
+        .map_err(|e| FfmpegError::InvalidOutput(format!("Failed to decode {}: {}", input.display(), e)))
+}
+
+/// Plain TIFF images are decoded directly rather than as RAW
+fn is_tiff_image(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref(),
+        Some("tif") | Some("tiff")
+    )
+}
+
+/// Inputs the native backend decodes (sniffed from content, not extension)
+///
+/// TIFF is only accepted with a .tif/.tiff extension so TIFF-based RAW files
+/// (DNG, NEF, ...) are not mistaken for their embedded thumbnails.
+fn is_native_input(path: &Path) -> bool {
+    let format = match ImageReader::open(path).and_then(|r| r.with_guessed_format()) {
+        Ok(reader) => reader.format(),
+        Err(_) => return false,
+    };
+    
+    match format {
+        Some(ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP | ImageFormat::Gif | ImageFormat::Bmp) => true,
+        Some(ImageFormat::Tiff) => matches!(
+            path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref(),
+            Some("tif") | Some("tiff")
+        ),
+        _ => false,
+    }
+}
+
+/// Output size for a `box_width`x`box_height` target from a `width`x`height` source
+fn fitted_size(width: u32, height: u32, box_width: u32, box_height: u32, mode: ResizeMode) -> (u32, u32) {
+    match mode {
+        ResizeMode::Fill | ResizeMode::Smart | ResizeMode::Stretch => (box_width.max(1), box_height.max(1)),
+        ResizeMode::Fit => {
+            let scale = (box_width as f64 / width as f64)
+                .min(box_height as f64 / height as f64)
+                .min(1.0);
+            (
+                ((width as f64 * scale).round() as u32).max(1),
+                ((height as f64 * scale).round() as u32).max(1),
+            )
+        }
+    }
+}
+
+/// Resize options cropping to `rect`, scaled by `scale` for a smaller source
+fn crop_options(rect: CropRect, scale: f64) -> fast_image_resize::ResizeOptions {
+    fast_image_resize::ResizeOptions::new().crop(
+        rect.x as f64 * scale,
+        rect.y as f64 * scale,
+        rect.width as f64 * scale,
+        rect.height as f64 * scale,
+    )
+}
+
+/// SIMD resize of an interleaved 8-bit buffer to exactly `dst_width`x`dst_height`
+///
+/// `options` can crop the source, e.g. `fit_into_destination` for cover-style fills.
+pub(crate) fn resize_buffer(
+    buffer: &[u8],
+    width: u32,
+    height: u32,
+    pixel_type: fast_image_resize::PixelType,
+    dst_width: u32,
+    dst_height: u32,
+    options: Option<&fast_image_resize::ResizeOptions>,
+) -> Result<Vec<u8>, FfmpegError> {
+    use fast_image_resize as fr;
+    use fr::images::{Image as FrImage, ImageRef};
+    
+    if width == dst_width && height == dst_height {
+        return Ok(buffer.to_vec());
+    }
+    
+    let src_image = ImageRef::new(width, height, buffer, pixel_type)
+        .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to create source image: {:?}", e)))?;
+    let mut dst_image = FrImage::new(dst_width, dst_height, pixel_type);
+    
+    fr::Resizer::new().resize(&src_image, &mut dst_image, options)
+        .map_err(|e| FfmpegError::ExecutionFailed(format!("Resize failed: {:?}", e)))?;
+    
+    Ok(dst_image.into_vec())
+}
+
+/// SIMD resize of interleaved 16-bit RGB samples to exactly `dst_width`x`dst_height`
+pub(crate) fn resize_buffer_u16(
+    samples: &[u16],
+    width: u32,
+    height: u32,
+    dst_width: u32,
+    dst_height: u32,
+) -> Result<Vec<u16>, FfmpegError> {
+    use fast_image_resize as fr;
+    use fr::images::{Image as FrImage, ImageRef};
+    use fr::pixels::U16x3;
+    
+    if width == dst_width && height == dst_height {
+        return Ok(samples.to_vec());
+    }
+    
+    let pixels: Vec<U16x3> = samples.chunks_exact(3).map(|p| U16x3::new([p[0], p[1], p[2]])).collect();
+    let src_image = ImageRef::from_pixels(width, height, &pixels)
+        .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to create source image: {:?}", e)))?;
+    let mut dst_image = FrImage::new(dst_width, dst_height, fr::PixelType::U16x3);
+    
+    fr::Resizer::new().resize(&src_image, &mut dst_image, None)
+        .map_err(|e| FfmpegError::ExecutionFailed(format!("Resize failed: {:?}", e)))?;
+    
+    Ok(dst_image
+        .buffer()
+        .chunks_exact(2)
+        .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+        .collect())
+}
+
+/// Encode an uncompressed TIFF of `C` samples, with `icc` in the ICC profile tag
+fn encode_tiff<C: tiff::encoder::colortype::ColorType>(
+    samples: &[C::Inner],
+    width: u32,
+    height: u32,
+    icc: Option<&[u8]>,
+) -> Result<Vec<u8>, FfmpegError>
+where
+    [C::Inner]: tiff::encoder::TiffValue,
+{
+    let tiff_err = |e: tiff::TiffError| FfmpegError::ExecutionFailed(format!("Failed to encode TIFF: {}", e));
+    
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    let mut encoder = tiff::encoder::TiffEncoder::new(&mut encoded).map_err(tiff_err)?;
+    let mut image = encoder.new_image::<C>(width, height).map_err(tiff_err)?;
+    if let Some(icc) = icc {
+        image.encoder().write_tag(tiff::tags::Tag::IccProfile, icc).map_err(tiff_err)?;
+    }
+    image.write_data(samples).map_err(tiff_err)?;
+    Ok(encoded.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_native_preprocess_png() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let input = dir.join("input.png");
+        let output = dir.join("output.jpg");
+        
+        image::RgbImage::from_pixel(64, 32, image::Rgb([255, 0, 0])).save(&input).unwrap();
+        
+        let processor = ImagePreprocessor::new(ImageConfig { width: 16, height: 8, ..ImageConfig::default() });
+        let backend = processor.preprocess_with_report(&input, &output).unwrap();
+        
+        assert_eq!(backend, ImageBackend::Native);
+        let result = image::open(&output).unwrap();
+        assert_eq!((result.width(), result.height()), (16, 8));
+    }
+    
+    #[test]
+    fn test_preprocess_from_memory() {
+        let mut png = Vec::new();
+        image::RgbImage::from_pixel(64, 32, image::Rgb([0, 128, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        
+        let config = ImageConfig { width: 16, height: 16, resize: ResizeMode::Fit, format: ImageOutputFormat::Webp, ..ImageConfig::default() };
+        let processor = ImagePreprocessor::new(config);
+        let (encoded, report) = processor.preprocess_from_memory(&png).unwrap();
+        
+        assert_eq!(report.backend, ImageBackend::Native);
+        assert_eq!(image::guess_format(&encoded).unwrap(), ImageFormat::WebP);
+        let result = image::load_from_memory(&encoded).unwrap();
+        assert_eq!((result.width(), result.height()), (16, 8));
+        
+        let mut streamed = Vec::new();
+        processor.preprocess_stream(&png[..], &mut streamed).unwrap();
+        assert_eq!(streamed, encoded);
+    }
+    
+    #[test]
+    fn test_16bit_tiff_png_keep_depth_and_icc() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        
+        // A ramp finer than 8 bits can represent
+        let samples: Vec<u16> = (0..64 * 32).flat_map(|i| [i as u16 * 30, 1000, 65535 - i as u16]).collect();
+        let resized = resize_buffer_u16(&samples, 64, 32, 32, 16).unwrap();
+        assert_eq!(resized.len(), 32 * 16 * 3);
+        assert!(resized.iter().any(|&s| s % 257 != 0));
+        
+        let icc = TargetColorSpace::DisplayP3.icc_profile();
+        for format in [ImageOutputFormat::Tiff, ImageOutputFormat::Png] {
+            let output = dir.join(format!("out.{}", format.extension()));
+            let processor = ImagePreprocessor::new(ImageConfig { format, ..ImageConfig::default() });
+            processor.encode_native_16(&resized, 32, 16, &output, Some(&icc)).unwrap();
+            
+            let decoded = image::open(&output).unwrap();
+            assert_eq!(decoded.into_rgb16().into_raw(), resized);
+        }
+        
+        // `image` looks the TIFF profile up by raw tag number, so read it with `tiff`
+        let file = std::fs::File::open(dir.join("out.tif")).unwrap();
+        let mut tiff = tiff::decoder::Decoder::new(std::io::BufReader::new(file)).unwrap();
+        assert_eq!(tiff.get_tag_u8_vec(tiff::tags::Tag::IccProfile).unwrap(), icc);
+        let mut png = image::codecs::png::PngDecoder::new(std::io::BufReader::new(std::fs::File::open(dir.join("out.png")).unwrap())).unwrap();
+        assert_eq!(png.icc_profile().unwrap(), Some(icc));
+    }
+    
+    #[test]
+    fn test_variants_single_decode() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let input = dir.join("input.png");
+        image::RgbImage::from_fn(400, 200, |x, _| image::Rgb([(x % 256) as u8, 90, 160])).save(&input).unwrap();
+        
+        let spec = |name: &str, width, height, resize, format| VariantSpec {
+            name: name.to_string(),
+            width,
+            height,
+            resize,
+            format,
+            quality: 85,
+            output: dir.join(format!("{}.{}", name, format.extension())),
+        };
+        let specs = [
+            spec("thumb", 64, 64, ResizeMode::Fill, ImageOutputFormat::Jpeg),
+            spec("detail", 1000, 1000, ResizeMode::Fit, ImageOutputFormat::Png),
+            spec("grid", 100, 100, ResizeMode::Fit, ImageOutputFormat::Webp),
+        ];
+        
+        let processor = ImagePreprocessor::new(ImageConfig::default());
+        let outputs = processor.variants(&input, &specs, &RawDecode::Preview).unwrap();
+        
+        // Spec order is kept; Fit never upscales or pads, Fill crops to the box
+        let sizes: Vec<_> = outputs.iter().map(|o| (o.name.as_str(), o.width, o.height)).collect();
+        assert_eq!(sizes, [("thumb", 64, 64), ("detail", 400, 200), ("grid", 100, 50)]);
+        for output in &outputs {
+            assert!(output.bytes > 0);
+            let written = image::open(&output.output).unwrap();
+            assert_eq!((written.width(), written.height()), (output.width, output.height));
         }
     }
 }
@@ -0,0 +1,378 @@
+//! Video quality diagnostics for ingest QA
+//!
+//! Scans a video for black frames, frozen/duplicated frames, silent audio and
+//! interlacing using FFmpeg's detectors (`blackdetect`, `freezedetect`,
+//! `silencedetect`, `idet`) in a single decode pass, then scores sharpness per
+//! segment in Rust (variance of Laplacian on sampled grayscale frames).
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use soma_media::{VideoInspector, InspectConfig};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let report = VideoInspector::new(InspectConfig::default()).inspect("upload.mp4")?;
+//! if !report.black.is_empty() || !report.frozen.is_empty() {
+//!     println!("Flagged for review: {:?}", report.black);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::ffmpeg::{FfmpegCommand, FfmpegError};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Width sampled frames are scaled to before sharpness scoring
+const SHARPNESS_SAMPLE_WIDTH: u32 = 640;
+
+/// Detector thresholds for [`VideoInspector`]
+#[derive(Debug, Clone)]
+pub struct InspectConfig {
+    /// Minimum black run to report, in seconds
+    pub black_min_duration: f64,
+    /// Luma ratio below which a pixel counts as black (0.0-1.0)
+    pub black_pixel_threshold: f64,
+    /// Minimum frozen run to report, in seconds
+    pub freeze_min_duration: f64,
+    /// Noise tolerance for frozen-frame detection in dB
+    pub freeze_noise_db: f64,
+    /// Minimum silence to report, in seconds
+    pub silence_min_duration: f64,
+    /// Level below which audio counts as silent, in dB
+    pub silence_noise_db: f64,
+    /// Number of equal segments scored for sharpness (0 disables)
+    pub sharpness_segments: usize,
+    /// Variance-of-Laplacian score below which a segment is flagged blurry
+    pub blur_threshold: f64,
+}
+
+impl Default for InspectConfig {
+    fn default() -> Self {
+        Self {
+            black_min_duration: 0.5,
+            black_pixel_threshold: 0.10,
+            freeze_min_duration: 2.0,
+            freeze_noise_db: -60.0,
+            silence_min_duration: 2.0,
+            silence_noise_db: -50.0,
+            sharpness_segments: 10,
+            blur_threshold: 100.0,  // Common VoL cut-off for 8-bit frames
+        }
+    }
+}
+
+/// Time range in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: f64,
+    pub end: f64,
+    pub duration: f64,
+}
+
+impl TimeRange {
+    fn new(start: f64, end: f64) -> Self {
+        Self { start, end, duration: (end - start).max(0.0) }
+    }
+}
+
+/// Interlacing statistics from `idet` multi-frame detection
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InterlaceReport {
+    pub tff: u64,
+    pub bff: u64,
+    pub progressive: u64,
+    pub undetermined: u64,
+    /// True when interlaced frames outnumber progressive ones
+    pub interlaced: bool,
+}
+
+/// Sharpness score of one segment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentSharpness {
+    pub start: f64,
+    pub end: f64,
+    /// Variance of Laplacian of the frame sampled at the segment midpoint
+    pub sharpness: f64,
+    pub blurry: bool,
+}
+
+/// Result of [`VideoInspector::inspect`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VideoInspection {
+    pub duration: Option<f64>,
+    pub has_audio: bool,
+    pub black: Vec<TimeRange>,
+    pub frozen: Vec<TimeRange>,
+    pub silence: Vec<TimeRange>,
+    pub interlace: Option<InterlaceReport>,
+    pub sharpness: Vec<SegmentSharpness>,
+}
+
+impl VideoInspection {
+    /// True when any detector flagged a problem
+    pub fn has_issues(&self) -> bool {
+        !self.black.is_empty()
+            || !self.frozen.is_empty()
+            || !self.silence.is_empty()
+            || self.interlace.as_ref().map(|i| i.interlaced).unwrap_or(false)
+            || self.sharpness.iter().any(|s| s.blurry)
+    }
+}
+
+/// Video quality inspector
+pub struct VideoInspector {
+    config: InspectConfig,
+}
+
+impl VideoInspector {
+    pub fn new(config: InspectConfig) -> Self {
+        Self { config }
+    }
+    
+    /// Scan a video and report black, frozen, silent, interlaced and blurry sections
+    pub fn inspect(&self, video: impl AsRef<Path>) -> Result<VideoInspection, FfmpegError> {
+        let video = video.as_ref();
+        let meta = crate::metadata::extract_with_ffprobe(video).ok();
+        let duration = meta.as_ref().and_then(|m| m.duration);
+        let has_audio = meta.as_ref().map(|m| m.sample_rate.is_some() || m.channels.is_some()).unwrap_or(false);
+        
+        let video_filters = format!(
+            "blackdetect=d={}:pix_th={},freezedetect=n={}dB:d={},idet",
+            self.config.black_min_duration,
+            self.config.black_pixel_threshold,
+            self.config.freeze_noise_db,
+            self.config.freeze_min_duration,
+        );
+        
+        let mut cmd = FfmpegCommand::new()
+            .args(&["-hide_banner", "-nostats"])
+            .input(video)
+            .args(&["-vf", &video_filters]);
+        
+        if has_audio {
+            cmd = cmd.args(&[
+                "-af", &format!(
+                    "silencedetect=n={}dB:d={}",
+                    self.config.silence_noise_db,
+                    self.config.silence_min_duration,
+                ),
+            ]);
+        }
+        
+        let output = cmd.args(&["-f", "null"]).output("-").execute()?;
+        let log = String::from_utf8_lossy(&output.stderr);
+        
+        let end = duration.unwrap_or(0.0);
+        
+        Ok(VideoInspection {
+            duration,
+            has_audio,
+            black: parse_blackdetect(&log),
+            frozen: parse_freezedetect(&log, end),
+            silence: if has_audio { parse_silencedetect(&log, end) } else { Vec::new() },
+            interlace: parse_idet(&log),
+            sharpness: match duration {
+                Some(d) if d > 0.0 => self.score_sharpness(video, d)?,
+                _ => Vec::new(),
+            },
+        })
+    }
+    
+    /// Score sharpness at the midpoint of each segment
+    fn score_sharpness(&self, video: &Path, duration: f64) -> Result<Vec<SegmentSharpness>, FfmpegError> {
+        let segments = self.config.sharpness_segments;
+        let mut scores = Vec::with_capacity(segments);
+        
+        for i in 0..segments {
+            let start = duration * i as f64 / segments as f64;
+            let end = duration * (i + 1) as f64 / segments as f64;
+            let midpoint = (start + end) / 2.0;
+            
+            let output = FfmpegCommand::new()
+                .args(&["-ss", &format!("{:.3}", midpoint)])
+                .input(video)
+                .args(&[
+                    "-frames:v", "1",
+                    "-vf", &format!("scale={}:-2,format=gray", SHARPNESS_SAMPLE_WIDTH),
+                    "-f", "rawvideo",
+                ])
+                .output("-")
+                .execute()?;
+            
+            let gray = output.stdout;
+            let width = SHARPNESS_SAMPLE_WIDTH as usize;
+            if gray.len() < width * 3 {
+                continue;  // Seek past the last decodable frame
+            }
+            let height = gray.len() / width;
+            
+            let sharpness = laplacian_variance(&gray, width, height);
+            scores.push(SegmentSharpness {
+                start,
+                end,
+                sharpness,
+                blurry: sharpness < self.config.blur_threshold,
+            });
+        }
+        
+        Ok(scores)
+    }
+}
+
+/// Variance of the 4-neighbour Laplacian over an 8-bit grayscale image
+///
+/// Higher is sharper; blurred or out-of-focus frames score low.
+pub(crate) fn laplacian_variance(gray: &[u8], width: usize, height: usize) -> f64 {
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+    
+    let mut sum = 0.0f64;
+    let mut sum_sq = 0.0f64;
+    let mut count = 0usize;
+    
+    for y in 1..height - 1 {
+        let row = y * width;
+        for x in 1..width - 1 {
+            let center = gray[row + x] as f64;
+            let lap = gray[row + x - 1] as f64
+                + gray[row + x + 1] as f64
+                + gray[row - width + x] as f64
+                + gray[row + width + x] as f64
+                - 4.0 * center;
+            sum += lap;
+            sum_sq += lap * lap;
+            count += 1;
+        }
+    }
+    
+    let mean = sum / count as f64;
+    sum_sq / count as f64 - mean * mean
+}
+
+/// Read `key:value` from a detector log line
+fn log_value(line: &str, key: &str) -> Option<f64> {
+    let rest = line.split(key).nth(1)?;
+    rest.trim_start_matches([':', ' '])
+        .split(|c: char| c.is_whitespace() || c == '|')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Parse `blackdetect` ranges (`black_start:0 black_end:2.04 black_duration:2.04`)
+fn parse_blackdetect(log: &str) -> Vec<TimeRange> {
+    log.lines()
+        .filter(|line| line.contains("black_start"))
+        .filter_map(|line| {
+            let start = log_value(line, "black_start")?;
+            let end = log_value(line, "black_end")?;
+            Some(TimeRange::new(start, end))
+        })
+        .collect()
+}
+
+/// Parse `freezedetect` metadata log lines; an open freeze runs to `end`
+fn parse_freezedetect(log: &str, end: f64) -> Vec<TimeRange> {
+    let mut ranges = Vec::new();
+    let mut open: Option<f64> = None;
+    
+    for line in log.lines() {
+        if let Some(start) = log_value(line, "lavfi.freezedetect.freeze_start") {
+            open = Some(start);
+        } else if let Some(stop) = log_value(line, "lavfi.freezedetect.freeze_end") {
+            if let Some(start) = open.take() {
+                ranges.push(TimeRange::new(start, stop));
+            }
+        }
+    }
+    if let Some(start) = open {
+        ranges.push(TimeRange::new(start, end.max(start)));
+    }
+    
+    ranges
+}
+
+/// Parse `silencedetect` start/end pairs; an open silence runs to `end`
+fn parse_silencedetect(log: &str, end: f64) -> Vec<TimeRange> {
+    let mut ranges = Vec::new();
+    let mut open: Option<f64> = None;
+    
+    for line in log.lines() {
+        if let Some(start) = log_value(line, "silence_start") {
+            open = Some(start);
+        } else if let Some(stop) = log_value(line, "silence_end") {
+            if let Some(start) = open.take() {
+                ranges.push(TimeRange::new(start, stop));
+            }
+        }
+    }
+    if let Some(start) = open {
+        ranges.push(TimeRange::new(start, end.max(start)));
+    }
+    
+    ranges
+}
+
+/// Parse the `idet` multi-frame detection summary
+fn parse_idet(log: &str) -> Option<InterlaceReport> {
+    let line = log.lines().rev().find(|line| line.contains("Multi frame detection"))?;
+    
+    let tff = log_value(line, "TFF")? as u64;
+    let bff = log_value(line, "BFF")? as u64;
+    let progressive = log_value(line, "Progressive")? as u64;
+    let undetermined = log_value(line, "Undetermined").unwrap_or(0.0) as u64;
+    
+    Some(InterlaceReport {
+        tff,
+        bff,
+        progressive,
+        undetermined,
+        interlaced: tff + bff > progressive,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const LOG: &str = "\
+[blackdetect @ 0x5581] black_start:0 black_end:1.5 black_duration:1.5
+[freezedetect @ 0x5582] lavfi.freezedetect.freeze_start: 4.004
+[freezedetect @ 0x5582] lavfi.freezedetect.freeze_duration: 3.003
+[freezedetect @ 0x5582] lavfi.freezedetect.freeze_end: 7.007
+[freezedetect @ 0x5582] lavfi.freezedetect.freeze_start: 28.5
+[silencedetect @ 0x5583] silence_start: 10.2
+[silencedetect @ 0x5583] silence_end: 12.7 | silence_duration: 2.5
+[Parsed_idet_2 @ 0x5584] Repeated Fields: Neither:   750 Top:     0 Bottom:     0
+[Parsed_idet_2 @ 0x5584] Single frame detection: TFF:     3 BFF:     0 Progressive:   700 Undetermined:    47
+[Parsed_idet_2 @ 0x5584] Multi frame detection: TFF:     0 BFF:     0 Progressive:   748 Undetermined:     2
+";
+    
+    #[test]
+    fn test_parse_detectors() {
+        assert_eq!(parse_blackdetect(LOG), vec![TimeRange::new(0.0, 1.5)]);
+        assert_eq!(
+            parse_freezedetect(LOG, 30.0),
+            vec![TimeRange::new(4.004, 7.007), TimeRange::new(28.5, 30.0)]
+        );
+        assert_eq!(parse_silencedetect(LOG, 30.0), vec![TimeRange::new(10.2, 12.7)]);
+        
+        let idet = parse_idet(LOG).unwrap();
+        assert_eq!(idet.progressive, 748);
+        assert_eq!(idet.undetermined, 2);
+        assert!(!idet.interlaced);
+    }
+    
+    #[test]
+    fn test_laplacian_variance() {
+        let flat = vec![128u8; 32 * 32];
+        assert_eq!(laplacian_variance(&flat, 32, 32), 0.0);
+        
+        let checker: Vec<u8> = (0..32 * 32)
+            .map(|i| if (i % 32 + i / 32) % 2 == 0 { 0 } else { 255 })
+            .collect();
+        assert!(laplacian_variance(&checker, 32, 32) > 1000.0);
+    }
+}
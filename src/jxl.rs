@@ -0,0 +1,213 @@
+//! JPEG XL encoding and decoding
+//!
+//! Shells out to libjxl's `cjxl`/`djxl` tools (same approach as FFmpeg, no
+//! linking), falling back to FFmpeg's libjxl wrapper when they are missing.
+//!
+//! Existing JPEGs can be recompressed losslessly: `cjxl` stores the JPEG's
+//! DCT coefficients (~20% smaller) and `djxl` reconstructs the original file
+//! bit for bit.
+
+use crate::ffmpeg::{temp_path, FfmpegCommand, FfmpegError};
+use image::{DynamicImage, ImageEncoder};
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Bare codestream signature
+const CODESTREAM_MAGIC: [u8; 2] = [0xFF, 0x0A];
+
+/// ISOBMFF container signature (`JXL ` box)
+const CONTAINER_MAGIC: [u8; 12] = [0x00, 0x00, 0x00, 0x0C, b'J', b'X', b'L', b' ', 0x0D, 0x0A, 0x87, 0x0A];
+
+/// Encoder effort (1-10); 7 is libjxl's default trade-off
+const EFFORT: &str = "7";
+
+/// Check whether a file is JPEG XL (codestream or container)
+pub fn is_jxl(path: impl AsRef<Path>) -> bool {
+    let header = read_header(path.as_ref());
+    header.starts_with(&CODESTREAM_MAGIC) || header.starts_with(&CONTAINER_MAGIC)
+}
+
+/// Check whether a file is a JPEG (candidate for lossless recompression)
+pub(crate) fn is_jpeg(path: impl AsRef<Path>) -> bool {
+    read_header(path.as_ref()).starts_with(&[0xFF, 0xD8, 0xFF])
+}
+
+/// Check if libjxl's `cjxl` encoder is available on the system (probed once)
+pub fn jxl_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| tool_available("cjxl"))
+}
+
+/// Check if libjxl's `djxl` decoder is available on the system (probed once)
+fn djxl_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| tool_available("djxl"))
+}
+
+/// Encode an image as JPEG XL
+///
+/// `quality` follows `cjxl -q`: 100 is mathematically lossless, 90 is
+/// visually lossless, lower values trade detail for size.
+pub fn encode_jxl(img: &DynamicImage, quality: u8) -> Result<Vec<u8>, FfmpegError> {
+    encode_jxl_with_icc(img, quality, None)
+}
+
+/// Encode an image as JPEG XL tagged with an ICC profile (sRGB when `None`)
+pub fn encode_jxl_with_icc(img: &DynamicImage, quality: u8, icc: Option<&[u8]>) -> Result<Vec<u8>, FfmpegError> {
+    let input = temp_path("jxl", "png");
+    let output = temp_path("jxl", "jxl");
+    
+    let result = (|| {
+        // cjxl takes the colour encoding from the staged PNG's iCCP chunk
+        let mut encoder = image::codecs::png::PngEncoder::new(std::io::BufWriter::new(std::fs::File::create(&input)?));
+        if let Some(icc) = icc {
+            encoder.set_icc_profile(icc.to_vec())
+                .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to attach ICC profile: {}", e)))?;
+        }
+        img.write_with_encoder(encoder)
+            .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to stage image for cjxl: {}", e)))?;
+        
+        if jxl_available() {
+            run_tool(Command::new("cjxl")
+                .arg(&input)
+                .arg(&output)
+                .args(["-q", &quality.min(100).to_string(), "-e", EFFORT]))?;
+        } else {
+            FfmpegCommand::new()
+                .input(&input)
+                .args(&["-c:v", "libjxl", "-distance", &format!("{:.2}", jxl_distance(quality)), "-effort", EFFORT])
+                .output(&output)
+                .execute()?;
+        }
+        
+        Ok(std::fs::read(&output)?)
+    })();
+    
+    let _ = std::fs::remove_file(&input);
+    let _ = std::fs::remove_file(&output);
+    result
+}
+
+/// Decode a JPEG XL file (orientation from the codestream is applied)
+pub fn decode_jxl(path: impl AsRef<Path>) -> Result<DynamicImage, FfmpegError> {
+    decode_jxl_with_profile(path).map(|(image, _)| image)
+}
+
+/// Decode a JPEG XL file along with its ICC profile (libjxl backend only)
+pub(crate) fn decode_jxl_with_profile(path: impl AsRef<Path>) -> Result<(DynamicImage, Option<Vec<u8>>), FfmpegError> {
+    let path = path.as_ref();
+    let load = |bytes: &[u8]| {
+        image::load_from_memory_with_format(bytes, image::ImageFormat::Png)
+            .map_err(|e| FfmpegError::InvalidOutput(format!("Failed to decode {}: {}", path.display(), e)))
+    };
+    
+    if !djxl_available() {
+        let output = FfmpegCommand::new()
+            .input(path)
+            .args(&["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png"])
+            .output("-")
+            .execute()?;
+        return Ok((load(&output.stdout)?, None));
+    }
+    
+    let decoded = temp_path("jxl", "png");
+    let result = run_tool(Command::new("djxl").arg(path).arg(&decoded))
+        .and_then(|_| crate::color::open_with_profile(&decoded));
+    let _ = std::fs::remove_file(&decoded);
+    result
+}
+
+/// Losslessly recompress a JPEG into JPEG XL (reversible with [`reconstruct_jpeg`])
+pub fn recompress_jpeg(input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<(), FfmpegError> {
+    let input = input.as_ref();
+    if !is_jpeg(input) {
+        return Err(FfmpegError::InvalidOutput(format!("{} is not a JPEG", input.display())));
+    }
+    
+    run_tool(Command::new("cjxl")
+        .arg(input)
+        .arg(output.as_ref())
+        .args(["--lossless_jpeg=1", "-e", EFFORT]))
+}
+
+/// Reconstruct the original JPEG from a losslessly recompressed JPEG XL file
+pub fn reconstruct_jpeg(input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<(), FfmpegError> {
+    // djxl reconstructs the bitstream when asked for a .jpg output
+    run_tool(Command::new("djxl").arg(input.as_ref()).arg(output.as_ref()))
+}
+
+/// Butteraugli distance equivalent to `cjxl -q` (for FFmpeg's `-distance`)
+pub(crate) fn jxl_distance(quality: u8) -> f32 {
+    let quality = quality.min(100) as f32;
+    if quality >= 100.0 {
+        0.0
+    } else if quality >= 30.0 {
+        0.1 + (100.0 - quality) * 0.09
+    } else {
+        // Quadratic below 30, as in libjxl's cjxl
+        53.0 / 3000.0 * quality * quality - 23.0 / 20.0 * quality + 25.0
+    }
+}
+
+fn read_header(path: &Path) -> Vec<u8> {
+    let mut header = [0u8; 12];
+    match std::fs::File::open(path).and_then(|mut f| f.read(&mut header)) {
+        Ok(read) => header[..read].to_vec(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn tool_available(bin: &str) -> bool {
+    Command::new(bin)
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+fn run_tool(cmd: &mut Command) -> Result<(), FfmpegError> {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let output = cmd.output().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => FfmpegError::ExecutionFailed(format!("{} not found in system PATH", program)),
+        _ => FfmpegError::Io(e),
+    })?;
+    
+    if !output.status.success() {
+        return Err(FfmpegError::ExecutionFailed(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_signatures_and_distance() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        
+        let codestream = dir.join("a.jxl");
+        std::fs::write(&codestream, [0xFF, 0x0A, 0xFA, 0x1F]).unwrap();
+        let container = dir.join("b.jxl");
+        std::fs::write(&container, CONTAINER_MAGIC).unwrap();
+        let jpeg = dir.join("c.jpg");
+        std::fs::write(&jpeg, [0xFF, 0xD8, 0xFF, 0xE0]).unwrap();
+        
+        assert!(is_jxl(&codestream));
+        assert!(is_jxl(&container));
+        assert!(!is_jxl(&jpeg));
+        assert!(is_jpeg(&jpeg));
+        
+        assert_eq!(jxl_distance(100), 0.0);
+        assert!((jxl_distance(90) - 1.0).abs() < 1e-4);
+        assert!(jxl_distance(50) > jxl_distance(90));
+    }
+}
@@ -12,9 +12,10 @@
 //!
 //! - **Audio Processing**: Format conversion, mel spectrograms for CLAP/Whisper
 //! - **Video Processing**: Frame extraction for CLIP and vision models
-//! - **Image Processing**: Format conversion, resizing, quality control
-//! - **RAW Processing**: Fast preview extraction (11-38x faster), metadata extraction
+//! - **Image Processing**: Format conversion, resizing, quality control, HEIC/HEIF/AVIF input, JPEG XL, ICC colour management, logo/text watermarks
+//! - **RAW Processing**: Fast preview extraction (11-38x faster), metadata extraction, 16-bit TIFF/PNG conversions
 //! - **GPU Acceleration**: Automatic CUDA → Vulkan → CPU cascade (optional)
+//! - **In-Memory I/O**: `_from_memory` / `_stream` variants take bytes or `Read` and return bytes or write to `Write`, piping through FFmpeg's stdin/stdout instead of staging files (HEIC/HEIF/AVIF, which libheif reads from a file, excepted)
 //! - **UMA Compliant**: Full Universal Module Architecture support
 //!
 //! ## Usage
@@ -73,7 +74,20 @@
 //! - `audio.preprocess` - Convert audio to target format/sample rate
 //! - `audio.mel_spectrogram` - Generate mel spectrograms
 //! - `video.extract_frames` - Extract frames at specified FPS
+//! - `video.batch_extract_frames` - Batch frame extraction with bounded concurrency
+//! - `video.transcode` - Video transcoding with orientation/crop/HDR fixes and watermarks
+//! - `video.batch_transcode` - Batch video transcoding with bounded concurrency
+//! - `video.from_images` - Encode timelapse/slideshow video from an image or RAW sequence
+//! - `video.inspect` - Detect black/frozen/silent/interlaced/blurry sections
 //! - `image.preprocess` - Convert/resize images
+//! - `image.variants` - Thumb/grid/detail/ML derivatives from a single decode
+//! - `image.tiles` - DeepZoom and IIIF level-0 tile pyramids for pan/zoom viewers
+//! - `image.colors` - Dominant colours (OKLab k-means / median cut), average colour, colourfulness and light/dark tone for images, RAW previews and video storyboards
+//! - `image.placeholder` - BlurHash and ThumbHash strings for blurred stand-ins while the real image loads (also an option on image.preprocess, raw.preview and frame extraction)
+//! - `image.to_tensor` - Normalized float32 tensors for model input (.npy/.safetensors)
+//! - `image.phash` - Perceptual image hashes (pHash, dHash, aHash, wavelet)
+//! - `image.dedupe` - Cluster near-duplicate images by Hamming distance
+//! - `image.quality` - Sharpness, exposure clipping, noise and aesthetic scores for culling
 //! - `raw.preview` - Fast RAW preview extraction (WebP Q92)
 //! - `raw.metadata` - Extract EXIF/camera metadata
 //! - `media.capabilities` - Get organ capability card
@@ -113,9 +127,25 @@
 //! └─────────────────────────────────────────────────┘
 //! ```
 
+mod animation;
 mod audio;
 mod video;
+mod inspect;
+mod palette;
+mod phash;
+mod placeholder;
+mod quality;
+mod sequence;
+mod geometry;
+mod smartcrop;
+mod tensor;
+mod tiles;
+mod heif;
+mod jxl;
+mod color;
 mod image;
+mod metadata_policy;
+mod watermark;
 mod ffmpeg;
 mod raw;
 mod validation;
@@ -140,15 +170,31 @@ pub mod error;
 pub use metrics::{Metrics, MetricsSnapshot};
 
 pub use audio::{AudioPreprocessor, AudioConfig, MelSpectrogram, AudioFormat};
-pub use video::{VideoPreprocessor, VideoConfig, VideoFrame};
-pub use image::{ImagePreprocessor, ImageConfig, ImageOutputFormat};
-pub use ffmpeg::{FfmpegCommand, FfmpegError};
-pub use raw::{RawProcessor, RawOptions, WhiteBalance, ColorSpace, PreviewOptions};
+pub use video::{VideoPreprocessor, VideoConfig, VideoFrame, VideoSourceInfo, FrameExtraction, ToneMapOperator, FrameDedupe, DedupeMethod, DroppedFrame, VideoCodec, TranscodeConfig, TranscodeReport, default_batch_concurrency};
+pub use inspect::{VideoInspector, InspectConfig, VideoInspection, TimeRange, InterlaceReport, SegmentSharpness};
+pub use image::{ImagePreprocessor, ImageConfig, ImageOutputFormat, ImageBackend, PreprocessReport, VariantSpec, VariantOutput};
+pub use animation::{AnimatedFormat, AnimationInfo, AnimationMode, AnimationFrame, AnimationOutput, probe_animation, is_animated, decode_frames, representative_frame, sample_frames, encode_animation};
+pub use heif::{HeifImage, decode_heif, decode_heif_from_memory, is_heif, heif_dec_available};
+pub use color::{TargetColorSpace, convert_to_target};
+pub use metadata_policy::{MetadataPolicy, apply_metadata_policy};
+pub use watermark::{Watermark, WatermarkSource, WatermarkAnchor, RenderedWatermark};
+pub use jxl::{encode_jxl, encode_jxl_with_icc, decode_jxl, is_jxl, jxl_available, recompress_jpeg, reconstruct_jpeg};
+pub use phash::{HashAlgorithm, hamming_distance, cluster_hashes};
+pub use quality::{QualityAnalyzer, QualityConfig, QualityReport};
+pub use palette::{ColorAnalyzer, ColorConfig, ColorReport, PaletteMethod, Swatch};
+pub use placeholder::{Placeholders, placeholders, blurhash, thumbhash};
+pub use tensor::{TensorConverter, TensorConfig, TensorPreset, TensorLayout, ChannelOrder, Tensor};
+pub use tiles::{TileGenerator, TileConfig, TileLayout, TileReport};
+pub use ffmpeg::{FfmpegCommand, FfmpegError, FfmpegProcess};
+pub use smartcrop::{FocusRegion, smart_crop, smart_crop_rect, focus_hints};
+pub use sequence::{SequenceEncoder, SequenceConfig, SequenceReport, DngSequence, expand_glob};
+pub use raw::{RawProcessor, RawOptions, WhiteBalance, ColorSpace, PreviewOptions, RawDecode};
+pub use geometry::{ResizeMode, CropRect};
 
 // Universal metadata extraction (ExifTool + fallbacks)
 pub use metadata::{
     MediaMetadata, RawMetadata,  // RawMetadata is alias for backward compatibility
-    extract_metadata, exiftool_available, ffprobe_available,
+    extract_metadata, extract_metadata_from_memory, exiftool_available, ffprobe_available,
     detect_mime_from_extension,
     GpsCoordinates, LensInfo, ExposureInfo, ColorInfo, Dimensions,
     SensorInfo, ShootingInfo, MetadataBackend,
@@ -88,6 +88,11 @@ pub struct MediaMetadata {
     /// Image orientation (1-8, EXIF standard)
     pub orientation: Option<u8>,
     
+    /// Display rotation in degrees clockwise (0, 90, 180, 270) from the
+    /// container rotate tag or display matrix (video). When set to 90/270,
+    /// `dimensions` already reports the upright (display) size.
+    pub rotation: Option<u16>,
+    
     // ---- Video/Audio Metadata ----
     
     /// Duration in seconds
@@ -108,6 +113,14 @@ pub struct MediaMetadata {
     /// Number of audio channels
     pub channels: Option<u8>,
     
+    // ---- Animated Image Metadata ----
+    
+    /// Frames in a GIF/APNG/animated WebP (`duration` holds one play)
+    pub frame_count: Option<u32>,
+    
+    /// Animation plays, 0 = loops forever
+    pub loop_count: Option<u32>,
+    
     // ---- Document Metadata ----
     
     /// Title
@@ -183,6 +196,10 @@ pub struct ColorInfo {
     pub color_profile: Option<String>,
     pub white_balance: Option<String>,
     pub color_temperature: Option<u32>,
+    /// Transfer characteristics (video, e.g. "bt709", "smpte2084", "arib-std-b67")
+    pub transfer_characteristics: Option<String>,
+    /// Colour primaries (video, e.g. "bt709", "bt2020")
+    pub color_primaries: Option<String>,
 }
 
 /// Metadata extraction backend
@@ -269,6 +286,53 @@ pub struct ShootingInfo {
 /// println!("Camera: {:?}", meta.make);
 /// ```
 pub fn extract_metadata(path: &Path) -> Result<MediaMetadata> {
+    let mut meta = extract_with_backends(path)?;
+    
+    // Animation timing straight from the GIF/PNG/WebP container
+    if let Some(animation) = crate::animation::probe_animation(path) {
+        meta.frame_count = Some(animation.frame_count);
+        meta.loop_count = Some(animation.loop_count);
+        if animation.is_animated() {
+            meta.duration = Some(animation.duration);
+        }
+    }
+    Ok(meta)
+}
+
+/// Extract metadata from a file already in memory (e.g. an object storage upload)
+///
+/// ExifTool reads the bytes from stdin; without it, RAW data goes to LibRaw
+/// and other images to kamadak-exif. The MIME type falls back to magic-byte
+/// sniffing and `source_file` is left empty.
+pub fn extract_metadata_from_memory(data: &[u8]) -> Result<MediaMetadata> {
+    let mut meta = extract_with_exiftool_stdin(data)
+        .or_else(|_| libraw_metadata(data))
+        .or_else(|_| kamadak_metadata(&mut std::io::Cursor::new(data), data.len() as u64))
+        .unwrap_or_else(|_| MediaMetadata {
+            file_size: data.len() as u64,
+            backend: MetadataBackend::FileMagic,
+            ..Default::default()
+        });
+    
+    if let Some(kind) = infer::get(data) {
+        if meta.mime_type.is_empty() {
+            meta.mime_type = kind.mime_type().to_string();
+        }
+        if meta.file_type.is_empty() {
+            meta.file_type = kind.extension().to_uppercase();
+        }
+    }
+    if let Some(animation) = crate::animation::probe_bytes(data) {
+        meta.frame_count = Some(animation.frame_count);
+        meta.loop_count = Some(animation.loop_count);
+        if animation.is_animated() {
+            meta.duration = Some(animation.duration);
+        }
+    }
+    Ok(meta)
+}
+
+fn extract_with_backends(path: &Path) -> Result<MediaMetadata> {
     // Try ExifTool first (most comprehensive)
     if let Ok(meta) = extract_with_exiftool(path) {
         return Ok(meta);
@@ -333,12 +397,7 @@ pub fn ffprobe_available() -> bool {
 
 /// Extract metadata using ExifTool (most comprehensive)
 fn extract_with_exiftool(path: &Path) -> Result<MediaMetadata> {
-    let output = Command::new("exiftool")
-        .arg("-j")           // JSON output
-        .arg("-G")           // Group names
-        .arg("-n")           // Numeric values (not formatted)
-        .arg("-a")           // Allow duplicates
-        .arg("-u")           // Unknown tags
+    let output = exiftool_json_command()
         .arg(path)
         .output()
         .map_err(|e| MediaError::ProcessingError(format!("ExifTool failed: {}", e)))?;
@@ -349,14 +408,42 @@ fn extract_with_exiftool(path: &Path) -> Result<MediaMetadata> {
         ));
     }
     
-    let json_str = String::from_utf8_lossy(&output.stdout);
+    let tags = parse_exiftool_json(&output.stdout)?;
+    Ok(parse_exiftool_output(path, tags))
+}
+
+/// Extract metadata with ExifTool reading the file from stdin
+fn extract_with_exiftool_stdin(data: &[u8]) -> Result<MediaMetadata> {
+    let mut command = exiftool_json_command();
+    command.arg("-");
+    
+    let mut stdout = Vec::new();
+    crate::ffmpeg::pipe_command(command, data, &mut stdout)
+        .map_err(|e| MediaError::ProcessingError(format!("ExifTool failed: {}", e)))?;
+    
+    let tags = parse_exiftool_json(&stdout)?;
+    Ok(parse_exiftool_output(Path::new(""), tags))
+}
+
+fn exiftool_json_command() -> Command {
+    let mut command = Command::new("exiftool");
+    command
+        .arg("-j")           // JSON output
+        .arg("-G")           // Group names
+        .arg("-n")           // Numeric values (not formatted)
+        .arg("-a")           // Allow duplicates
+        .arg("-u");          // Unknown tags
+    command
+}
+
+/// First file's tags from `exiftool -j` output
+fn parse_exiftool_json(stdout: &[u8]) -> Result<HashMap<String, serde_json::Value>> {
+    let json_str = String::from_utf8_lossy(stdout);
     let parsed: Vec<HashMap<String, serde_json::Value>> = serde_json::from_str(&json_str)
         .map_err(|e| MediaError::ProcessingError(format!("JSON parse error: {}", e)))?;
     
-    let tags = parsed.into_iter().next()
-        .ok_or_else(|| MediaError::ProcessingError("No metadata found".to_string()))?;
-    
-    Ok(parse_exiftool_output(path, tags))
+    parsed.into_iter().next()
+        .ok_or_else(|| MediaError::ProcessingError("No metadata found".to_string()))
 }
 
 /// Parse ExifTool JSON output into MediaMetadata
@@ -379,6 +466,11 @@ fn parse_exiftool_output(path: &Path, tags: HashMap<String, serde_json::Value>)
         tags.get(key).and_then(|v| v.as_u64().map(|n| n as u32).or_else(|| v.as_str().and_then(|s| s.parse().ok())))
     };
     
+    // Video rotation (QuickTime track matrix, degrees clockwise)
+    let rotation = get_f64("QuickTime:Rotation")
+        .or(get_f64("Composite:Rotation"))
+        .map(normalize_rotation);
+
     // Parse dimensions
     let dimensions = match (get_u32("EXIF:ImageWidth").or(get_u32("File:ImageWidth")),
                            get_u32("EXIF:ImageHeight").or(get_u32("File:ImageHeight"))) {
@@ -387,7 +479,11 @@ fn parse_exiftool_output(path: &Path, tags: HashMap<String, serde_json::Value>)
             height: h,
             bit_depth: get_u32("EXIF:BitsPerSample").map(|b| b as u8),
         }),
-        _ => None,
+        // QuickTime track size is pre-rotation, report the display size
+        _ => match (get_u32("QuickTime:ImageWidth"), get_u32("QuickTime:ImageHeight")) {
+            (Some(w), Some(h)) => Some(display_dimensions(w, h, rotation)),
+            _ => None,
+        },
     };
     
     // Parse exposure info
@@ -430,6 +526,7 @@ fn parse_exiftool_output(path: &Path, tags: HashMap<String, serde_json::Value>)
         color_profile: get_str("ICC_Profile:ProfileDescription"),
         white_balance: get_str("EXIF:WhiteBalance"),
         color_temperature: get_u32("MakerNotes:ColorTemperature"),
+        ..Default::default()
     };
     
     // Build raw_tags from all exiftool output
@@ -453,12 +550,16 @@ fn parse_exiftool_output(path: &Path, tags: HashMap<String, serde_json::Value>)
         gps,
         color: Some(color),
         orientation: get_u32("EXIF:Orientation").map(|v| v as u8),
+        rotation,
         duration: get_f64("QuickTime:Duration").or(get_f64("Composite:Duration")),
         codec: get_str("QuickTime:CompressorID").or(get_str("File:FileType")),
         bitrate: get_f64("Composite:AvgBitrate").map(|v| v as u64),
-        frame_rate: get_f64("QuickTime:VideoFrameRate"),
+        frame_rate: get_f64("QuickTime:VideoFrameRate").or(get_f64("EXIF:FrameRate")),
         sample_rate: get_u32("QuickTime:AudioSampleRate"),
         channels: get_u32("QuickTime:AudioChannels").map(|v| v as u8),
+        // Filled from the container by `extract_metadata`
+        frame_count: None,
+        loop_count: None,
         title: get_str("XMP:Title").or(get_str("IPTC:ObjectName")),
         artist: get_str("EXIF:Artist").or(get_str("XMP:Creator")),
         description: get_str("EXIF:ImageDescription").or(get_str("XMP:Description")),
@@ -497,10 +598,20 @@ fn extract_with_kamadak_exif(path: &Path) -> Result<MediaMetadata> {
     
     let file = File::open(path).map_err(MediaError::Io)?;
     let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
-    let mut reader = BufReader::new(file);
+    let meta = kamadak_metadata(&mut BufReader::new(file), file_size)?;
     
+    Ok(MediaMetadata {
+        source_file: path.to_string_lossy().to_string(),
+        mime_type: detect_mime_from_extension(path),
+        file_type: path.extension().map(|e| e.to_string_lossy().to_uppercase()).unwrap_or_default(),
+        ..meta
+    })
+}
+
+/// kamadak-exif fields of an image container (no file name or type)
+fn kamadak_metadata<R: std::io::BufRead + std::io::Seek>(reader: &mut R, file_size: u64) -> Result<MediaMetadata> {
     let exif_data = exif::Reader::new()
-        .read_from_container(&mut reader)
+        .read_from_container(reader)
         .map_err(|e| MediaError::ProcessingError(format!("EXIF parse error: {}", e)))?;
     
     let get_str = |tag: exif::Tag| -> Option<String> {
@@ -562,9 +673,6 @@ fn extract_with_kamadak_exif(path: &Path) -> Result<MediaMetadata> {
     let gps = parse_exif_gps(&exif_data);
     
     Ok(MediaMetadata {
-        source_file: path.to_string_lossy().to_string(),
-        mime_type: detect_mime_from_extension(path),
-        file_type: path.extension().map(|e| e.to_string_lossy().to_uppercase()).unwrap_or_default(),
         file_size,
         date_created: get_str(exif::Tag::DateTimeOriginal),
         date_modified: get_str(exif::Tag::DateTime),
@@ -580,11 +688,34 @@ fn extract_with_kamadak_exif(path: &Path) -> Result<MediaMetadata> {
             ..Default::default()
         }),
         orientation: get_u32(exif::Tag::Orientation).map(|v| v as u8),
+        frame_rate: exif_frame_rate(&exif_data),
         backend: MetadataBackend::KamadakExif,
         ..Default::default()
     })
 }
 
+/// CinemaDNG `FrameRate` tag (0xC764, SRATIONAL in IFD0)
+const DNG_FRAME_RATE_TAG: u16 = 0xC764;
+
+fn exif_frame_rate(exif_data: &exif::Exif) -> Option<f64> {
+    let tag = exif::Tag(exif::Context::Tiff, DNG_FRAME_RATE_TAG);
+    exif_data.get_field(tag, exif::In::PRIMARY).and_then(|f| {
+        match f.value {
+            exif::Value::SRational(ref v) if !v.is_empty() => Some(v[0].to_f64()),
+            exif::Value::Rational(ref v) if !v.is_empty() => Some(v[0].to_f64()),
+            _ => None,
+        }
+    }).filter(|fps| *fps > 0.0)
+}
+
+/// Frame rate recorded in a CinemaDNG frame, read without external tools
+pub fn dng_frame_rate(path: &Path) -> Option<f64> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut reader = std::io::BufReader::new(file);
+    let exif_data = exif::Reader::new().read_from_container(&mut reader).ok()?;
+    exif_frame_rate(&exif_data)
+}
+
 /// Parse GPS from EXIF fields
 fn parse_exif_gps(exif_data: &exif::Exif) -> Option<GpsCoordinates> {
     let lat = exif_data.get_field(exif::Tag::GPSLatitude, exif::In::PRIMARY)?;
@@ -631,13 +762,25 @@ fn parse_exif_gps(exif_data: &exif::Exif) -> Option<GpsCoordinates> {
 
 /// Extract metadata using LibRaw (for RAW files)
 fn extract_with_libraw(path: &Path) -> Result<MediaMetadata> {
+    let file_data = std::fs::read(path).map_err(MediaError::Io)?;
+    let meta = libraw_metadata(&file_data)?;
+    
+    Ok(MediaMetadata {
+        source_file: path.to_string_lossy().to_string(),
+        mime_type: detect_mime_from_extension(path),
+        file_type: path.extension().map(|e| e.to_string_lossy().to_uppercase()).unwrap_or_default(),
+        ..meta
+    })
+}
+
+/// LibRaw fields of RAW data in memory (no file name or type)
+fn libraw_metadata(file_data: &[u8]) -> Result<MediaMetadata> {
     use rsraw::RawImage;
     use rsraw_sys as sys;
     
-    let file_data = std::fs::read(path).map_err(MediaError::Io)?;
     let file_size = file_data.len() as u64;
     
-    let raw = RawImage::open(&file_data)
+    let raw = RawImage::open(file_data)
         .map_err(|e| MediaError::ProcessingError(format!("LibRaw error: {:?}", e)))?;
     
     let raw_ptr: *mut sys::libraw_data_t = unsafe { std::mem::transmute_copy(&raw) };
@@ -701,9 +844,6 @@ fn extract_with_libraw(path: &Path) -> Result<MediaMetadata> {
         };
         
         Ok(MediaMetadata {
-            source_file: path.to_string_lossy().to_string(),
-            mime_type: detect_mime_from_extension(path),
-            file_type: path.extension().map(|e| e.to_string_lossy().to_uppercase()).unwrap_or_default(),
             file_size,
             date_created: if other.timestamp > 0 {
                 Some(format_timestamp(other.timestamp as i64))
@@ -733,7 +873,7 @@ fn extract_with_libraw(path: &Path) -> Result<MediaMetadata> {
 // ============================================================================
 
 /// Extract metadata using FFprobe (for video/audio)
-fn extract_with_ffprobe(path: &Path) -> Result<MediaMetadata> {
+pub(crate) fn extract_with_ffprobe(path: &Path) -> Result<MediaMetadata> {
     let output = Command::new("ffprobe")
         .args([
             "-v", "quiet",
@@ -783,11 +923,13 @@ fn extract_with_ffprobe(path: &Path) -> Result<MediaMetadata> {
         .and_then(|b| b.as_str())
         .and_then(|s| s.parse::<u64>().ok());
     
-    // Video info
+    // Video info (ffprobe reports the coded size, swap for rotated streams)
+    let rotation = video_stream.and_then(ffprobe_stream_rotation);
+
     let dimensions = video_stream.and_then(|v| {
         let w = v.get("width").and_then(|x| x.as_u64()).map(|x| x as u32)?;
         let h = v.get("height").and_then(|x| x.as_u64()).map(|x| x as u32)?;
-        Some(Dimensions { width: w, height: h, bit_depth: None })
+        Some(display_dimensions(w, h, rotation))
     });
     
     let frame_rate = video_stream.and_then(|v| {
@@ -803,6 +945,20 @@ fn extract_with_ffprobe(path: &Path) -> Result<MediaMetadata> {
         })
     });
     
+    let get_stream_str = |key: &str| -> Option<String> {
+        video_stream.and_then(|v| v.get(key))
+            .and_then(|v| v.as_str())
+            .filter(|s| *s != "unknown")
+            .map(|s| s.to_string())
+    };
+    
+    let color = video_stream.map(|_| ColorInfo {
+        color_space: get_stream_str("color_space"),
+        transfer_characteristics: get_stream_str("color_transfer"),
+        color_primaries: get_stream_str("color_primaries"),
+        ..Default::default()
+    });
+    
     let codec = video_stream
         .or(audio_stream)
         .and_then(|s| s.get("codec_name"))
@@ -837,6 +993,8 @@ fn extract_with_ffprobe(path: &Path) -> Result<MediaMetadata> {
         description: get_format_tags("description").or(get_format_tags("comment")),
         copyright: get_format_tags("copyright"),
         dimensions,
+        rotation,
+        color,
         duration,
         codec,
         bitrate,
@@ -930,6 +1088,46 @@ pub fn detect_mime_from_extension(path: &Path) -> String {
     }.to_string()
 }
 
+/// Read the display rotation of an ffprobe stream (degrees clockwise)
+///
+/// Older FFmpeg builds expose a `rotate` tag (clockwise), newer ones only
+/// emit a "Display Matrix" side data entry whose `rotation` is counter-clockwise.
+fn ffprobe_stream_rotation(stream: &serde_json::Value) -> Option<u16> {
+    let matrix = stream.get("side_data_list")
+        .and_then(|s| s.as_array())
+        .and_then(|list| list.iter().find_map(|sd| {
+            if sd.get("side_data_type").and_then(|t| t.as_str()) != Some("Display Matrix") {
+                return None;
+            }
+            sd.get("rotation").and_then(|r| r.as_f64().or_else(|| r.as_str().and_then(|s| s.parse().ok())))
+        }));
+
+    if let Some(ccw) = matrix {
+        return Some(normalize_rotation(-ccw));
+    }
+
+    stream.get("tags")
+        .and_then(|t| t.get("rotate"))
+        .and_then(|r| r.as_str())
+        .and_then(|s| s.parse::<f64>().ok())
+        .map(normalize_rotation)
+}
+
+/// Snap a rotation angle to 0/90/180/270 degrees clockwise
+fn normalize_rotation(degrees: f64) -> u16 {
+    let quarter_turns = (degrees / 90.0).round() as i64;
+    (quarter_turns.rem_euclid(4) * 90) as u16
+}
+
+/// Upright dimensions for a coded frame size and clockwise rotation
+fn display_dimensions(width: u32, height: u32, rotation: Option<u16>) -> Dimensions {
+    let (width, height) = match rotation {
+        Some(90) | Some(270) => (height, width),
+        _ => (width, height),
+    };
+    Dimensions { width, height, bit_depth: None }
+}
+
 /// Format Unix timestamp to ISO 8601
 fn format_timestamp(ts: i64) -> String {
     chrono::DateTime::from_timestamp(ts, 0)
@@ -989,6 +1187,14 @@ impl MediaMetadata {
         (self.duration.is_some() && self.sample_rate.is_some() && !self.is_video())
     }
     
+    /// Check if the video uses an HDR transfer (PQ / HDR10 / Dolby Vision 8.1 or HLG)
+    pub fn is_hdr(&self) -> bool {
+        matches!(
+            self.color.as_ref().and_then(|c| c.transfer_characteristics.as_deref()),
+            Some("smpte2084") | Some("arib-std-b67")
+        )
+    }
+    
     /// Check if this is an image file
     pub fn is_image(&self) -> bool {
         self.mime_type.starts_with("image/")
@@ -1045,6 +1251,50 @@ mod tests {
         assert_eq!(parse_shutter_speed(None), None);
     }
     
+    #[test]
+    fn test_ffprobe_rotation() {
+        use serde_json::json;
+
+        // FFmpeg 5+: display matrix side data (counter-clockwise)
+        let stream = json!({
+            "width": 1920, "height": 1080,
+            "side_data_list": [{ "side_data_type": "Display Matrix", "rotation": -90 }]
+        });
+        assert_eq!(ffprobe_stream_rotation(&stream), Some(90));
+
+        // Older FFmpeg: rotate tag (clockwise)
+        let stream = json!({ "tags": { "rotate": "270" } });
+        assert_eq!(ffprobe_stream_rotation(&stream), Some(270));
+
+        let stream = json!({ "width": 1920, "height": 1080 });
+        assert_eq!(ffprobe_stream_rotation(&stream), None);
+
+        let dims = display_dimensions(1920, 1080, Some(90));
+        assert_eq!((dims.width, dims.height), (1080, 1920));
+        assert_eq!(normalize_rotation(-180.0), 180);
+    }
+
+    #[test]
+    fn test_metadata_from_memory() {
+        use image::codecs::gif::GifEncoder;
+        use image::{Delay, Frame, Rgba, RgbaImage};
+        
+        let mut gif = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut gif);
+            for shade in [0u8, 255] {
+                let frame = Frame::from_parts(RgbaImage::from_pixel(8, 8, Rgba([shade, 0, 0, 255])), 0, 0, Delay::from_numer_denom_ms(100, 1));
+                encoder.encode_frame(frame).unwrap();
+            }
+        }
+        
+        let meta = extract_metadata_from_memory(&gif).unwrap();
+        assert_eq!(meta.mime_type, "image/gif");
+        assert_eq!(meta.frame_count, Some(2));
+        assert_eq!(meta.duration, Some(0.2));
+        assert!(meta.source_file.is_empty());
+    }
+    
     #[test]
     fn test_aspect_ratio() {
         let meta = MediaMetadata {
@@ -1084,5 +1334,16 @@ mod tests {
         };
         assert!(raw.is_raw());
         assert!(raw.is_image());
+        
+        let hlg = MediaMetadata {
+            color: Some(ColorInfo {
+                transfer_characteristics: Some("arib-std-b67".to_string()),
+                color_primaries: Some("bt2020".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(hlg.is_hdr());
+        assert!(!video.is_hdr());
     }
 }
@@ -0,0 +1,228 @@
+//! Metadata policy for derivative outputs
+//!
+//! Outputs are encoded from pixels, so they start out without EXIF/XMP/IPTC
+//! (ICC embedding is governed separately by `embed_icc`). A policy other than
+//! [`MetadataPolicy::Strip`] copies tags from the source afterwards with
+//! ExifTool, which writes EXIF and XMP into JPEG, PNG, WebP and AVIF alike:
+//!
+//! - `Whitelist`: attribution (artist/creator, copyright/rights, credit) and
+//!   capture date only, for client deliverables
+//! - `Scrub`: everything except location (GPS and IPTC/XMP place names),
+//!   serial numbers, owner name and maker notes (which carry serials too),
+//!   for public derivatives
+//!
+//! Both write `Orientation = 1` when the pixels were already rotated upright,
+//! and never copy the source ICC profile over the converted output's.
+
+use crate::ffmpeg::FfmpegError;
+use std::path::Path;
+use std::process::Command;
+
+/// Which source metadata a derivative carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetadataPolicy {
+    /// No EXIF/XMP/IPTC (default)
+    #[default]
+    Strip,
+    /// Copyright, creator and capture date
+    Whitelist,
+    /// Everything except location, serial numbers and owner name
+    Scrub,
+}
+
+impl MetadataPolicy {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "strip" | "none" => Some(Self::Strip),
+            "whitelist" | "attribution" => Some(Self::Whitelist),
+            "scrub" | "public" => Some(Self::Scrub),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Strip => "strip",
+            Self::Whitelist => "whitelist",
+            Self::Scrub => "scrub",
+        }
+    }
+}
+
+/// Attribution and capture-date tags kept by `Whitelist`
+const WHITELIST_TAGS: &[&str] = &[
+    "EXIF:Artist",
+    "EXIF:Copyright",
+    "EXIF:DateTimeOriginal",
+    "EXIF:SubSecTimeOriginal",
+    "EXIF:OffsetTimeOriginal",
+    "XMP-dc:Creator",
+    "XMP-dc:Rights",
+    "XMP-xmpRights:all",
+    "XMP-photoshop:Credit",
+    "XMP-photoshop:DateCreated",
+    "IPTC:By-line",
+    "IPTC:CopyrightNotice",
+    "IPTC:Credit",
+    "IPTC:DateCreated",
+    "IPTC:TimeCreated",
+];
+
+/// Tags `Scrub` leaves behind
+const PRIVATE_TAGS: &[&str] = &[
+    "GPS:all",
+    "XMP-exif:GPS*",
+    "IPTC:City",
+    "IPTC:Sub-location",
+    "IPTC:Province-State",
+    "IPTC:Country-PrimaryLocationName",
+    "XMP-photoshop:City",
+    "XMP-photoshop:State",
+    "XMP-photoshop:Country",
+    "XMP-iptcCore:Location",
+    "XMP-iptcExt:LocationCreated",
+    "XMP-iptcExt:LocationShown",
+    "SerialNumber",
+    "InternalSerialNumber",
+    "BodySerialNumber",
+    "CameraSerialNumber",
+    "LensSerialNumber",
+    "XMP-aux:SerialNumber",
+    "XMP-aux:LensSerialNumber",
+    "OwnerName",
+    "CameraOwnerName",
+    "MakerNotes:all",
+];
+
+/// Tags that describe the source file rather than the derivative
+const STALE_TAGS: &[&str] = &[
+    "ICC_Profile:all",
+    "ThumbnailImage",
+    "PreviewImage",
+    "JpgFromRaw",
+    "ExifImageWidth",
+    "ExifImageHeight",
+];
+
+/// Copy `source` metadata into the already written `output` per `policy`
+///
+/// `upright` means the output pixels had the source orientation applied, so
+/// the output is tagged `Orientation = 1`; otherwise the source orientation
+/// is kept. `Strip` does nothing. Other policies need ExifTool.
+pub fn apply_metadata_policy(source: &Path, output: &Path, policy: MetadataPolicy, upright: bool) -> Result<(), FfmpegError> {
+    let args = match exiftool_args(policy, upright) {
+        Some(args) => args,
+        None => return Ok(()),
+    };
+    
+    let result = Command::new("exiftool")
+        .args(["-m", "-q", "-overwrite_original", "-tagsFromFile"])
+        .arg(source)
+        .args(&args)
+        .arg(output)
+        .output()
+        .map_err(|e| FfmpegError::ExecutionFailed(format!("ExifTool is required for the {} metadata policy: {}", policy.as_str(), e)))?;
+    
+    if !result.status.success() {
+        return Err(FfmpegError::ExecutionFailed(format!(
+            "ExifTool failed to write metadata to {}: {}",
+            output.display(),
+            String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Copy/exclude arguments following `-tagsFromFile <source>`
+fn exiftool_args(policy: MetadataPolicy, upright: bool) -> Option<Vec<String>> {
+    let mut args: Vec<String> = match policy {
+        MetadataPolicy::Strip => return None,
+        MetadataPolicy::Whitelist => WHITELIST_TAGS.iter().map(|t| format!("-{}", t)).collect(),
+        MetadataPolicy::Scrub => std::iter::once("-all:all".to_string())
+            .chain(PRIVATE_TAGS.iter().chain(STALE_TAGS).map(|t| format!("--{}", t)))
+            .collect(),
+    };
+    
+    // Never copy a rotation the pixels already have; otherwise keep the source's
+    match (upright, policy) {
+        (true, MetadataPolicy::Scrub) => args.extend(["--Orientation".to_string(), "-Orientation#=1".to_string()]),
+        (true, _) => args.push("-Orientation#=1".to_string()),
+        (false, MetadataPolicy::Whitelist) => args.push("-Orientation".to_string()),
+        (false, _) => {}
+    }
+    Some(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_policy_arguments() {
+        assert_eq!(exiftool_args(MetadataPolicy::Strip, true), None);
+        
+        let whitelist = exiftool_args(MetadataPolicy::Whitelist, true).unwrap();
+        assert!(whitelist.contains(&"-EXIF:Copyright".to_string()));
+        assert!(whitelist.contains(&"-EXIF:DateTimeOriginal".to_string()));
+        assert!(!whitelist.iter().any(|a| a.contains("GPS") || a.contains("Serial") || a.contains("ICC")));
+        assert_eq!(whitelist.last().unwrap(), "-Orientation#=1");
+        
+        let scrub = exiftool_args(MetadataPolicy::Scrub, false).unwrap();
+        assert_eq!(scrub[0], "-all:all");
+        for excluded in ["--GPS:all", "--IPTC:City", "--XMP-photoshop:City", "--XMP-iptcExt:LocationShown", "--SerialNumber", "--LensSerialNumber", "--OwnerName", "--ICC_Profile:all"] {
+            assert!(scrub.contains(&excluded.to_string()), "missing {}", excluded);
+        }
+        assert!(!scrub.iter().any(|a| a.contains("Orientation")));
+        let upright = exiftool_args(MetadataPolicy::Scrub, true).unwrap();
+        assert!(upright.ends_with(&["--Orientation".to_string(), "-Orientation#=1".to_string()]));
+        
+        assert_eq!(MetadataPolicy::parse("public"), Some(MetadataPolicy::Scrub));
+        assert_eq!(MetadataPolicy::parse("attribution"), Some(MetadataPolicy::Whitelist));
+        assert_eq!(MetadataPolicy::parse("everything"), None);
+    }
+    
+    #[test]
+    fn test_scrub_round_trip() {
+        if !crate::metadata::exiftool_available() {
+            eprintln!("Skipping test - ExifTool not installed");
+            return;
+        }
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        
+        let source = dir.join("source.jpg");
+        RgbImage::from_pixel(8, 6, Rgb([90, 120, 150])).save(&source).unwrap();
+        let tagged = Command::new("exiftool")
+            .args([
+                "-q", "-overwrite_original",
+                "-Artist=Jane Doe", "-Copyright=CC BY 4.0", "-SerialNumber=SN98765",
+                "-GPSLatitude=51.5", "-GPSLatitudeRef=N", "-GPSLongitude=0.1", "-GPSLongitudeRef=W",
+                "-IPTC:City=Testville", "-IPTC:Sub-location=Testquarter",
+                "-IPTC:Province-State=Testshire", "-IPTC:Country-PrimaryLocationName=Testland",
+                "-XMP-photoshop:City=Testville", "-XMP-photoshop:State=Testshire", "-XMP-photoshop:Country=Testland",
+                "-XMP-iptcCore:Location=Testquarter", "-XMP-iptcExt:LocationShownCity=Testville",
+            ])
+            .arg(&source)
+            .status()
+            .unwrap();
+        assert!(tagged.success());
+        
+        let jpeg = dir.join("public.jpg");
+        RgbImage::from_pixel(8, 6, Rgb([90, 120, 150])).save(&jpeg).unwrap();
+        let webp = dir.join("public.webp");
+        std::fs::write(&webp, &*webp::Encoder::from_rgb(&[128u8; 8 * 6 * 3], 8, 6).encode(80.0)).unwrap();
+        
+        for output in [&jpeg, &webp] {
+            apply_metadata_policy(&source, output, MetadataPolicy::Scrub, true).unwrap();
+            let tags = Command::new("exiftool").args(["-a", "-G1", "-s", "--System:all"]).arg(output).output().unwrap();
+            let tags = String::from_utf8_lossy(&tags.stdout);
+            
+            assert!(tags.contains("Jane Doe") && tags.contains("CC BY 4.0"), "{}", tags);
+            for private in ["GPS", "Test", "SN98765"] {
+                assert!(!tags.contains(private), "{} survived in {}:\n{}", private, output.display(), tags);
+            }
+        }
+    }
+}
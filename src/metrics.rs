@@ -18,7 +18,20 @@ pub struct Metrics {
     pub audio_preprocess_count: AtomicU64,
     pub audio_mel_count: AtomicU64,
     pub video_frames_count: AtomicU64,
+    pub video_batch_count: AtomicU64,
+    pub video_transcode_count: AtomicU64,
+    pub video_batch_transcode_count: AtomicU64,
+    pub video_from_images_count: AtomicU64,
+    pub video_inspect_count: AtomicU64,
     pub image_preprocess_count: AtomicU64,
+    pub image_variants_count: AtomicU64,
+    pub image_tiles_count: AtomicU64,
+    pub image_colors_count: AtomicU64,
+    pub image_placeholder_count: AtomicU64,
+    pub image_tensor_count: AtomicU64,
+    pub image_phash_count: AtomicU64,
+    pub image_dedupe_count: AtomicU64,
+    pub image_quality_count: AtomicU64,
     pub raw_preview_count: AtomicU64,
     pub raw_metadata_count: AtomicU64,
 }
@@ -33,7 +46,20 @@ impl Metrics {
             audio_preprocess_count: AtomicU64::new(0),
             audio_mel_count: AtomicU64::new(0),
             video_frames_count: AtomicU64::new(0),
+            video_batch_count: AtomicU64::new(0),
+            video_transcode_count: AtomicU64::new(0),
+            video_batch_transcode_count: AtomicU64::new(0),
+            video_from_images_count: AtomicU64::new(0),
+            video_inspect_count: AtomicU64::new(0),
             image_preprocess_count: AtomicU64::new(0),
+            image_variants_count: AtomicU64::new(0),
+            image_tiles_count: AtomicU64::new(0),
+            image_colors_count: AtomicU64::new(0),
+            image_placeholder_count: AtomicU64::new(0),
+            image_tensor_count: AtomicU64::new(0),
+            image_phash_count: AtomicU64::new(0),
+            image_dedupe_count: AtomicU64::new(0),
+            image_quality_count: AtomicU64::new(0),
             raw_preview_count: AtomicU64::new(0),
             raw_metadata_count: AtomicU64::new(0),
         })
@@ -55,7 +81,20 @@ impl Metrics {
             "audio.preprocess" => self.audio_preprocess_count.fetch_add(1, Ordering::Relaxed),
             "audio.mel_spectrogram" => self.audio_mel_count.fetch_add(1, Ordering::Relaxed),
             "video.extract_frames" => self.video_frames_count.fetch_add(1, Ordering::Relaxed),
+            "video.batch_extract_frames" => self.video_batch_count.fetch_add(1, Ordering::Relaxed),
+            "video.transcode" => self.video_transcode_count.fetch_add(1, Ordering::Relaxed),
+            "video.batch_transcode" => self.video_batch_transcode_count.fetch_add(1, Ordering::Relaxed),
+            "video.from_images" => self.video_from_images_count.fetch_add(1, Ordering::Relaxed),
+            "video.inspect" => self.video_inspect_count.fetch_add(1, Ordering::Relaxed),
             "image.preprocess" => self.image_preprocess_count.fetch_add(1, Ordering::Relaxed),
+            "image.variants" => self.image_variants_count.fetch_add(1, Ordering::Relaxed),
+            "image.tiles" => self.image_tiles_count.fetch_add(1, Ordering::Relaxed),
+            "image.colors" => self.image_colors_count.fetch_add(1, Ordering::Relaxed),
+            "image.placeholder" => self.image_placeholder_count.fetch_add(1, Ordering::Relaxed),
+            "image.to_tensor" => self.image_tensor_count.fetch_add(1, Ordering::Relaxed),
+            "image.phash" => self.image_phash_count.fetch_add(1, Ordering::Relaxed),
+            "image.dedupe" => self.image_dedupe_count.fetch_add(1, Ordering::Relaxed),
+            "image.quality" => self.image_quality_count.fetch_add(1, Ordering::Relaxed),
             "raw.preview" => self.raw_preview_count.fetch_add(1, Ordering::Relaxed),
             "raw.metadata" => self.raw_metadata_count.fetch_add(1, Ordering::Relaxed),
             _ => 0,
@@ -78,7 +117,20 @@ impl Metrics {
                 audio_preprocess: self.audio_preprocess_count.load(Ordering::Relaxed),
                 audio_mel_spectrogram: self.audio_mel_count.load(Ordering::Relaxed),
                 video_extract_frames: self.video_frames_count.load(Ordering::Relaxed),
+                video_batch_extract_frames: self.video_batch_count.load(Ordering::Relaxed),
+                video_transcode: self.video_transcode_count.load(Ordering::Relaxed),
+                video_batch_transcode: self.video_batch_transcode_count.load(Ordering::Relaxed),
+                video_from_images: self.video_from_images_count.load(Ordering::Relaxed),
+                video_inspect: self.video_inspect_count.load(Ordering::Relaxed),
                 image_preprocess: self.image_preprocess_count.load(Ordering::Relaxed),
+                image_variants: self.image_variants_count.load(Ordering::Relaxed),
+                image_tiles: self.image_tiles_count.load(Ordering::Relaxed),
+                image_colors: self.image_colors_count.load(Ordering::Relaxed),
+                image_placeholder: self.image_placeholder_count.load(Ordering::Relaxed),
+                image_to_tensor: self.image_tensor_count.load(Ordering::Relaxed),
+                image_phash: self.image_phash_count.load(Ordering::Relaxed),
+                image_dedupe: self.image_dedupe_count.load(Ordering::Relaxed),
+                image_quality: self.image_quality_count.load(Ordering::Relaxed),
                 raw_preview: self.raw_preview_count.load(Ordering::Relaxed),
                 raw_metadata: self.raw_metadata_count.load(Ordering::Relaxed),
             },
@@ -96,7 +148,20 @@ impl Default for Metrics {
             audio_preprocess_count: AtomicU64::new(0),
             audio_mel_count: AtomicU64::new(0),
             video_frames_count: AtomicU64::new(0),
+            video_batch_count: AtomicU64::new(0),
+            video_transcode_count: AtomicU64::new(0),
+            video_batch_transcode_count: AtomicU64::new(0),
+            video_from_images_count: AtomicU64::new(0),
+            video_inspect_count: AtomicU64::new(0),
             image_preprocess_count: AtomicU64::new(0),
+            image_variants_count: AtomicU64::new(0),
+            image_tiles_count: AtomicU64::new(0),
+            image_colors_count: AtomicU64::new(0),
+            image_placeholder_count: AtomicU64::new(0),
+            image_tensor_count: AtomicU64::new(0),
+            image_phash_count: AtomicU64::new(0),
+            image_dedupe_count: AtomicU64::new(0),
+            image_quality_count: AtomicU64::new(0),
             raw_preview_count: AtomicU64::new(0),
             raw_metadata_count: AtomicU64::new(0),
         }
@@ -118,7 +183,20 @@ pub struct OperationMetrics {
     pub audio_preprocess: u64,
     pub audio_mel_spectrogram: u64,
     pub video_extract_frames: u64,
+    pub video_batch_extract_frames: u64,
+    pub video_transcode: u64,
+    pub video_batch_transcode: u64,
+    pub video_from_images: u64,
+    pub video_inspect: u64,
     pub image_preprocess: u64,
+    pub image_variants: u64,
+    pub image_tiles: u64,
+    pub image_colors: u64,
+    pub image_placeholder: u64,
+    pub image_to_tensor: u64,
+    pub image_phash: u64,
+    pub image_dedupe: u64,
+    pub image_quality: u64,
     pub raw_preview: u64,
     pub raw_metadata: u64,
 }
@@ -15,10 +15,23 @@
 //! 1. `audio.preprocess` - Audio format conversion
 //! 2. `audio.mel_spectrogram` - Mel spectrogram generation
 //! 3. `video.extract_frames` - Video frame extraction
-//! 4. `image.preprocess` - Image format conversion/resize
-//! 5. `raw.preview` - Fast RAW preview extraction
-//! 6. `raw.metadata` - RAW metadata extraction
-//! 7. `media.capabilities` - Capability card query
+//! 4. `video.batch_extract_frames` - Bounded-concurrency frame extraction over many videos
+//! 5. `video.transcode` - Re-encode with orientation, crop, tone-mapping and watermark
+//! 6. `video.batch_transcode` - Bounded-concurrency transcoding of many videos
+//! 7. `video.from_images` - Timelapse/slideshow encoding from image or RAW sequences
+//! 8. `video.inspect` - Video quality diagnostics
+//! 9. `image.preprocess` - Image format conversion/resize
+//! 10. `image.variants` - Multi-size derivatives from one decode
+//! 11. `image.tiles` - Deep-zoom tile pyramids (DZI, IIIF level 0)
+//! 12. `image.colors` - Dominant colour palette and colour statistics
+//! 13. `image.placeholder` - BlurHash and ThumbHash placeholder strings
+//! 14. `image.to_tensor` - Normalized model input tensors
+//! 15. `image.phash` - Perceptual hashes (pHash/dHash/aHash/wavelet)
+//! 16. `image.dedupe` - Near-duplicate clustering
+//! 17. `image.quality` - No-reference quality scoring for culling
+//! 18. `raw.preview` - Fast RAW preview extraction
+//! 19. `raw.metadata` - RAW metadata extraction
+//! 20. `media.capabilities` - Capability card query
 //!
 //! ## Example
 //!
@@ -45,12 +58,16 @@
 //! # }
 //! ```
 
-use crate::{AudioPreprocessor, AudioConfig, AudioFormat, VideoPreprocessor, VideoConfig, ImagePreprocessor, ImageConfig, ImageOutputFormat, FfmpegError, RawProcessor, PreviewOptions};
+use crate::image::load_any_image;
+use crate::jxl::is_jpeg;
+use crate::{HashAlgorithm, QualityAnalyzer, QualityConfig, ColorAnalyzer, ColorConfig, PaletteMethod, cluster_hashes, hamming_distance, is_heif, decode_heif, TargetColorSpace, recompress_jpeg, ImageBackend, TensorConverter, TensorPreset, TensorLayout, ChannelOrder, AudioPreprocessor, AudioConfig, AudioFormat, VideoPreprocessor, VideoConfig, ToneMapOperator, FrameDedupe, DedupeMethod, default_batch_concurrency, VideoCodec, TranscodeConfig, SequenceEncoder, SequenceConfig, ResizeMode, RawDecode, RawOptions, ColorSpace, DngSequence, expand_glob, VideoInspector, InspectConfig, ImagePreprocessor, ImageConfig, ImageOutputFormat, VariantSpec, TileGenerator, TileConfig, TileLayout, FocusRegion, focus_hints, placeholders, blurhash, PreprocessReport, MetadataPolicy, apply_metadata_policy, Watermark, WatermarkSource, WatermarkAnchor, AnimationMode, is_animated, FfmpegError, RawProcessor, PreviewOptions};
 use crate::metrics::Metrics;
 use async_trait::async_trait;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 use thiserror::Error;
@@ -206,28 +223,305 @@ impl MediaOrgan {
             .as_str()
             .ok_or_else(|| OrganError::InvalidInput("Missing output_dir".to_string()))?;
         
-        let fps = input["fps"].as_u64().unwrap_or(1) as u8;
-        let width = input["width"].as_u64().unwrap_or(336) as u32;
-        let height = input["height"].as_u64().unwrap_or(336) as u32;
-        let max_frames = input["max_frames"].as_u64().map(|v| v as usize);
+        let config = video_config_from_input(&input)?;
         
-        let config = VideoConfig {
-            fps,
-            width,
-            height,
-            max_frames,
+        // CinemaDNG folders / DNG sequences are decoded frame by frame through LibRaw
+        let dng_sequence = if DngSequence::is_sequence_dir(video_path) {
+            Some(DngSequence::open(video_path)?)
+        } else {
+            None
         };
         
-        let processor = VideoPreprocessor::new(config);
-        let frames = processor.extract_frames(video_path, output_dir)?;
+        let extraction = match &dng_sequence {
+            Some(sequence) => sequence.extract_frames(output_dir, &config, &raw_preset_from_input(&input)?)?,
+            None => VideoPreprocessor::new(config).extract_frames_with_report(video_path, output_dir)?,
+        };
         
         Ok(json!({
             "extracted": true,
-            "frame_count": frames.len(),
-            "frames": frames.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>()
+            "source": if dng_sequence.is_some() { "dng_sequence" } else { "video" },
+            "source_fps": dng_sequence.as_ref().map(|s| s.fps()),
+            "frame_count": extraction.frames.len(),
+            "frames": extraction.frames.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>(),
+            "rotation_applied": extraction.rotation,
+            "tone_mapped": extraction.tone_mapping.is_some(),
+            "tone_map_operator": extraction.tone_mapping.map(|op| op.as_str().to_string()),
+            "crop": extraction.crop,
+            "smart_crops": extraction.smart_crops,
+            "placeholders": extraction.placeholders,
+            "dropped_count": extraction.dropped.len(),
+            "dropped_frames": extraction.dropped
+        }))
+    }
+    
+    /// Handle video.batch_extract_frames operation
+    async fn handle_video_batch_extract_frames(&self, input: Value) -> Result<Value, OrganError> {
+        let video_paths: Vec<&str> = input["video_paths"]
+            .as_array()
+            .ok_or_else(|| OrganError::InvalidInput("Missing video_paths".to_string()))?
+            .iter()
+            .map(|v| v.as_str().ok_or_else(|| OrganError::InvalidInput("video_paths must be strings".to_string())))
+            .collect::<Result<_, _>>()?;
+        let output_dir = input["output_dir"]
+            .as_str()
+            .ok_or_else(|| OrganError::InvalidInput("Missing output_dir".to_string()))?;
+        let max_concurrent = input["max_concurrent"]
+            .as_u64()
+            .map(|v| v as usize)
+            .unwrap_or_else(default_batch_concurrency);
+        
+        let config = video_config_from_input(&input)?;
+        let processor = VideoPreprocessor::new(config);
+        let results = processor.batch_extract_frames(&video_paths, output_dir, max_concurrent);
+        
+        let succeeded = results.iter().filter(|(_, r)| r.is_ok()).count();
+        let results: Vec<Value> = results
+            .into_iter()
+            .map(|(i, result)| match result {
+                Ok(extraction) => json!({
+                    "video_path": video_paths[i],
+                    "ok": true,
+                    "frame_count": extraction.frames.len(),
+                    "frames": extraction.frames.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>(),
+                    "rotation_applied": extraction.rotation,
+                    "tone_mapped": extraction.tone_mapping.is_some(),
+                    "crop": extraction.crop,
+                    "dropped_count": extraction.dropped.len()
+                }),
+                Err(e) => json!({
+                    "video_path": video_paths[i],
+                    "ok": false,
+                    "error": e.to_string()
+                }),
+            })
+            .collect();
+        
+        Ok(json!({
+            "total": video_paths.len(),
+            "succeeded": succeeded,
+            "failed": video_paths.len() - succeeded,
+            "max_concurrent": max_concurrent,
+            "results": results
         }))
     }
     
+    /// Handle video.transcode operation
+    async fn handle_video_transcode(&self, input: Value) -> Result<Value, OrganError> {
+        let video_path = input["video_path"]
+            .as_str()
+            .ok_or_else(|| OrganError::InvalidInput("Missing video_path".to_string()))?;
+        let output_path = input["output_path"]
+            .as_str()
+            .ok_or_else(|| OrganError::InvalidInput("Missing output_path".to_string()))?;
+        let options = transcode_config_from_input(&input)?;
+        
+        // CinemaDNG folders / DNG sequences become a proxy at their own frame rate
+        if DngSequence::is_sequence_dir(video_path) {
+            let report = DngSequence::open(video_path)?
+                .transcode(output_path, &options, &raw_preset_from_input(&input)?)?;
+            return Ok(json!({
+                "transcoded": true,
+                "source": "dng_sequence",
+                "output_path": output_path,
+                "codec": options.codec.encoder(),
+                "frame_count": report.frame_count,
+                "duration": report.duration,
+                "watermarked": options.watermark.is_some()
+            }));
+        }
+        
+        let report = VideoPreprocessor::new(video_config_from_input(&input)?)
+            .transcode(video_path, output_path, &options)?;
+        
+        Ok(json!({
+            "transcoded": true,
+            "source": "video",
+            "output_path": report.output.to_string_lossy(),
+            "codec": options.codec.encoder(),
+            "rotation_applied": report.rotation,
+            "tone_mapped": report.tone_mapping.is_some(),
+            "tone_map_operator": report.tone_mapping.map(|op| op.as_str().to_string()),
+            "crop": report.crop,
+            "watermarked": options.watermark.is_some()
+        }))
+    }
+    
+    /// Handle video.batch_transcode operation
+    async fn handle_video_batch_transcode(&self, input: Value) -> Result<Value, OrganError> {
+        let video_paths: Vec<&str> = input["video_paths"]
+            .as_array()
+            .ok_or_else(|| OrganError::InvalidInput("Missing video_paths".to_string()))?
+            .iter()
+            .map(|v| v.as_str().ok_or_else(|| OrganError::InvalidInput("video_paths must be strings".to_string())))
+            .collect::<Result<_, _>>()?;
+        let output_dir = input["output_dir"]
+            .as_str()
+            .ok_or_else(|| OrganError::InvalidInput("Missing output_dir".to_string()))?;
+        let max_concurrent = input["max_concurrent"]
+            .as_u64()
+            .map(|v| v as usize)
+            .unwrap_or_else(default_batch_concurrency);
+        
+        let options = transcode_config_from_input(&input)?;
+        let processor = VideoPreprocessor::new(video_config_from_input(&input)?);
+        
+        // CinemaDNG folders / DNG sequences already decode on every core, so
+        // they are encoded one at a time after the bounded video batch
+        let (sequences, videos): (Vec<usize>, Vec<usize>) = (0..video_paths.len())
+            .partition(|&i| DngSequence::is_sequence_dir(video_paths[i]));
+        let mut results = vec![Value::Null; video_paths.len()];
+        
+        let video_batch: Vec<&str> = videos.iter().map(|&i| video_paths[i]).collect();
+        for (j, result) in processor.batch_transcode(&video_batch, output_dir, &options, max_concurrent) {
+            let i = videos[j];
+            results[i] = match result {
+                Ok(report) => json!({
+                    "video_path": video_paths[i],
+                    "ok": true,
+                    "source": "video",
+                    "output_path": report.output.to_string_lossy(),
+                    "rotation_applied": report.rotation,
+                    "tone_mapped": report.tone_mapping.is_some(),
+                    "crop": report.crop
+                }),
+                Err(e) => json!({
+                    "video_path": video_paths[i],
+                    "ok": false,
+                    "error": e.to_string()
+                }),
+            };
+        }
+        
+        if !sequences.is_empty() {
+            let raw_options = raw_preset_from_input(&input)?;
+            for i in sequences {
+                let name = std::path::Path::new(video_paths[i])
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| format!("sequence_{}", i));
+                let output = std::path::Path::new(output_dir).join(format!("{}.{}", name, options.codec.extension()));
+                let result = std::fs::create_dir_all(output_dir)
+                    .map_err(FfmpegError::from)
+                    .and_then(|_| DngSequence::open(video_paths[i]))
+                    .and_then(|sequence| sequence.transcode(&output, &options, &raw_options));
+                results[i] = match result {
+                    Ok(report) => json!({
+                        "video_path": video_paths[i],
+                        "ok": true,
+                        "source": "dng_sequence",
+                        "output_path": report.output.to_string_lossy(),
+                        "frame_count": report.frame_count,
+                        "duration": report.duration
+                    }),
+                    Err(e) => json!({
+                        "video_path": video_paths[i],
+                        "ok": false,
+                        "error": e.to_string()
+                    }),
+                };
+            }
+        }
+        let succeeded = results.iter().filter(|r| r["ok"] == true).count();
+        
+        Ok(json!({
+            "total": video_paths.len(),
+            "succeeded": succeeded,
+            "failed": video_paths.len() - succeeded,
+            "max_concurrent": max_concurrent,
+            "codec": options.codec.encoder(),
+            "results": results
+        }))
+    }
+    
+    /// Handle video.from_images operation
+    async fn handle_video_from_images(&self, input: Value) -> Result<Value, OrganError> {
+        let output_path = input["output_path"]
+            .as_str()
+            .ok_or_else(|| OrganError::InvalidInput("Missing output_path".to_string()))?;
+        
+        let images: Vec<PathBuf> = if let Some(list) = input["images"].as_array() {
+            list.iter()
+                .map(|v| v.as_str().map(PathBuf::from)
+                    .ok_or_else(|| OrganError::InvalidInput("images must be strings".to_string())))
+                .collect::<Result<_, _>>()?
+        } else if let Some(pattern) = input["glob"].as_str() {
+            expand_glob(pattern)?
+        } else {
+            return Err(OrganError::InvalidInput("Missing images or glob".to_string()));
+        };
+        
+        let defaults = SequenceConfig::default();
+        let resize = match input["resize_mode"].as_str() {
+            None => defaults.resize,
+            Some(name) => ResizeMode::parse(name)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown resize_mode: {}", name)))?,
+        };
+        let codec = match input["codec"].as_str() {
+            None => defaults.codec,
+            Some(name) => VideoCodec::parse(name)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown codec: {}", name)))?,
+        };
+        let raw_decode = match input["raw_decode"].as_str() {
+            None | Some("preview") => RawDecode::Preview,
+            Some("full") => RawDecode::Full(RawOptions::default()),
+            Some(name) => return Err(OrganError::InvalidInput(format!("Unknown raw_decode: {}", name))),
+        };
+        
+        let config = SequenceConfig {
+            fps: input["fps"].as_f64().unwrap_or(defaults.fps),
+            width: input["width"].as_u64().map(|v| v as u32).unwrap_or(defaults.width),
+            height: input["height"].as_u64().map(|v| v as u32).unwrap_or(defaults.height),
+            resize,
+            codec,
+            crf: crf_from_input(&input, codec, defaults.crf)?,
+            preset: input["preset"].as_str().map(|s| s.to_string()),
+            raw_decode,
+            hold: input["hold"].as_f64(),
+            crossfade: input["crossfade"].as_f64(),
+            ken_burns: input["ken_burns"].as_bool().unwrap_or(false),
+            watermark: watermark_from_input(&input)?,
+        };
+        
+        let report = SequenceEncoder::new(config).encode(&images, output_path)?;
+        
+        Ok(json!({
+            "encoded": true,
+            "output_path": output_path,
+            "image_count": report.image_count,
+            "frame_count": report.frame_count,
+            "duration": report.duration,
+            "skipped": report.skipped.iter()
+                .map(|(path, reason)| json!({ "path": path.to_string_lossy(), "reason": reason }))
+                .collect::<Vec<_>>()
+        }))
+    }
+    
+    /// Handle video.inspect operation
+    async fn handle_video_inspect(&self, input: Value) -> Result<Value, OrganError> {
+        let video_path = input["video_path"]
+            .as_str()
+            .ok_or_else(|| OrganError::InvalidInput("Missing video_path".to_string()))?;
+        
+        let defaults = InspectConfig::default();
+        let config = InspectConfig {
+            black_min_duration: input["black_min_duration"].as_f64().unwrap_or(defaults.black_min_duration),
+            black_pixel_threshold: input["black_pixel_threshold"].as_f64().unwrap_or(defaults.black_pixel_threshold),
+            freeze_min_duration: input["freeze_min_duration"].as_f64().unwrap_or(defaults.freeze_min_duration),
+            freeze_noise_db: input["freeze_noise_db"].as_f64().unwrap_or(defaults.freeze_noise_db),
+            silence_min_duration: input["silence_min_duration"].as_f64().unwrap_or(defaults.silence_min_duration),
+            silence_noise_db: input["silence_noise_db"].as_f64().unwrap_or(defaults.silence_noise_db),
+            sharpness_segments: input["sharpness_segments"].as_u64().map(|v| v as usize).unwrap_or(defaults.sharpness_segments),
+            blur_threshold: input["blur_threshold"].as_f64().unwrap_or(defaults.blur_threshold),
+        };
+        
+        let report = VideoInspector::new(config).inspect(video_path)?;
+        let has_issues = report.has_issues();
+        
+        let mut output = serde_json::to_value(&report).map_err(OrganError::SerializationError)?;
+        output["has_issues"] = json!(has_issues);
+        Ok(output)
+    }
+    
     /// Handle image.preprocess operation
     async fn handle_image_preprocess(&self, input: Value) -> Result<Value, OrganError> {
         let input_path = input["input_path"]
@@ -247,26 +541,573 @@ impl MediaOrgan {
             "png" => ImageOutputFormat::Png,
             "webp" => ImageOutputFormat::Webp,
             "avif" => ImageOutputFormat::Avif,
+            "jxl" => ImageOutputFormat::Jxl,
+            "gif" => ImageOutputFormat::Gif,
+            "tif" | "tiff" => ImageOutputFormat::Tiff,
             _ => ImageOutputFormat::Jpeg,
         };
         
+        let watermark = watermark_from_input(&input)?;
+        
+        // JPEG → JPEG XL without re-encoding (keeps source size, reversible);
+        // a watermark needs the pixels, so it forces a normal encode
+        if matches!(format, ImageOutputFormat::Jxl)
+            && watermark.is_none()
+            && input["lossless_jpeg"].as_bool().unwrap_or(false)
+            && is_jpeg(input_path)
+        {
+            recompress_jpeg(input_path, output_path)?;
+            let (width, height) = image::image_dimensions(input_path)
+                .map_err(|e| OrganError::ProcessingError(format!("Failed to read dimensions: {}", e)))?;
+            
+            return Ok(json!({
+                "processed": true,
+                "output_path": output_path,
+                "width": width,
+                "height": height,
+                "format": format_str,
+                "lossless_jpeg": true,
+                "backend": ImageBackend::Libjxl.as_str()
+            }));
+        }
+        
+        let auto_orient = input["auto_orient"].as_bool().unwrap_or(true);
+        let color_space = match input["color_space"].as_str() {
+            Some(name) => TargetColorSpace::parse(name)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown color_space: {}", name)))?,
+            None => TargetColorSpace::Srgb,
+        };
+        let embed_icc = input["embed_icc"].as_bool().unwrap_or(true);
+        let resize = match input["resize"].as_str() {
+            Some(mode) => ResizeMode::parse(mode)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown resize mode: {}", mode)))?,
+            None => ResizeMode::Stretch,
+        };
+        let focus = focus_hints_from_input(&input["focus_hints"], input_path)?;
+        let metadata = metadata_policy_from_input(&input)?;
+        let animation = match input["animation"].as_str() {
+            Some(mode) => AnimationMode::parse(mode)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown animation mode: {}", mode)))?,
+            None => AnimationMode::First,
+        };
+        
         let config = ImageConfig {
             width,
             height,
             format,
             quality,
+            auto_orient,
+            color_space,
+            embed_icc,
+            resize,
+            focus,
+            placeholders: input["placeholders"].as_bool().unwrap_or(false),
+            metadata,
+            watermark: watermark.clone(),
+            animation,
         };
         
         let processor = ImagePreprocessor::new(config);
-        processor.preprocess(input_path, output_path)?;
+        let depth_path = input["depth_output_path"].as_str();
+        let gain_map_path = input["gain_map_output_path"].as_str();
         
-        Ok(json!({
+        // HEIF auxiliary images come out of the same decode as the primary image
+        let mut aux = serde_json::Map::new();
+        let report = if (depth_path.is_some() || gain_map_path.is_some()) && is_heif(input_path) {
+            let decoded = decode_heif(input_path, auto_orient)?;
+            let report = processor.write_image(&decoded.image, output_path)?;
+            processor.copy_metadata(input_path, output_path)?;
+            
+            for (key, path, image) in [
+                ("depth_path", depth_path, &decoded.depth),
+                ("gain_map_path", gain_map_path, &decoded.gain_map),
+            ] {
+                if let (Some(path), Some(image)) = (path, image) {
+                    image.save(path)
+                        .map_err(|e| OrganError::ProcessingError(format!("Failed to write {}: {}", key, e)))?;
+                    aux.insert(key.to_string(), json!(path));
+                }
+            }
+            PreprocessReport { backend: decoded.backend, ..report }
+        } else {
+            processor.preprocess_detailed(input_path, output_path)?
+        };
+        
+        let mut output = json!({
             "processed": true,
             "output_path": output_path,
             "width": width,
             "height": height,
             "format": format_str,
-            "quality": quality
+            "quality": quality,
+            "color_space": color_space.as_str(),
+            "backend": report.backend.as_str(),
+            "crop": report.crop,
+            "placeholders": report.placeholders,
+            "metadata": metadata.as_str(),
+            "watermarked": watermark.is_some(),
+            "animation": report.animation
+        });
+        if let Some(object) = output.as_object_mut() {
+            object.extend(aux);
+        }
+        
+        Ok(output)
+    }
+    
+    /// Handle image.to_tensor operation
+    async fn handle_image_to_tensor(&self, input: Value) -> Result<Value, OrganError> {
+        let preset_name = input["preset"].as_str().unwrap_or("clip");
+        let mut config = TensorPreset::parse(preset_name)
+            .ok_or_else(|| OrganError::InvalidInput(format!("Unknown preset: {}", preset_name)))?
+            .config();
+        
+        if let Some(width) = input["width"].as_u64() {
+            config.width = width as u32;
+            config.height = input["height"].as_u64().unwrap_or(width) as u32;
+        }
+        if let Some(name) = input["resize_mode"].as_str() {
+            config.resize = ResizeMode::parse(name)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown resize_mode: {}", name)))?;
+        }
+        if let Some(fraction) = input["crop_fraction"].as_f64() {
+            config.crop_fraction = fraction as f32;
+        }
+        if let Some(name) = input["channel_order"].as_str() {
+            config.channel_order = ChannelOrder::parse(name)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown channel_order: {}", name)))?;
+        }
+        if let Some(name) = input["layout"].as_str() {
+            config.layout = TensorLayout::parse(name)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown layout: {}", name)))?;
+        }
+        if let Some(mean) = channel_triplet(&input["mean"])? {
+            config.mean = mean;
+        }
+        if let Some(std) = channel_triplet(&input["std"])? {
+            config.std = std;
+        }
+        if let Some(batch_dim) = input["batch_dim"].as_bool() {
+            config.batch_dim = batch_dim;
+        }
+        
+        let converter = TensorConverter::new(config);
+        let sample_count = input["frames"].as_u64().map(|n| n as usize);
+        let mut frame_indices = None;
+        let tensor = if let Some(path) = input["input_path"].as_str() {
+            match sample_count {
+                Some(count) if is_animated(path) => {
+                    let (tensor, indices) = converter.from_animation(path, count)?;
+                    frame_indices = Some(indices);
+                    tensor
+                }
+                _ => converter.from_path(path)?,
+            }
+        } else if let Some(video) = input["video_path"].as_str() {
+            converter.from_video_frame(video, input["timestamp"].as_f64().unwrap_or(0.0))?
+        } else {
+            return Err(OrganError::InvalidInput("Missing input_path or video_path".to_string()));
+        };
+        
+        let mut output = json!({
+            "shape": tensor.shape,
+            "dtype": "float32",
+            "preset": preset_name
+        });
+        if let Some(indices) = frame_indices {
+            output["frame_indices"] = json!(indices);
+        }
+        
+        match input["output_path"].as_str() {
+            Some(output_path) => {
+                let format = input["format"].as_str().unwrap_or_else(|| {
+                    if output_path.ends_with(".safetensors") { "safetensors" } else { "npy" }
+                });
+                match format {
+                    "npy" => tensor.save_npy(output_path)?,
+                    "safetensors" => tensor.save_safetensors(output_path)?,
+                    other => return Err(OrganError::InvalidInput(format!("Unknown tensor format: {}", other))),
+                }
+                output["output_path"] = json!(output_path);
+                output["format"] = json!(format);
+            }
+            None => {
+                output["data"] = json!(tensor.data);
+            }
+        }
+        
+        Ok(output)
+    }
+    
+    /// Handle image.phash operation
+    async fn handle_image_phash(&self, input: Value) -> Result<Value, OrganError> {
+        let paths = input_path_list(&input)?;
+        let algorithms = match input["algorithms"].as_array() {
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    let name = name.as_str().unwrap_or_default();
+                    HashAlgorithm::parse(name)
+                        .ok_or_else(|| OrganError::InvalidInput(format!("Unknown hash algorithm: {}", name)))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![HashAlgorithm::PHash],
+        };
+        
+        let results: Vec<Value> = paths
+            .par_iter()
+            .map(|path| {
+                let preview = PreviewOptions { max_dimension: Some(512), ..PreviewOptions::default() };
+                match load_any_image(std::path::Path::new(path), &preview) {
+                    Ok(image) => {
+                        let hashes: serde_json::Map<String, Value> = algorithms
+                            .iter()
+                            .map(|algorithm| (algorithm.as_str().to_string(), json!(format!("{:016x}", algorithm.hash(&image)))))
+                            .collect();
+                        json!({ "path": path, "hashes": hashes })
+                    }
+                    Err(e) => json!({ "path": path, "error": e.to_string() }),
+                }
+            })
+            .collect();
+        
+        Ok(json!({
+            "algorithms": algorithms.iter().map(|a| a.as_str()).collect::<Vec<_>>(),
+            "results": results
+        }))
+    }
+    
+    /// Handle image.dedupe operation
+    async fn handle_image_dedupe(&self, input: Value) -> Result<Value, OrganError> {
+        let algorithm_name = input["algorithm"].as_str().unwrap_or("phash");
+        let algorithm = HashAlgorithm::parse(algorithm_name)
+            .ok_or_else(|| OrganError::InvalidInput(format!("Unknown hash algorithm: {}", algorithm_name)))?;
+        let threshold = input["threshold"].as_u64().unwrap_or(8) as u32;
+        
+        // Stored hashes ({"id", "hash"} objects) skip decoding entirely
+        let mut ids = Vec::new();
+        let mut hashes = Vec::new();
+        let mut errors = Vec::new();
+        if let Some(stored) = input["hashes"].as_array() {
+            for entry in stored {
+                let id = entry["id"].as_str()
+                    .ok_or_else(|| OrganError::InvalidInput("Each hash needs an id".to_string()))?;
+                let hash = entry["hash"].as_str()
+                    .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| OrganError::InvalidInput(format!("Invalid hash for {}", id)))?;
+                ids.push(id.to_string());
+                hashes.push(hash);
+            }
+        } else {
+            let paths = input_path_list(&input)?;
+            let computed: Vec<(String, Result<u64, FfmpegError>)> = paths
+                .par_iter()
+                .map(|path| (path.clone(), algorithm.hash_file(path)))
+                .collect();
+            for (path, result) in computed {
+                match result {
+                    Ok(hash) => {
+                        ids.push(path);
+                        hashes.push(hash);
+                    }
+                    Err(e) => errors.push(json!({ "path": path, "error": e.to_string() })),
+                }
+            }
+        }
+        
+        let clusters: Vec<Value> = cluster_hashes(&hashes, threshold)
+            .into_iter()
+            .map(|members| {
+                let max_distance = members
+                    .iter()
+                    .flat_map(|&a| members.iter().map(move |&b| (a, b)))
+                    .map(|(a, b)| hamming_distance(hashes[a], hashes[b]))
+                    .max()
+                    .unwrap_or(0);
+                json!({
+                    "members": members.iter().map(|&i| ids[i].clone()).collect::<Vec<_>>(),
+                    "max_distance": max_distance
+                })
+            })
+            .collect();
+        
+        Ok(json!({
+            "algorithm": algorithm.as_str(),
+            "threshold": threshold,
+            "count": ids.len(),
+            "duplicate_count": clusters.iter().map(|c| c["members"].as_array().map_or(0, |m| m.len() - 1)).sum::<usize>(),
+            "clusters": clusters,
+            "hashes": ids.iter().zip(&hashes).map(|(id, hash)| json!({ "id": id, "hash": format!("{:016x}", hash) })).collect::<Vec<_>>(),
+            "errors": errors
+        }))
+    }
+    
+    /// Handle image.quality operation
+    async fn handle_image_quality(&self, input: Value) -> Result<Value, OrganError> {
+        let paths = input_path_list(&input)?;
+        
+        let mut config = QualityConfig::default();
+        if let Some(size) = input["analysis_size"].as_u64() {
+            config.analysis_size = size as u32;
+        }
+        if let Some(grid) = input["grid"].as_u64() {
+            config.grid = grid as u32;
+        }
+        if let Some(threshold) = input["blur_threshold"].as_f64() {
+            config.blur_threshold = threshold;
+        }
+        if let Some(percent) = input["max_highlight_clip"].as_f64() {
+            config.max_highlight_clip = percent;
+        }
+        if let Some(percent) = input["max_shadow_clip"].as_f64() {
+            config.max_shadow_clip = percent;
+        }
+        let analyzer = QualityAnalyzer::new(config);
+        
+        let results = paths
+            .par_iter()
+            .map(|path| match analyzer.analyze_path(path) {
+                Ok(report) => {
+                    let mut value = serde_json::to_value(&report).map_err(OrganError::SerializationError)?;
+                    value["path"] = json!(path);
+                    Ok(value)
+                }
+                Err(e) => Ok(json!({ "path": path, "error": e.to_string() })),
+            })
+            .collect::<Result<Vec<Value>, OrganError>>()?;
+        
+        Ok(json!({
+            "count": results.len(),
+            "reject_count": results.iter().filter(|r| r["likely_reject"].as_bool() == Some(true)).count(),
+            "results": results
+        }))
+    }
+    
+    /// Handle image.variants operation
+    async fn handle_image_variants(&self, input: Value) -> Result<Value, OrganError> {
+        let input_path = input["input_path"]
+            .as_str()
+            .ok_or_else(|| OrganError::InvalidInput("Missing input_path".to_string()))?;
+        let entries = input["variants"]
+            .as_array()
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| OrganError::InvalidInput("Missing variants".to_string()))?;
+        let output_dir = input["output_dir"].as_str().map(std::path::Path::new);
+        let stem = std::path::Path::new(input_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("image");
+        
+        let mut specs = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.iter().enumerate() {
+            let name = entry["name"].as_str().map(str::to_string).unwrap_or_else(|| format!("variant{}", index));
+            let width = entry["width"].as_u64()
+                .ok_or_else(|| OrganError::InvalidInput(format!("Variant {} needs a width", name)))? as u32;
+            let height = entry["height"].as_u64().map_or(width, |h| h as u32);
+            let resize = match entry["resize"].as_str() {
+                Some(mode) => ResizeMode::parse(mode)
+                    .ok_or_else(|| OrganError::InvalidInput(format!("Unknown resize mode: {}", mode)))?,
+                None => ResizeMode::Fit,
+            };
+            let format = match entry["format"].as_str() {
+                Some(format) => ImageOutputFormat::parse(format)
+                    .ok_or_else(|| OrganError::InvalidInput(format!("Unknown format: {}", format)))?,
+                None => ImageOutputFormat::Jpeg,
+            };
+            let output = match (entry["output_path"].as_str(), output_dir) {
+                (Some(path), _) => std::path::PathBuf::from(path),
+                (None, Some(dir)) => dir.join(format!("{}_{}.{}", stem, name, format.extension())),
+                (None, None) => return Err(OrganError::InvalidInput(format!("Variant {} needs output_path or output_dir", name))),
+            };
+            
+            specs.push(VariantSpec {
+                name,
+                width,
+                height,
+                resize,
+                format,
+                quality: entry["quality"].as_u64().unwrap_or(85) as u8,
+                output,
+            });
+        }
+        
+        let raw_decode = match input["raw_decode"].as_str() {
+            None | Some("preview") => RawDecode::Preview,
+            Some("full") => RawDecode::Full(RawOptions::default()),
+            Some(name) => return Err(OrganError::InvalidInput(format!("Unknown raw_decode: {}", name))),
+        };
+        let color_space = match input["color_space"].as_str() {
+            Some(name) => TargetColorSpace::parse(name)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown color_space: {}", name)))?,
+            None => TargetColorSpace::Srgb,
+        };
+        
+        let processor = ImagePreprocessor::new(ImageConfig {
+            auto_orient: input["auto_orient"].as_bool().unwrap_or(true),
+            color_space,
+            embed_icc: input["embed_icc"].as_bool().unwrap_or(true),
+            focus: focus_hints_from_input(&input["focus_hints"], input_path)?,
+            metadata: metadata_policy_from_input(&input)?,
+            watermark: watermark_from_input(&input)?,
+            ..ImageConfig::default()
+        });
+        let outputs = processor.variants(input_path, &specs, &raw_decode)?;
+        
+        Ok(json!({
+            "input_path": input_path,
+            "color_space": color_space.as_str(),
+            "watermarked": !input["watermark"].is_null(),
+            "variants": outputs.iter().map(|o| json!({
+                "name": o.name,
+                "output_path": o.output,
+                "width": o.width,
+                "height": o.height,
+                "format": o.format.as_str(),
+                "bytes": o.bytes,
+                "crop": o.crop
+            })).collect::<Vec<_>>()
+        }))
+    }
+    
+    /// Handle image.tiles operation
+    async fn handle_image_tiles(&self, input: Value) -> Result<Value, OrganError> {
+        let input_path = input["input_path"]
+            .as_str()
+            .ok_or_else(|| OrganError::InvalidInput("Missing input_path".to_string()))?;
+        let output_dir = input["output_dir"]
+            .as_str()
+            .ok_or_else(|| OrganError::InvalidInput("Missing output_dir".to_string()))?;
+        let name = input["name"].as_str().unwrap_or_else(|| {
+            std::path::Path::new(input_path).file_stem().and_then(|s| s.to_str()).unwrap_or("image")
+        });
+        
+        let mut config = TileConfig::default();
+        if let Some(layout) = input["layout"].as_str() {
+            config.layout = TileLayout::parse(layout)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown layout: {}", layout)))?;
+        }
+        if config.layout == TileLayout::Iiif {
+            // IIIF tiles don't overlap; 256 is the usual static tile size
+            config.tile_size = 256;
+            config.overlap = 0;
+        }
+        if let Some(size) = input["tile_size"].as_u64() {
+            config.tile_size = size as u32;
+        }
+        if let Some(overlap) = input["overlap"].as_u64() {
+            config.overlap = overlap as u32;
+        }
+        if let Some(format) = input["format"].as_str() {
+            config.format = ImageOutputFormat::parse(format)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown format: {}", format)))?;
+        }
+        if let Some(quality) = input["quality"].as_u64() {
+            config.quality = quality as u8;
+        }
+        if let Some(name) = input["color_space"].as_str() {
+            config.color_space = TargetColorSpace::parse(name)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown color_space: {}", name)))?;
+        }
+        match input["raw_decode"].as_str() {
+            None | Some("full") => {}
+            Some("preview") => config.raw_decode = RawDecode::Preview,
+            Some(name) => return Err(OrganError::InvalidInput(format!("Unknown raw_decode: {}", name))),
+        }
+        config.base_url = input["base_url"].as_str().map(str::to_string);
+        
+        let report = TileGenerator::new(config).generate(input_path, output_dir, name)?;
+        
+        Ok(json!({
+            "layout": report.layout.as_str(),
+            "width": report.width,
+            "height": report.height,
+            "levels": report.levels,
+            "tile_count": report.tile_count,
+            "manifest_path": report.manifest,
+            "tiles_dir": report.tiles_dir
+        }))
+    }
+    
+    /// Handle image.colors operation
+    async fn handle_image_colors(&self, input: Value) -> Result<Value, OrganError> {
+        let mut config = ColorConfig::default();
+        if let Some(colors) = input["colors"].as_u64() {
+            config.colors = colors.clamp(1, 32) as usize;
+        }
+        if let Some(name) = input["method"].as_str() {
+            config.method = PaletteMethod::parse(name)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown palette method: {}", name)))?;
+        }
+        if let Some(size) = input["analysis_size"].as_u64() {
+            config.analysis_size = size as u32;
+        }
+        if let Some(frames) = input["storyboard_frames"].as_u64() {
+            config.storyboard_frames = frames as usize;
+        }
+        let method = config.method;
+        let analyzer = ColorAnalyzer::new(config);
+        
+        if let Some(video) = input["video_path"].as_str() {
+            let report = analyzer.analyze_video(video)?;
+            let mut value = serde_json::to_value(&report).map_err(OrganError::SerializationError)?;
+            value["path"] = json!(video);
+            value["method"] = json!(method.as_str());
+            return Ok(value);
+        }
+        
+        let paths = input_path_list(&input)?;
+        let results = paths
+            .par_iter()
+            .map(|path| match analyzer.analyze_path(path) {
+                Ok(report) => {
+                    let mut value = serde_json::to_value(&report).map_err(OrganError::SerializationError)?;
+                    value["path"] = json!(path);
+                    Ok(value)
+                }
+                Err(e) => Ok(json!({ "path": path, "error": e.to_string() })),
+            })
+            .collect::<Result<Vec<Value>, OrganError>>()?;
+        
+        Ok(json!({
+            "count": results.len(),
+            "method": method.as_str(),
+            "results": results
+        }))
+    }
+    
+    /// Handle image.placeholder operation
+    async fn handle_image_placeholder(&self, input: Value) -> Result<Value, OrganError> {
+        let paths = input_path_list(&input)?;
+        let components = match (input["components_x"].as_u64(), input["components_y"].as_u64()) {
+            (None, None) => None,
+            (x, y) => Some((x.unwrap_or(4) as u32, y.unwrap_or(3) as u32)),
+        };
+        // Placeholders only need a tiny image; RAW files use their embedded preview
+        let preview = PreviewOptions { max_dimension: Some(256), ..PreviewOptions::default() };
+        
+        let results = paths
+            .par_iter()
+            .map(|path| match load_any_image(std::path::Path::new(path), &preview) {
+                Ok(image) => {
+                    let mut hashes = placeholders(&image);
+                    if let Some((x, y)) = components {
+                        hashes.blurhash = blurhash(&image, x, y);
+                    }
+                    json!({
+                        "path": path,
+                        "width": image.width(),
+                        "height": image.height(),
+                        "blurhash": hashes.blurhash,
+                        "thumbhash": hashes.thumbhash
+                    })
+                }
+                Err(e) => json!({ "path": path, "error": e.to_string() }),
+            })
+            .collect::<Vec<Value>>();
+        
+        Ok(json!({
+            "count": results.len(),
+            "results": results
         }))
     }
     
@@ -288,6 +1129,13 @@ impl MediaOrgan {
         let quality = input["quality"].as_u64().unwrap_or(92) as u8;
         let max_dimension = input["max_dimension"].as_u64().map(|v| v as u32);
         let force_raw = input["force_raw_processing"].as_bool().unwrap_or(false);
+        let metadata = metadata_policy_from_input(&input)?;
+        let watermark = watermark_from_input(&input)?;
+        let resize = match input["resize"].as_str() {
+            Some(mode) => ResizeMode::parse(mode)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown resize mode: {}", mode)))?,
+            None => ResizeMode::Fit,
+        };
         
         let processor = RawProcessor::new()
             .map_err(|e| OrganError::ProcessingError(e.to_string()))?;
@@ -296,23 +1144,52 @@ impl MediaOrgan {
             quality,
             max_dimension,
             force_raw_processing: force_raw,
+            resize,
+        };
+        
+        let format = input["format"].as_str().unwrap_or("webp");
+        if !matches!(format, "webp" | "jxl") {
+            return Err(OrganError::InvalidInput(format!("Unsupported preview format: {}", format)));
+        }
+        // A CinemaDNG folder / DNG sequence is previewed by its middle frame
+        let source = if DngSequence::is_sequence_dir(input_path) {
+            DngSequence::open(input_path)?.poster_frame().to_path_buf()
+        } else {
+            PathBuf::from(input_path)
         };
         
         let start = std::time::Instant::now();
-        let webp = processor.extract_preview_webp(std::path::Path::new(input_path), &options)
+        let (mut img, crop) = processor.preview_image_with_crop(&source, &options)
             .map_err(|e| OrganError::ProcessingError(e.to_string()))?;
+        if let Some(watermark) = &watermark {
+            watermark.render()?.apply(&mut img)?;
+        }
+        let encoded = if format == "jxl" {
+            crate::jxl::encode_jxl(&img, quality).map_err(OrganError::from)
+        } else {
+            processor.image_to_webp(&img, quality).map_err(|e| OrganError::ProcessingError(e.to_string()))
+        }?;
+        // From the decoded preview, before it is dropped
+        let placeholders = input["placeholders"].as_bool().unwrap_or(false).then(|| placeholders(&img));
         let elapsed = start.elapsed();
         
-        std::fs::write(output_path, &webp)
-            .map_err(|e| OrganError::ProcessingError(format!("Failed to write WebP: {}", e)))?;
+        std::fs::write(output_path, &encoded)
+            .map_err(|e| OrganError::ProcessingError(format!("Failed to write preview: {}", e)))?;
+        // Previews are rendered upright
+        apply_metadata_policy(&source, std::path::Path::new(output_path), metadata, true)?;
         
         Ok(json!({
             "output_path": output_path,
-            "format": "webp",
+            "source_frame": source.to_string_lossy(),
+            "format": format,
             "quality": quality,
-            "size_bytes": webp.len(),
+            "size_bytes": encoded.len(),
             "processing_time_ms": elapsed.as_millis(),
             "method": if force_raw { "raw_processing" } else { "auto" },
+            "metadata": metadata.as_str(),
+            "watermarked": watermark.is_some(),
+            "crop": crop,
+            "placeholders": placeholders,
         }))
     }
     
@@ -339,6 +1216,210 @@ impl Default for MediaOrgan {
     }
 }
 
+/// LibRaw settings for DNG sequences (`raw_preset`: fast, default or maximum)
+///
+/// Video frames carry no ICC profile, so `maximum` renders sRGB instead of ProPhoto.
+fn raw_preset_from_input(input: &Value) -> Result<RawOptions, OrganError> {
+    match input["raw_preset"].as_str().unwrap_or("fast") {
+        "fast" => Ok(RawOptions::fast_preview()),
+        "default" => Ok(RawOptions::default()),
+        "maximum" => Ok(RawOptions { color_space: ColorSpace::SRGB, ..RawOptions::maximum() }),
+        other => Err(OrganError::InvalidInput(format!("Unknown raw_preset: {}", other))),
+    }
+}
+
+/// `crf` checked against the codec's range
+fn crf_from_input(input: &Value, codec: VideoCodec, default: u8) -> Result<u8, OrganError> {
+    match &input["crf"] {
+        Value::Null => Ok(default),
+        value => value
+            .as_u64()
+            .filter(|&crf| crf <= codec.max_crf() as u64)
+            .map(|crf| crf as u8)
+            .ok_or_else(|| OrganError::InvalidInput(format!(
+                "crf must be an integer from 0 to {} for {}", codec.max_crf(), codec.as_str()
+            ))),
+    }
+}
+
+/// Encoding settings shared by video.transcode and video.batch_transcode
+fn transcode_config_from_input(input: &Value) -> Result<TranscodeConfig, OrganError> {
+    let defaults = TranscodeConfig::default();
+    let codec = match input["codec"].as_str() {
+        None => defaults.codec,
+        Some(name) => VideoCodec::parse(name)
+            .ok_or_else(|| OrganError::InvalidInput(format!("Unknown codec: {}", name)))?,
+    };
+    let max_dimension = match &input["max_dimension"] {
+        Value::Null => defaults.max_dimension,
+        value => Some(value
+            .as_u64()
+            .ok_or_else(|| OrganError::InvalidInput("max_dimension must be an integer".to_string()))? as u32),
+    };
+    
+    Ok(TranscodeConfig {
+        codec,
+        crf: crf_from_input(input, codec, defaults.crf)?,
+        preset: input["preset"].as_str().map(|s| s.to_string()),
+        max_dimension,
+        audio: input["audio"].as_bool().unwrap_or(defaults.audio),
+        watermark: watermark_from_input(input)?,
+    })
+}
+
+/// Parse an optional 3-element per-channel array (mean / std)
+fn channel_triplet(value: &Value) -> Result<Option<[f32; 3]>, OrganError> {
+    let values = match value.as_array() {
+        Some(values) => values,
+        None => return Ok(None),
+    };
+    let floats: Vec<f32> = values.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect();
+    match floats.as_slice() {
+        [a, b, c] => Ok(Some([*a, *b, *c])),
+        _ => Err(OrganError::InvalidInput("mean/std must have 3 numbers".to_string())),
+    }
+}
+
+/// Files from `input_paths` (array) or a single `input_path`
+fn input_path_list(input: &Value) -> Result<Vec<String>, OrganError> {
+    if let Some(paths) = input["input_paths"].as_array() {
+        return paths
+            .iter()
+            .enumerate()
+            .map(|(i, p)| p.as_str()
+                .map(str::to_string)
+                .ok_or_else(|| OrganError::InvalidInput(format!("input_paths[{}] must be a string", i))))
+            .collect();
+    }
+    input["input_path"]
+        .as_str()
+        .map(|path| vec![path.to_string()])
+        .ok_or_else(|| OrganError::InvalidInput("Missing input_path or input_paths".to_string()))
+}
+
+/// Frame extraction settings shared by video.extract_frames and video.batch_extract_frames
+fn video_config_from_input(input: &Value) -> Result<VideoConfig, OrganError> {
+    let fps = input["fps"].as_u64().unwrap_or(1) as u8;
+    let width = input["width"].as_u64().unwrap_or(336) as u32;
+    let height = input["height"].as_u64().unwrap_or(336) as u32;
+    let max_frames = input["max_frames"].as_u64().map(|v| v as usize);
+    let keep_raw_orientation = input["keep_raw_orientation"].as_bool().unwrap_or(false);
+    let crop_detect = input["crop_detect"].as_bool().unwrap_or(false);
+    let resize = match input["resize"].as_str() {
+        Some(mode) => ResizeMode::parse(mode)
+            .ok_or_else(|| OrganError::InvalidInput(format!("Unknown resize mode: {}", mode)))?,
+        None => ResizeMode::Stretch,
+    };
+    
+    let tone_map = match input["tone_map"].as_str() {
+        None => Some(ToneMapOperator::Hable),
+        Some("none") => None,
+        Some(name) => Some(ToneMapOperator::parse(name)
+            .ok_or_else(|| OrganError::InvalidInput(format!("Unknown tone_map operator: {}", name)))?),
+    };
+    
+    let dedupe = match input["dedupe"].as_str() {
+        None | Some("none") => None,
+        Some(name) => {
+            let method = DedupeMethod::parse(name)
+                .ok_or_else(|| OrganError::InvalidInput(format!("Unknown dedupe method: {}", name)))?;
+            Some(FrameDedupe {
+                method,
+                threshold: input["dedupe_threshold"].as_f64().unwrap_or(method.default_threshold()),
+            })
+        }
+    };
+    
+    Ok(VideoConfig {
+        fps,
+        width,
+        height,
+        max_frames,
+        keep_raw_orientation,
+        tone_map,
+        crop_detect,
+        dedupe,
+        resize,
+        placeholders: input["placeholders"].as_bool().unwrap_or(false),
+    })
+}
+
+/// Parse the `metadata` policy for derivative outputs (default: strip)
+fn metadata_policy_from_input(input: &Value) -> Result<MetadataPolicy, OrganError> {
+    match input["metadata"].as_str() {
+        Some(name) => MetadataPolicy::parse(name)
+            .ok_or_else(|| OrganError::InvalidInput(format!("Unknown metadata policy: {}", name))),
+        None => Ok(MetadataPolicy::Strip),
+    }
+}
+
+/// Parse the optional `watermark` object: a logo (`image`) or `text`, plus
+/// `anchor`, `margin`, `opacity`, `scale` and `tile`
+fn watermark_from_input(input: &Value) -> Result<Option<Watermark>, OrganError> {
+    let spec = &input["watermark"];
+    if spec.is_null() {
+        return Ok(None);
+    }
+    
+    let mut watermark = match (spec["image"].as_str(), spec["text"].as_str()) {
+        (Some(path), None) => Watermark::image(path),
+        (None, Some(text)) => {
+            let color = match spec["color"].as_str() {
+                Some(hex) => parse_hex_color(hex)
+                    .ok_or_else(|| OrganError::InvalidInput(format!("Invalid watermark color: {}", hex)))?,
+                None => [255, 255, 255],
+            };
+            Watermark {
+                source: WatermarkSource::Text {
+                    text: text.to_string(),
+                    color,
+                    font: spec["font"].as_str().map(PathBuf::from),
+                },
+                ..Watermark::text(text)
+            }
+        }
+        _ => return Err(OrganError::InvalidInput("watermark needs exactly one of image or text".to_string())),
+    };
+    
+    if let Some(name) = spec["anchor"].as_str() {
+        watermark.anchor = WatermarkAnchor::parse(name)
+            .ok_or_else(|| OrganError::InvalidInput(format!("Unknown watermark anchor: {}", name)))?;
+    }
+    if let Some(margin) = spec["margin"].as_f64() {
+        watermark.margin = margin as f32;
+    }
+    if let Some(opacity) = spec["opacity"].as_f64() {
+        watermark.opacity = opacity as f32;
+    }
+    if let Some(scale) = spec["scale"].as_f64() {
+        watermark.scale = scale as f32;
+    }
+    watermark.tile = spec["tile"].as_bool().unwrap_or(false);
+    Ok(Some(watermark))
+}
+
+/// `#rrggbb` / `rrggbb` to RGB
+fn parse_hex_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Parse `focus_hints`: `"auto"` reads AF point / XMP regions from the file,
+/// an array gives explicit normalised `{x, y, width, height}` regions
+fn focus_hints_from_input(value: &Value, path: &str) -> Result<Vec<FocusRegion>, OrganError> {
+    match value {
+        Value::Null => Ok(Vec::new()),
+        Value::String(s) if s == "auto" => Ok(focus_hints(path)),
+        Value::Array(_) => serde_json::from_value(value.clone())
+            .map_err(|e| OrganError::InvalidInput(format!("Invalid focus_hints: {}", e))),
+        _ => Err(OrganError::InvalidInput("focus_hints must be \"auto\" or an array of regions".to_string())),
+    }
+}
+
 #[async_trait]
 impl Organ for MediaOrgan {
     async fn stimulate(&self, stimulus: Stimulus) -> Result<Response, OrganError> {
@@ -349,7 +1430,20 @@ impl Organ for MediaOrgan {
             "audio.preprocess" => self.handle_audio_preprocess(stimulus.input).await?,
             "audio.mel_spectrogram" => self.handle_audio_mel_spectrogram(stimulus.input).await?,
             "video.extract_frames" => self.handle_video_extract_frames(stimulus.input).await?,
+            "video.batch_extract_frames" => self.handle_video_batch_extract_frames(stimulus.input).await?,
+            "video.transcode" => self.handle_video_transcode(stimulus.input).await?,
+            "video.batch_transcode" => self.handle_video_batch_transcode(stimulus.input).await?,
+            "video.from_images" => self.handle_video_from_images(stimulus.input).await?,
+            "video.inspect" => self.handle_video_inspect(stimulus.input).await?,
             "image.preprocess" => self.handle_image_preprocess(stimulus.input).await?,
+            "image.variants" => self.handle_image_variants(stimulus.input).await?,
+            "image.tiles" => self.handle_image_tiles(stimulus.input).await?,
+            "image.colors" => self.handle_image_colors(stimulus.input).await?,
+            "image.placeholder" => self.handle_image_placeholder(stimulus.input).await?,
+            "image.to_tensor" => self.handle_image_to_tensor(stimulus.input).await?,
+            "image.phash" => self.handle_image_phash(stimulus.input).await?,
+            "image.dedupe" => self.handle_image_dedupe(stimulus.input).await?,
+            "image.quality" => self.handle_image_quality(stimulus.input).await?,
             "raw.preview" => self.handle_raw_preview(stimulus.input).await?,
             "raw.metadata" => self.handle_raw_metadata(stimulus.input).await?,
             "media.capabilities" => self.handle_capabilities()?,
@@ -371,7 +1465,20 @@ impl Organ for MediaOrgan {
                             "audio.preprocess",
                             "audio.mel_spectrogram",
                             "video.extract_frames",
+                            "video.batch_extract_frames",
+                            "video.transcode",
+                            "video.batch_transcode",
+                            "video.from_images",
+                            "video.inspect",
                             "image.preprocess",
+                            "image.variants",
+                            "image.tiles",
+                            "image.colors",
+                            "image.placeholder",
+                            "image.to_tensor",
+                            "image.phash",
+                            "image.dedupe",
+                            "image.quality",
                             "raw.preview",
                             "raw.metadata",
                             "media.capabilities",
@@ -497,12 +1604,20 @@ impl Organ for MediaOrgan {
                     input_schema: Some(json!({
                         "type": "object",
                         "properties": {
-                            "video_path": { "type": "string", "description": "Path to video file" },
+                            "video_path": { "type": "string", "description": "Path to video file, or a CinemaDNG / DNG sequence directory" },
                             "output_dir": { "type": "string", "description": "Directory to save extracted frames" },
                             "fps": { "type": "integer", "description": "Frames per second to extract (default: 1)" },
                             "width": { "type": "integer", "description": "Frame width (default: 336)" },
                             "height": { "type": "integer", "description": "Frame height (default: 336)" },
-                            "max_frames": { "type": "integer", "description": "Maximum number of frames (optional)" }
+                            "max_frames": { "type": "integer", "description": "Maximum number of frames (optional)" },
+                            "keep_raw_orientation": { "type": "boolean", "description": "Skip rotate tag / display matrix correction (default: false)" },
+                            "tone_map": { "type": "string", "enum": ["hable", "mobius", "reinhard", "clip", "linear", "gamma", "none"], "description": "Tone-mapping operator for HDR (PQ/HLG) sources (default: hable)" },
+                            "crop_detect": { "type": "boolean", "description": "Detect and crop letterbox/pillarbox black bars (default: false)" },
+                            "resize": { "type": "string", "enum": ["stretch", "fit", "fill", "smart"], "description": "Fit frames to width x height; smart crops each frame around its most salient region (default: stretch)" },
+                            "placeholders": { "type": "boolean", "description": "Return BlurHash/ThumbHash for each kept frame (default: false)" },
+                            "dedupe": { "type": "string", "enum": ["phash", "histogram", "none"], "description": "Drop near-duplicate frames (default: none)" },
+                            "dedupe_threshold": { "type": "number", "description": "Max distance to last kept frame to drop (default: 6 bits for phash, 0.05 for histogram)" },
+                            "raw_preset": { "type": "string", "enum": ["fast", "default", "maximum"], "description": "LibRaw preset for DNG sequences (default: fast)" }
                         },
                         "required": ["video_path", "output_dir"]
                     })),
@@ -510,14 +1625,246 @@ impl Organ for MediaOrgan {
                         "type": "object",
                         "properties": {
                             "extracted": { "type": "boolean" },
+                            "source": { "type": "string", "enum": ["video", "dng_sequence"] },
+                            "source_fps": { "type": "number", "description": "Frame rate from DNG metadata (DNG sequences only)" },
                             "frame_count": { "type": "integer" },
-                            "frames": { "type": "array", "items": { "type": "string" } }
+                            "frames": { "type": "array", "items": { "type": "string" } },
+                            "rotation_applied": { "type": "integer", "description": "Clockwise rotation applied to frames (0, 90, 180, 270)" },
+                            "tone_mapped": { "type": "boolean", "description": "HDR source was tone mapped to sRGB" },
+                            "tone_map_operator": { "type": "string" },
+                            "crop": { "type": "object", "description": "Detected active picture area {x, y, width, height}, null if no bars" },
+                            "smart_crops": { "type": "array", "items": { "type": "object" }, "description": "Per-frame {x, y, width, height} chosen by smart resize, in scaled-frame pixels" },
+                            "placeholders": { "type": "array", "items": { "type": "object" }, "description": "Per kept frame {blurhash, thumbhash} when requested" },
+                            "dropped_count": { "type": "integer" },
+                            "dropped_frames": { "type": "array", "items": { "type": "object", "description": "{frame, duplicate_of, distance, method}" } }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "video.batch_extract_frames".to_string(),
+                    description: "Extract frames from many videos with a bounded number of concurrent FFmpeg processes".to_string(),
+                    tags: vec!["video".to_string(), "frames".to_string(), "batch".to_string(), "extraction".to_string()],
+                    examples: vec![
+                        "Nightly ingest of thousands of clips without overloading the host".to_string(),
+                        "Extract CLIP frames for a whole directory of videos".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec!["writes image files".to_string(), "invokes ffmpeg".to_string()],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "video_paths": { "type": "array", "items": { "type": "string" }, "description": "Paths to video files" },
+                            "output_dir": { "type": "string", "description": "Root directory; frames go to <output_dir>/<file stem>/" },
+                            "max_concurrent": { "type": "integer", "description": "Maximum FFmpeg processes at once (default: half the CPU cores)" },
+                            "fps": { "type": "integer", "description": "Frames per second to extract (default: 1)" },
+                            "width": { "type": "integer", "description": "Frame width (default: 336)" },
+                            "height": { "type": "integer", "description": "Frame height (default: 336)" },
+                            "max_frames": { "type": "integer", "description": "Maximum number of frames per video (optional)" },
+                            "keep_raw_orientation": { "type": "boolean" },
+                            "tone_map": { "type": "string" },
+                            "crop_detect": { "type": "boolean" },
+                            "dedupe": { "type": "string", "enum": ["phash", "histogram", "none"] },
+                            "dedupe_threshold": { "type": "number" }
+                        },
+                        "required": ["video_paths", "output_dir"]
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "total": { "type": "integer" },
+                            "succeeded": { "type": "integer" },
+                            "failed": { "type": "integer" },
+                            "max_concurrent": { "type": "integer" },
+                            "results": { "type": "array", "items": { "type": "object", "description": "Per-file result in input order: {video_path, ok, frame_count, frames, ...} or {video_path, ok: false, error}" } }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "video.transcode".to_string(),
+                    description: "Re-encode a video or DNG sequence to H.264/H.265/VP9/AV1 with orientation, crop and HDR tone-mapping fixes and an optional burnt-in watermark".to_string(),
+                    tags: vec!["video".to_string(), "transcode".to_string(), "encoding".to_string(), "watermark".to_string()],
+                    examples: vec![
+                        "Make an upright, web-friendly H.264 copy of a phone clip".to_string(),
+                        "Render a watermarked review proxy of a CinemaDNG shot".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec!["writes video file".to_string(), "invokes ffmpeg".to_string()],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "video_path": { "type": "string", "description": "Path to video file, or a CinemaDNG / DNG sequence directory" },
+                            "output_path": { "type": "string", "description": "Output video file (.mp4 for h264/h265/av1, .webm for vp9)" },
+                            "codec": { "type": "string", "enum": ["h264", "h265", "vp9", "av1"], "description": "Video codec (default: h264)" },
+                            "crf": { "type": "integer", "description": "Constant rate factor, lower is higher quality: 0-51 for h264/h265, 0-63 for vp9/av1 (default: 23)" },
+                            "preset": { "type": "string", "description": "Encoder preset (optional)" },
+                            "max_dimension": { "type": "integer", "description": "Longest side limit in pixels, never upscales (default: 1920)" },
+                            "audio": { "type": "boolean", "description": "Keep the audio track (default: true)" },
+                            "keep_raw_orientation": { "type": "boolean", "description": "Skip rotate tag / display matrix correction (default: false)" },
+                            "tone_map": { "type": "string", "enum": ["hable", "mobius", "reinhard", "clip", "linear", "gamma", "none"], "description": "Tone-mapping operator for HDR (PQ/HLG) sources (default: hable)" },
+                            "crop_detect": { "type": "boolean", "description": "Detect and crop letterbox/pillarbox black bars (default: false)" },
+                            "raw_preset": { "type": "string", "enum": ["fast", "default", "maximum"], "description": "LibRaw preset for DNG sequences (default: fast)" },
+                            "watermark": {
+                                "type": "object",
+                                "description": "Logo or text burnt into every frame at the corrected frame size (FFmpeg overlay): one of image (PNG/SVG logo) or text",
+                                "properties": {
+                                    "image": { "type": "string" },
+                                    "text": { "type": "string" },
+                                    "color": { "type": "string", "description": "Text colour #rrggbb (default: #ffffff)" },
+                                    "font": { "type": "string", "description": "Font file for text (default: fontconfig default)" },
+                                    "anchor": { "type": "string", "enum": ["top-left", "top", "top-right", "left", "center", "right", "bottom-left", "bottom", "bottom-right"], "description": "Default: bottom-right" },
+                                    "margin": { "type": "number", "description": "Fraction of the frame's shorter side (default: 0.03)" },
+                                    "opacity": { "type": "number", "minimum": 0, "maximum": 1, "description": "Default: 0.5" },
+                                    "scale": { "type": "number", "description": "Mark width as a fraction of the frame width (default: 0.2)" },
+                                    "tile": { "type": "boolean", "description": "Repeat over the whole frame (default: false)" }
+                                }
+                            }
+                        },
+                        "required": ["video_path", "output_path"]
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "transcoded": { "type": "boolean" },
+                            "source": { "type": "string", "enum": ["video", "dng_sequence"] },
+                            "output_path": { "type": "string" },
+                            "codec": { "type": "string" },
+                            "rotation_applied": { "type": "integer" },
+                            "tone_mapped": { "type": "boolean" },
+                            "tone_map_operator": { "type": "string" },
+                            "crop": { "type": "object", "description": "Active picture area {x, y, width, height}, null if not cropped" },
+                            "frame_count": { "type": "integer", "description": "DNG sequences only" },
+                            "duration": { "type": "number", "description": "DNG sequences only, seconds" },
+                            "watermarked": { "type": "boolean" }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "video.batch_transcode".to_string(),
+                    description: "Transcode many videos to H.264/H.265/VP9/AV1 with orientation, crop and HDR tone-mapping fixes and a bounded number of concurrent encoders".to_string(),
+                    tags: vec!["video".to_string(), "transcode".to_string(), "batch".to_string(), "encoding".to_string()],
+                    examples: vec![
+                        "Normalise a folder of phone uploads to upright 1080p H.264".to_string(),
+                        "Re-encode an HDR archive to SDR AV1 overnight".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec!["writes video files".to_string(), "invokes ffmpeg".to_string()],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "video_paths": { "type": "array", "items": { "type": "string" }, "description": "Paths to video files or CinemaDNG / DNG sequence directories" },
+                            "output_dir": { "type": "string", "description": "Output directory; files are written as <output_dir>/<file stem>.<ext>" },
+                            "max_concurrent": { "type": "integer", "description": "Maximum FFmpeg processes at once (default: half the CPU cores)" },
+                            "codec": { "type": "string", "enum": ["h264", "h265", "vp9", "av1"], "description": "Video codec (default: h264)" },
+                            "crf": { "type": "integer", "description": "Constant rate factor, lower is higher quality: 0-51 for h264/h265, 0-63 for vp9/av1 (default: 23)" },
+                            "preset": { "type": "string", "description": "Encoder preset (optional)" },
+                            "max_dimension": { "type": "integer", "description": "Longest side limit in pixels, never upscales (default: 1920)" },
+                            "audio": { "type": "boolean", "description": "Keep the audio track (default: true)" },
+                            "keep_raw_orientation": { "type": "boolean" },
+                            "tone_map": { "type": "string" },
+                            "crop_detect": { "type": "boolean" },
+                            "raw_preset": { "type": "string", "enum": ["fast", "default", "maximum"], "description": "LibRaw preset for DNG sequences (default: fast)" },
+                            "watermark": { "type": "object", "description": "Logo or text burnt into every frame (same fields as video.transcode)" }
+                        },
+                        "required": ["video_paths", "output_dir"]
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "total": { "type": "integer" },
+                            "succeeded": { "type": "integer" },
+                            "failed": { "type": "integer" },
+                            "max_concurrent": { "type": "integer" },
+                            "codec": { "type": "string" },
+                            "results": { "type": "array", "items": { "type": "object", "description": "Per-file result in input order: {video_path, ok, source, output_path, rotation_applied, tone_mapped, crop} for videos, {video_path, ok, source, output_path, frame_count, duration} for DNG sequences, or {video_path, ok: false, error}" } }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "video.from_images".to_string(),
+                    description: "Encode a timelapse or slideshow video from an ordered image sequence, including RAW files".to_string(),
+                    tags: vec!["video".to_string(), "timelapse".to_string(), "slideshow".to_string(), "encoding".to_string(), "raw".to_string()],
+                    examples: vec![
+                        "Render a 24 fps timelapse straight from a folder of CR3 files".to_string(),
+                        "Build a slideshow with crossfades and Ken Burns pan/zoom".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec!["writes video file".to_string(), "invokes ffmpeg".to_string()],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "images": { "type": "array", "items": { "type": "string" }, "description": "Ordered image paths" },
+                            "glob": { "type": "string", "description": "Glob pattern (sorted by path), used when images is absent" },
+                            "output_path": { "type": "string", "description": "Output video path" },
+                            "fps": { "type": "number", "description": "Output frame rate (default: 24)" },
+                            "width": { "type": "integer", "description": "Output width, even (default: 1920)" },
+                            "height": { "type": "integer", "description": "Output height, even (default: 1080)" },
+                            "resize_mode": { "type": "string", "enum": ["fit", "fill", "stretch", "smart"], "description": "How images are fitted to the frame (default: fill)" },
+                            "codec": { "type": "string", "enum": ["h264", "h265", "vp9", "av1"], "description": "Video codec (default: h264)" },
+                            "crf": { "type": "integer", "description": "Constant rate factor: 0-51 for h264/h265, 0-63 for vp9/av1 (default: 20)" },
+                            "preset": { "type": "string", "description": "Encoder preset (optional)" },
+                            "raw_decode": { "type": "string", "enum": ["preview", "full"], "description": "RAW decoding: embedded preview or full LibRaw (default: preview)" },
+                            "hold": { "type": "number", "description": "Seconds per image for slideshows; omit for one frame per image" },
+                            "crossfade": { "type": "number", "description": "Crossfade seconds between images (slideshow only)" },
+                            "ken_burns": { "type": "boolean", "description": "Pan/zoom across each image (slideshow only)" },
+                            "watermark": { "type": "object", "description": "Logo or text burnt into every frame (same fields as video.transcode)" }
+                        },
+                        "required": ["output_path"]
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "encoded": { "type": "boolean" },
+                            "output_path": { "type": "string" },
+                            "image_count": { "type": "integer" },
+                            "frame_count": { "type": "integer" },
+                            "duration": { "type": "number" },
+                            "skipped": { "type": "array", "items": { "type": "object", "description": "{path, reason}" } }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "video.inspect".to_string(),
+                    description: "Scan video for black frames, frozen frames, silent audio, interlacing and per-segment blur for ingest QA".to_string(),
+                    tags: vec!["video".to_string(), "quality".to_string(), "diagnostics".to_string(), "qa".to_string()],
+                    examples: vec![
+                        "Flag uploads with long black or frozen sections".to_string(),
+                        "Detect interlaced sources before frame extraction".to_string(),
+                        "Find blurry segments in user-generated video".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec!["invokes ffmpeg".to_string()],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "video_path": { "type": "string", "description": "Path to video file" },
+                            "black_min_duration": { "type": "number", "description": "Minimum black run in seconds (default: 0.5)" },
+                            "black_pixel_threshold": { "type": "number", "description": "Luma ratio below which a pixel is black (default: 0.10)" },
+                            "freeze_min_duration": { "type": "number", "description": "Minimum frozen run in seconds (default: 2.0)" },
+                            "freeze_noise_db": { "type": "number", "description": "Freeze noise tolerance in dB (default: -60)" },
+                            "silence_min_duration": { "type": "number", "description": "Minimum silence in seconds (default: 2.0)" },
+                            "silence_noise_db": { "type": "number", "description": "Silence level in dB (default: -50)" },
+                            "sharpness_segments": { "type": "integer", "description": "Segments scored for sharpness, 0 disables (default: 10)" },
+                            "blur_threshold": { "type": "number", "description": "Variance-of-Laplacian below which a segment is blurry (default: 100)" }
+                        },
+                        "required": ["video_path"]
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "duration": { "type": "number" },
+                            "has_audio": { "type": "boolean" },
+                            "has_issues": { "type": "boolean" },
+                            "black": { "type": "array", "items": { "type": "object", "description": "{start, end, duration} in seconds" } },
+                            "frozen": { "type": "array", "items": { "type": "object" } },
+                            "silence": { "type": "array", "items": { "type": "object" } },
+                            "interlace": { "type": "object", "description": "{tff, bff, progressive, undetermined, interlaced}" },
+                            "sharpness": { "type": "array", "items": { "type": "object", "description": "{start, end, sharpness, blurry}" } }
                         }
                     }),
                 },
                 FunctionCard {
                     name: "image.preprocess".to_string(),
-                    description: "Resize and convert image to specified format and dimensions for vision model input (supports JPG, PNG, WEBP, AVIF, DNG/RAW)".to_string(),
+                    description: "Resize and convert image to specified format and dimensions for vision model input (supports JPG, PNG, WEBP, AVIF, HEIC/HEIF, DNG/RAW)".to_string(),
                     tags: vec!["image".to_string(), "preprocessing".to_string(), "resize".to_string(), "conversion".to_string()],
                     examples: vec![
                         "Resize image to 336x336 for CLIP vision encoder".to_string(),
@@ -525,7 +1872,7 @@ impl Organ for MediaOrgan {
                         "Batch resize images for dataset preparation".to_string(),
                     ],
                     idempotent: true,
-                    side_effects: vec!["writes image file".to_string(), "invokes ffmpeg".to_string()],
+                    side_effects: vec!["writes image file".to_string(), "invokes ffmpeg for exotic inputs".to_string()],
                     input_schema: Some(json!({
                         "type": "object",
                         "properties": {
@@ -533,8 +1880,34 @@ impl Organ for MediaOrgan {
                             "output_path": { "type": "string", "description": "Path to output image file" },
                             "width": { "type": "integer", "description": "Target width (default: 336)" },
                             "height": { "type": "integer", "description": "Target height (default: 336)" },
-                            "format": { "type": "string", "enum": ["jpg", "png", "webp", "avif"], "description": "Output format (default: jpg)" },
-                            "quality": { "type": "integer", "minimum": 1, "maximum": 100, "description": "Quality for lossy formats (default: 90)" }
+                            "format": { "type": "string", "enum": ["jpg", "png", "webp", "avif", "jxl", "gif", "tiff"], "description": "Output format (default: jpg)" },
+                            "animation": { "type": "string", "enum": ["first", "representative", "keep"], "description": "Animated GIF/APNG/WebP input: first frame, frame closest to the average, or keep every frame (webp/gif output) (default: first)" },
+                            "lossless_jpeg": { "type": "boolean", "description": "jxl from JPEG input: recompress losslessly at source size; ignored with a watermark (default: false)" },
+                            "quality": { "type": "integer", "minimum": 1, "maximum": 100, "description": "Quality for lossy formats (default: 90)" },
+                            "auto_orient": { "type": "boolean", "description": "Apply EXIF orientation (default: true)" },
+                            "color_space": { "type": "string", "enum": ["srgb", "display_p3", "adobe_rgb"], "description": "Convert embedded ICC profiles to this space (default: srgb)" },
+                            "embed_icc": { "type": "boolean", "description": "Embed the output ICC profile (default: true)" },
+                            "resize": { "type": "string", "enum": ["stretch", "fit", "fill", "smart"], "description": "How to reach width x height; smart picks the crop window from an edge/saturation/skin saliency map (default: stretch)" },
+                            "focus_hints": { "description": "smart resize: \"auto\" (AF point / XMP face regions) or [{x, y, width, height}] normalised to 0-1" },
+                            "placeholders": { "type": "boolean", "description": "Return BlurHash/ThumbHash of the output, computed from the resized pixels (default: false)" },
+                            "metadata": { "type": "string", "enum": ["strip", "whitelist", "scrub"], "description": "Source EXIF/XMP on the output: none, copyright + capture date only, or all but location/serials/owner (default: strip; lossless_jpeg keeps the JPEG's own)" },
+                            "watermark": {
+                                "type": "object",
+                                "description": "Overlay composited after resizing: one of image (PNG/SVG logo) or text",
+                                "properties": {
+                                    "image": { "type": "string" },
+                                    "text": { "type": "string" },
+                                    "color": { "type": "string", "description": "Text colour #rrggbb (default: #ffffff)" },
+                                    "font": { "type": "string", "description": "Font file for text (default: fontconfig default)" },
+                                    "anchor": { "type": "string", "enum": ["top-left", "top", "top-right", "left", "center", "right", "bottom-left", "bottom", "bottom-right"], "description": "Default: bottom-right" },
+                                    "margin": { "type": "number", "description": "Fraction of the output's shorter side (default: 0.03)" },
+                                    "opacity": { "type": "number", "minimum": 0, "maximum": 1, "description": "Default: 0.5" },
+                                    "scale": { "type": "number", "description": "Mark width as a fraction of the output width (default: 0.2)" },
+                                    "tile": { "type": "boolean", "description": "Repeat over the whole output (default: false)" }
+                                }
+                            },
+                            "depth_output_path": { "type": "string", "description": "HEIC/HEIF: write the depth map here (PNG)" },
+                            "gain_map_output_path": { "type": "string", "description": "HEIC/HEIF: write the HDR gain map here (PNG)" }
                         },
                         "required": ["input_path", "output_path"]
                     })),
@@ -546,7 +1919,291 @@ impl Organ for MediaOrgan {
                             "width": { "type": "integer" },
                             "height": { "type": "integer" },
                             "format": { "type": "string" },
-                            "quality": { "type": "integer" }
+                            "quality": { "type": "integer" },
+                            "backend": { "type": "string", "enum": ["native", "ffmpeg", "libheif", "libjxl"], "description": "Backend that produced the output" },
+                            "lossless_jpeg": { "type": "boolean" },
+                            "color_space": { "type": "string" },
+                            "crop": { "type": "object", "description": "Source window kept by fill/smart resize {x, y, width, height}" },
+                            "placeholders": { "type": "object", "description": "{blurhash, thumbhash (base64)} when requested; null from the FFmpeg backend" },
+                            "metadata": { "type": "string" },
+                            "watermarked": { "type": "boolean" },
+                            "animation": { "type": "object", "description": "{frame_count, frame_index} for animated input; frame_index is null when every frame was kept" },
+                            "depth_path": { "type": "string" },
+                            "gain_map_path": { "type": "string" }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "image.to_tensor".to_string(),
+                    description: "Convert an image, RAW preview or video frame into a normalized float32 tensor (CLIP, SigLIP, ImageNet, DINOv2 presets)".to_string(),
+                    tags: vec!["image".to_string(), "tensor".to_string(), "preprocessing".to_string(), "inference".to_string()],
+                    examples: vec![
+                        "Produce a 1x3x224x224 CLIP input tensor as .npy".to_string(),
+                        "Write DINOv2 pixel_values to .safetensors".to_string(),
+                        "Return an in-memory SigLIP tensor for a video frame".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec!["writes tensor file when output_path is set".to_string()],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "input_path": { "type": "string", "description": "Image or RAW file" },
+                            "video_path": { "type": "string", "description": "Video file (used when input_path is absent)" },
+                            "timestamp": { "type": "number", "description": "Video frame time in seconds (default: 0)" },
+                            "frames": { "type": "integer", "description": "Animated input_path: sample this many frames evenly over the animation, stacked on the leading axis" },
+                            "preset": { "type": "string", "enum": ["clip", "siglip", "imagenet", "dinov2"], "description": "Preprocessing preset (default: clip)" },
+                            "width": { "type": "integer", "description": "Override width (height defaults to width)" },
+                            "height": { "type": "integer" },
+                            "resize_mode": { "type": "string", "enum": ["fill", "fit", "stretch", "smart"] },
+                            "crop_fraction": { "type": "number", "description": "Center crop fraction for fill (e.g. 0.875)" },
+                            "channel_order": { "type": "string", "enum": ["rgb", "bgr"] },
+                            "layout": { "type": "string", "enum": ["chw", "hwc"] },
+                            "mean": { "type": "array", "items": { "type": "number" }, "minItems": 3, "maxItems": 3 },
+                            "std": { "type": "array", "items": { "type": "number" }, "minItems": 3, "maxItems": 3 },
+                            "batch_dim": { "type": "boolean", "description": "Prepend batch dimension (default: true)" },
+                            "output_path": { "type": "string", "description": "Write .npy or .safetensors; omit to return data inline" },
+                            "format": { "type": "string", "enum": ["npy", "safetensors"], "description": "Output format (default: from extension)" }
+                        }
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "shape": { "type": "array", "items": { "type": "integer" } },
+                            "dtype": { "type": "string" },
+                            "preset": { "type": "string" },
+                            "output_path": { "type": "string" },
+                            "format": { "type": "string" },
+                            "frame_indices": { "type": "array", "items": { "type": "integer" }, "description": "Sampled frame indices (only with frames)" },
+                            "data": { "type": "array", "items": { "type": "number" }, "description": "Flattened tensor (only without output_path)" }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "image.phash".to_string(),
+                    description: "Compute 64-bit perceptual hashes (pHash, dHash, aHash, wavelet) for images, including RAW via the embedded preview".to_string(),
+                    tags: vec!["image".to_string(), "hash".to_string(), "dedupe".to_string(), "similarity".to_string()],
+                    examples: vec![
+                        "Hash a photo library before computing embeddings".to_string(),
+                        "Compute pHash and dHash for a RAW file".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec![],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "input_path": { "type": "string", "description": "Image or RAW file" },
+                            "input_paths": { "type": "array", "items": { "type": "string" }, "description": "Several files (hashed in parallel)" },
+                            "algorithms": { "type": "array", "items": { "type": "string", "enum": ["phash", "dhash", "ahash", "whash"] }, "description": "Hash algorithms (default: [phash])" }
+                        }
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "algorithms": { "type": "array", "items": { "type": "string" } },
+                            "results": { "type": "array", "description": "{path, hashes: {algorithm: hex}} or {path, error}" }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "image.dedupe".to_string(),
+                    description: "Cluster near-duplicate images (re-exports, bursts) by perceptual hash Hamming distance".to_string(),
+                    tags: vec!["image".to_string(), "hash".to_string(), "dedupe".to_string(), "clustering".to_string()],
+                    examples: vec![
+                        "Find bursts and re-exports in a folder of photos".to_string(),
+                        "Cluster previously stored pHashes within distance 6".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec![],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "input_paths": { "type": "array", "items": { "type": "string" }, "description": "Files to hash and cluster" },
+                            "hashes": { "type": "array", "items": { "type": "object", "properties": { "id": { "type": "string" }, "hash": { "type": "string" } } }, "description": "Stored hex hashes instead of files" },
+                            "algorithm": { "type": "string", "enum": ["phash", "dhash", "ahash", "whash"], "description": "Hash used for files (default: phash)" },
+                            "threshold": { "type": "integer", "description": "Max Hamming distance to link two images (default: 8)" }
+                        }
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "clusters": { "type": "array", "description": "{members, max_distance} for groups of 2+" },
+                            "duplicate_count": { "type": "integer" },
+                            "hashes": { "type": "array", "description": "{id, hash} for storing" },
+                            "errors": { "type": "array" }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "image.quality".to_string(),
+                    description: "Score sharpness (with focus map), exposure clipping, noise, contrast and a rough aesthetic value to flag likely rejects".to_string(),
+                    tags: vec!["image".to_string(), "quality".to_string(), "culling".to_string(), "raw".to_string()],
+                    examples: vec![
+                        "Flag blurry or blown-out RAWs from a shoot".to_string(),
+                        "Rank a burst by peak focus".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec![],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "input_path": { "type": "string", "description": "Image or RAW file" },
+                            "input_paths": { "type": "array", "items": { "type": "string" }, "description": "Files to score in parallel" },
+                            "analysis_size": { "type": "integer", "description": "Long edge scored at (default: 1024)" },
+                            "grid": { "type": "integer", "description": "Focus map cells per side (default: 4)" },
+                            "blur_threshold": { "type": "number", "description": "Peak focus below which a file is blurry (default: 100)" },
+                            "max_highlight_clip": { "type": "number", "description": "Blown pixel percentage for overexposed (default: 5)" },
+                            "max_shadow_clip": { "type": "number", "description": "Crushed pixel percentage for underexposed (default: 25)" }
+                        }
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "results": { "type": "array", "description": "Per file: sharpness, focus_map, peak_focus, clipping, noise, contrast, colorfulness, aesthetic, likely_reject, reject_reasons" },
+                            "reject_count": { "type": "integer" }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "image.variants".to_string(),
+                    description: "Decode an image or RAW file once and write several derivatives (thumb, grid, detail, ML sizes) in one call".to_string(),
+                    tags: vec!["image".to_string(), "resize".to_string(), "thumbnail".to_string(), "raw".to_string()],
+                    examples: vec![
+                        "Write 256px, 1024px and 2048px WebP derivatives of a CR3".to_string(),
+                        "Produce a square JPEG thumb and a 336px PNG for CLIP from one decode".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec!["writes image files".to_string()],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "input_path": { "type": "string", "description": "Image or RAW file" },
+                            "variants": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "name": { "type": "string" },
+                                        "width": { "type": "integer" },
+                                        "height": { "type": "integer", "description": "Defaults to width" },
+                                        "resize": { "type": "string", "enum": ["fit", "fill", "stretch", "smart"], "description": "fit: inside the box, no padding or upscaling (default); fill: crop to the box; smart: crop around the subject" },
+                                        "format": { "type": "string", "enum": ["jpg", "png", "webp", "avif", "jxl"], "description": "Default: jpg" },
+                                        "quality": { "type": "integer", "description": "Default: 85" },
+                                        "output_path": { "type": "string" }
+                                    },
+                                    "required": ["width"]
+                                }
+                            },
+                            "output_dir": { "type": "string", "description": "Where variants without output_path go, as <stem>_<name>.<ext>" },
+                            "raw_decode": { "type": "string", "enum": ["preview", "full"], "description": "RAW decoding: embedded preview or full LibRaw (default: preview)" },
+                            "color_space": { "type": "string", "enum": ["srgb", "display_p3", "adobe_rgb"], "description": "Output colour space (default: srgb)" },
+                            "embed_icc": { "type": "boolean", "description": "Tag outputs with the colour profile (default: true)" },
+                            "focus_hints": { "description": "smart variants: \"auto\" or [{x, y, width, height}] normalised to 0-1" },
+                            "metadata": { "type": "string", "enum": ["strip", "whitelist", "scrub"], "description": "Source EXIF/XMP copied to every variant (default: strip)" },
+                            "watermark": { "type": "object", "description": "Logo or text on every variant, sized relative to each (same fields as image.preprocess)" },
+                            "auto_orient": { "type": "boolean", "description": "Apply EXIF orientation (default: true)" }
+                        },
+                        "required": ["input_path", "variants"]
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "variants": { "type": "array", "description": "{name, output_path, width, height, format, bytes, crop} in request order" },
+                            "watermarked": { "type": "boolean" }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "image.tiles".to_string(),
+                    description: "Cut a very large image or full-resolution RAW render into a DeepZoom (.dzi) or IIIF level-0 tile pyramid".to_string(),
+                    tags: vec!["image".to_string(), "tiles".to_string(), "deepzoom".to_string(), "iiif".to_string(), "raw".to_string()],
+                    examples: vec![
+                        "Tile a gigapixel scan for OpenSeadragon".to_string(),
+                        "Publish a 100MP RAW as a static IIIF image service".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec!["writes tile files".to_string()],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "input_path": { "type": "string", "description": "Image or RAW file" },
+                            "output_dir": { "type": "string", "description": "Directory the manifest and tiles are written to" },
+                            "name": { "type": "string", "description": "Pyramid name (default: input file stem)" },
+                            "layout": { "type": "string", "enum": ["dzi", "iiif"], "description": "Default: dzi" },
+                            "tile_size": { "type": "integer", "description": "Tile edge (default: 254 for dzi, 256 for iiif)" },
+                            "overlap": { "type": "integer", "description": "DeepZoom tile overlap (default: 1)" },
+                            "format": { "type": "string", "enum": ["jpg", "png", "webp", "avif", "jxl"], "description": "Tile format (default: jpg)" },
+                            "quality": { "type": "integer", "description": "Default: 85" },
+                            "color_space": { "type": "string", "enum": ["srgb", "display_p3", "adobe_rgb"], "description": "Output colour space (default: srgb)" },
+                            "raw_decode": { "type": "string", "enum": ["full", "preview"], "description": "RAW decoding (default: full)" },
+                            "base_url": { "type": "string", "description": "Public URL of output_dir, for the IIIF id" }
+                        },
+                        "required": ["input_path", "output_dir"]
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "manifest_path": { "type": "string", "description": ".dzi or info.json" },
+                            "levels": { "type": "integer" },
+                            "tile_count": { "type": "integer" }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "image.colors".to_string(),
+                    description: "Extract the dominant colour palette with pixel shares, average colour, colourfulness and light/dark tone".to_string(),
+                    tags: vec!["image".to_string(), "color".to_string(), "palette".to_string(), "search".to_string()],
+                    examples: vec![
+                        "Index colour facets (\"blue\", \"orange\") for asset search".to_string(),
+                        "Show a 5-colour palette on an asset card".to_string(),
+                        "Summarise a clip's colours from a 9-frame storyboard".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec![],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "input_path": { "type": "string", "description": "Image or RAW file (RAW via embedded preview)" },
+                            "input_paths": { "type": "array", "items": { "type": "string" }, "description": "Files to analyse in parallel" },
+                            "video_path": { "type": "string", "description": "Video to summarise from evenly sampled storyboard frames" },
+                            "colors": { "type": "integer", "minimum": 1, "maximum": 32, "description": "Palette size (default: 5)" },
+                            "method": { "type": "string", "enum": ["kmeans", "median_cut"], "description": "Clustering in OKLab (default: kmeans)" },
+                            "analysis_size": { "type": "integer", "description": "Long edge analysed at (default: 200)" },
+                            "storyboard_frames": { "type": "integer", "description": "Frames sampled from video_path (default: 9)" }
+                        }
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "results": { "type": "array", "description": "Per file: palette [{hex, rgb, oklab, share, name, light}], average, colorfulness, lightness, tone" },
+                            "palette": { "type": "array", "description": "video_path: storyboard palette, largest share first" },
+                            "tone": { "type": "string", "enum": ["light", "dark"] },
+                            "frames": { "type": "integer", "description": "video_path: frames pooled" }
+                        }
+                    }),
+                },
+                FunctionCard {
+                    name: "image.placeholder".to_string(),
+                    description: "Compute BlurHash and ThumbHash placeholder strings for images and RAW files".to_string(),
+                    tags: vec!["image".to_string(), "placeholder".to_string(), "blurhash".to_string(), "thumbhash".to_string(), "web".to_string()],
+                    examples: vec![
+                        "Backfill BlurHash strings for an existing library".to_string(),
+                        "Get a ThumbHash for a CR3 from its embedded preview".to_string(),
+                    ],
+                    idempotent: true,
+                    side_effects: vec![],
+                    input_schema: Some(json!({
+                        "type": "object",
+                        "properties": {
+                            "input_path": { "type": "string", "description": "Image or RAW file" },
+                            "input_paths": { "type": "array", "items": { "type": "string" }, "description": "Files to hash in parallel" },
+                            "components_x": { "type": "integer", "minimum": 1, "maximum": 9, "description": "BlurHash horizontal components (default: 4, 3 for portrait)" },
+                            "components_y": { "type": "integer", "minimum": 1, "maximum": 9, "description": "BlurHash vertical components (default: 3, 4 for portrait)" }
+                        }
+                    })),
+                    output_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "results": { "type": "array", "description": "Per file: {path, width, height, blurhash, thumbhash (base64)}" }
                         }
                     }),
                 },
@@ -620,4 +2277,28 @@ mod tests {
         assert!(card.tags.contains(&"audio".to_string()));
         assert!(card.tags.contains(&"video".to_string()));
     }
+    
+    #[test]
+    fn test_input_path_list_rejects_non_strings() {
+        let paths = input_path_list(&json!({ "input_paths": ["a.jpg", "b.jpg"] })).unwrap();
+        assert_eq!(paths, vec!["a.jpg", "b.jpg"]);
+        
+        match input_path_list(&json!({ "input_paths": ["a.jpg", 7] })) {
+            Err(OrganError::InvalidInput(message)) => assert!(message.contains("input_paths[1]")),
+            other => panic!("expected InvalidInput, got {:?}", other.map(|_| ())),
+        }
+    }
+    
+    #[test]
+    fn test_transcode_config_rejects_out_of_range_crf() {
+        let config = transcode_config_from_input(&json!({ "codec": "vp9", "crf": 60 })).unwrap();
+        assert_eq!(config.crf, 60);
+        
+        for input in [json!({ "crf": 300 }), json!({ "crf": 52 }), json!({ "crf": -1 })] {
+            match transcode_config_from_input(&input) {
+                Err(OrganError::InvalidInput(message)) => assert!(message.contains("crf")),
+                other => panic!("expected InvalidInput, got {:?}", other.map(|_| ())),
+            }
+        }
+    }
 }
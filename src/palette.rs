@@ -0,0 +1,447 @@
+//! Dominant colour palettes and colour statistics
+//!
+//! Pixels are clustered in OKLab, where Euclidean distance tracks perceived
+//! colour difference, so a palette does not split one sky into three blues
+//! while merging a red and an orange. Median cut gives deterministic starting
+//! boxes; k-means optionally refines them (Lloyd iterations, same input →
+//! same palette).
+//!
+//! Besides the palette, a report carries the average colour, Hasler–Süsstrunk
+//! colourfulness, mean OKLab lightness with a light/dark classification, and
+//! a coarse colour name per swatch for search facets.
+//!
+//! Videos are summarised as a storyboard: frames sampled evenly across the
+//! duration are pooled into one palette.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use soma_media::{ColorAnalyzer, ColorConfig};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let report = ColorAnalyzer::new(ColorConfig::default()).analyze_path("IMG_0412.CR3")?;
+//! for swatch in &report.palette {
+//!     println!("{} {} {:.0}%", swatch.hex, swatch.name, swatch.share * 100.0);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::ffmpeg::{FfmpegCommand, FfmpegError};
+use crate::image::load_any_image;
+use crate::quality::colorfulness;
+use crate::raw::PreviewOptions;
+use crate::video::{VideoConfig, VideoPreprocessor};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Clustering used to pick the palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteMethod {
+    /// Median cut only: fast, boxes follow the colour distribution
+    MedianCut,
+    /// Median cut refined by k-means: tighter clusters (default)
+    KMeans,
+}
+
+impl PaletteMethod {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "median_cut" | "mediancut" | "median-cut" => Some(Self::MedianCut),
+            "kmeans" | "k-means" | "k_means" => Some(Self::KMeans),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::MedianCut => "median_cut",
+            Self::KMeans => "kmeans",
+        }
+    }
+}
+
+/// Settings for [`ColorAnalyzer`]
+#[derive(Debug, Clone)]
+pub struct ColorConfig {
+    /// Number of palette colours
+    pub colors: usize,
+    pub method: PaletteMethod,
+    /// Long edge images (and storyboard frames) are scaled to before clustering
+    pub analysis_size: u32,
+    /// Maximum k-means iterations
+    pub iterations: usize,
+    /// Frames sampled across a video for its storyboard palette
+    pub storyboard_frames: usize,
+}
+
+impl Default for ColorConfig {
+    fn default() -> Self {
+        Self {
+            colors: 5,
+            method: PaletteMethod::KMeans,
+            analysis_size: 200,
+            iterations: 12,
+            storyboard_frames: 9,
+        }
+    }
+}
+
+/// One palette entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Swatch {
+    /// `#rrggbb`
+    pub hex: String,
+    pub rgb: [u8; 3],
+    /// OKLab `[L, a, b]`
+    pub oklab: [f32; 3],
+    /// Fraction of analysed pixels in this cluster (0.0-1.0)
+    pub share: f32,
+    /// Coarse colour family for facets ("red", "blue", "gray", ...)
+    pub name: String,
+    /// Light enough for dark text on top
+    pub light: bool,
+}
+
+/// Result of [`ColorAnalyzer::analyze_image`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColorReport {
+    /// Dominant colours, largest share first
+    pub palette: Vec<Swatch>,
+    /// Mean colour (averaged in OKLab)
+    pub average: Swatch,
+    /// Hasler–Süsstrunk colourfulness (0 = gray, ~100+ = vivid)
+    pub colorfulness: f64,
+    /// Mean OKLab lightness (0.0-1.0)
+    pub lightness: f32,
+    /// "light" or "dark"
+    pub tone: String,
+    /// Frames pooled (videos only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frames: Option<usize>,
+}
+
+/// Palette and colour statistics extractor
+pub struct ColorAnalyzer {
+    config: ColorConfig,
+}
+
+impl ColorAnalyzer {
+    pub fn new(config: ColorConfig) -> Self {
+        Self { config }
+    }
+    
+    /// Analyse an image file (RAW files via their embedded preview)
+    pub fn analyze_path(&self, path: impl AsRef<Path>) -> Result<ColorReport, FfmpegError> {
+        let preview = PreviewOptions {
+            max_dimension: Some(self.config.analysis_size),
+            ..PreviewOptions::default()
+        };
+        let image = load_any_image(path.as_ref(), &preview)?;
+        Ok(self.analyze_image(&image))
+    }
+    
+    /// Analyse a decoded image
+    pub fn analyze_image(&self, image: &DynamicImage) -> ColorReport {
+        self.analyze_pixels(&self.scaled_rgb(image), None)
+    }
+    
+    /// Pool frames sampled evenly across a video into one storyboard palette
+    pub fn analyze_video(&self, video: impl AsRef<Path>) -> Result<ColorReport, FfmpegError> {
+        let video = video.as_ref();
+        let count = self.config.storyboard_frames.max(1);
+        let offsets: Vec<f64> = match VideoPreprocessor::new(VideoConfig::default()).probe(video).duration {
+            Some(duration) if duration > 0.0 => (0..count)
+                .map(|i| duration * (i as f64 + 0.5) / count as f64)
+                .collect(),
+            _ => vec![0.0],
+        };
+        
+        let size = self.config.analysis_size;
+        let scale = format!("scale={}:{}:force_original_aspect_ratio=decrease", size, size);
+        let mut pixels = Vec::new();
+        let mut frames = 0;
+        for offset in offsets {
+            let output = FfmpegCommand::new()
+                .args(&["-ss", &format!("{:.3}", offset)])
+                .input(video)
+                .args(&["-vf", &scale, "-frames:v", "1", "-f", "image2pipe", "-vcodec", "png"])
+                .output("-")
+                .execute()?;
+            // Offsets past the last decodable frame yield no image; skip them
+            if let Ok(frame) = image::load_from_memory_with_format(&output.stdout, image::ImageFormat::Png) {
+                pixels.extend_from_slice(frame.to_rgb8().as_raw());
+                frames += 1;
+            }
+        }
+        if frames == 0 {
+            return Err(FfmpegError::InvalidOutput(format!("No frames decoded from {}", video.display())));
+        }
+        
+        Ok(self.analyze_pixels(&pixels, Some(frames)))
+    }
+    
+    fn scaled_rgb(&self, image: &DynamicImage) -> Vec<u8> {
+        let size = self.config.analysis_size;
+        if image.width().max(image.height()) > size {
+            image.resize(size, size, FilterType::Triangle).to_rgb8().into_raw()
+        } else {
+            image.to_rgb8().into_raw()
+        }
+    }
+    
+    /// Palette and statistics for packed RGB8 pixels
+    fn analyze_pixels(&self, rgb: &[u8], frames: Option<usize>) -> ColorReport {
+        let lab: Vec<[f32; 3]> = rgb.chunks_exact(3).map(|p| srgb_to_oklab([p[0], p[1], p[2]])).collect();
+        let n = lab.len().max(1) as f32;
+        
+        let mut mean = [0.0f32; 3];
+        for p in &lab {
+            for c in 0..3 {
+                mean[c] += p[c] / n;
+            }
+        }
+        
+        let mut centroids = median_cut(&lab, self.config.colors.max(1));
+        if self.config.method == PaletteMethod::KMeans {
+            kmeans(&lab, &mut centroids, self.config.iterations);
+        }
+        
+        let mut counts = vec![0usize; centroids.len()];
+        for p in &lab {
+            counts[nearest(p, &centroids)] += 1;
+        }
+        let mut palette: Vec<Swatch> = centroids
+            .iter()
+            .zip(&counts)
+            .filter(|(_, &count)| count > 0)
+            .map(|(c, &count)| swatch(*c, count as f32 / n))
+            .collect();
+        palette.sort_by(|a, b| b.share.total_cmp(&a.share));
+        
+        ColorReport {
+            palette,
+            average: swatch(mean, 1.0),
+            colorfulness: colorfulness(rgb),
+            lightness: mean[0],
+            tone: if mean[0] >= LIGHT_THRESHOLD { "light" } else { "dark" }.to_string(),
+            frames,
+        }
+    }
+}
+
+/// OKLab lightness splitting light from dark (mid gray sRGB 119 ≈ 0.57)
+const LIGHT_THRESHOLD: f32 = 0.6;
+
+fn swatch(lab: [f32; 3], share: f32) -> Swatch {
+    let rgb = oklab_to_srgb(lab);
+    Swatch {
+        hex: format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2]),
+        rgb,
+        oklab: lab,
+        share,
+        name: color_name(lab).to_string(),
+        light: lab[0] >= LIGHT_THRESHOLD,
+    }
+}
+
+/// Coarse colour family from OKLCh lightness, chroma and hue
+fn color_name(lab: [f32; 3]) -> &'static str {
+    let [l, a, b] = lab;
+    let chroma = (a * a + b * b).sqrt();
+    if chroma < 0.035 {
+        return match l {
+            l if l < 0.3 => "black",
+            l if l > 0.9 => "white",
+            _ => "gray",
+        };
+    }
+    
+    let hue = b.atan2(a).to_degrees().rem_euclid(360.0);
+    match hue {
+        h if !(45.0..345.0).contains(&h) => if l > 0.75 { "pink" } else { "red" },
+        h if h < 80.0 => if l < 0.55 { "brown" } else { "orange" },
+        h if h < 120.0 => if l < 0.55 { "brown" } else { "yellow" },
+        h if h < 170.0 => "green",
+        h if h < 225.0 => "cyan",
+        h if h < 290.0 => "blue",
+        h if h < 320.0 => "purple",
+        _ => "pink",
+    }
+}
+
+/// Split the box with the widest spread on its longest axis at the median,
+/// until `k` boxes; returns each box's mean
+fn median_cut(pixels: &[[f32; 3]], k: usize) -> Vec<[f32; 3]> {
+    if pixels.is_empty() {
+        return Vec::new();
+    }
+    
+    let mut boxes: Vec<Vec<[f32; 3]>> = vec![pixels.to_vec()];
+    while boxes.len() < k {
+        // Weight the range by population so large flat areas still get split
+        let (index, axis, score) = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let (axis, range) = longest_axis(b);
+                (i, axis, range * (b.len() as f32).sqrt())
+            })
+            .fold((0, 0, 0.0f32), |best, cur| if cur.2 > best.2 { cur } else { best });
+        if score <= 0.0 || boxes[index].len() < 2 {
+            break;
+        }
+        
+        let mut split = boxes.swap_remove(index);
+        split.sort_by(|a, b| a[axis].total_cmp(&b[axis]));
+        let upper = split.split_off(split.len() / 2);
+        boxes.push(split);
+        boxes.push(upper);
+    }
+    
+    boxes.iter().map(|b| mean_of(b)).collect()
+}
+
+fn longest_axis(pixels: &[[f32; 3]]) -> (usize, f32) {
+    (0..3)
+        .map(|c| {
+            let (min, max) = pixels.iter().fold((f32::MAX, f32::MIN), |(lo, hi), p| (lo.min(p[c]), hi.max(p[c])));
+            (c, max - min)
+        })
+        .fold((0, 0.0), |best, cur| if cur.1 > best.1 { cur } else { best })
+}
+
+fn mean_of(pixels: &[[f32; 3]]) -> [f32; 3] {
+    let n = pixels.len().max(1) as f32;
+    let mut sum = [0.0f32; 3];
+    for p in pixels {
+        for c in 0..3 {
+            sum[c] += p[c];
+        }
+    }
+    sum.map(|s| s / n)
+}
+
+/// Lloyd iterations from the given centroids; stops early once stable
+fn kmeans(pixels: &[[f32; 3]], centroids: &mut [[f32; 3]], iterations: usize) {
+    for _ in 0..iterations {
+        let mut sums = vec![[0.0f32; 3]; centroids.len()];
+        let mut counts = vec![0usize; centroids.len()];
+        for p in pixels {
+            let i = nearest(p, centroids);
+            for c in 0..3 {
+                sums[i][c] += p[c];
+            }
+            counts[i] += 1;
+        }
+        
+        let mut moved = 0.0f32;
+        for (i, centroid) in centroids.iter_mut().enumerate() {
+            // An emptied cluster keeps its old centre
+            if counts[i] > 0 {
+                let updated = sums[i].map(|s| s / counts[i] as f32);
+                moved = moved.max(distance(centroid, &updated));
+                *centroid = updated;
+            }
+        }
+        if moved < 1e-4 {
+            break;
+        }
+    }
+}
+
+fn nearest(p: &[f32; 3], centroids: &[[f32; 3]]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i, distance(p, c)))
+        .fold((0, f32::MAX), |best, cur| if cur.1 < best.1 { cur } else { best })
+        .0
+}
+
+fn distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    (0..3).map(|c| (a[c] - b[c]).powi(2)).sum()
+}
+
+fn srgb_to_linear(v: u8) -> f32 {
+    let v = v as f32 / 255.0;
+    if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(v: f32) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let v = if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+    (v * 255.0).round() as u8
+}
+
+/// sRGB → OKLab (Björn Ottosson's matrices)
+fn srgb_to_oklab(rgb: [u8; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb.map(srgb_to_linear);
+    let l = (0.41222147 * r + 0.53633254 * g + 0.05144599 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+    [
+        0.21045426 * l + 0.7936178 * m - 0.00407205 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.02590404 * l + 0.78277177 * m - 0.80867577 * s,
+    ]
+}
+
+fn oklab_to_srgb(lab: [f32; 3]) -> [u8; 3] {
+    let [l, a, b] = lab;
+    let l_ = (l + 0.39633778 * a + 0.21580376 * b).powi(3);
+    let m_ = (l - 0.10556135 * a - 0.06385417 * b).powi(3);
+    let s_ = (l - 0.08948418 * a - 1.2914855 * b).powi(3);
+    [
+        4.0767417 * l_ - 3.3077116 * m_ + 0.23096993 * s_,
+        -1.268438 * l_ + 2.6097574 * m_ - 0.3413194 * s_,
+        -0.00419609 * l_ - 0.7034186 * m_ + 1.7076147 * s_,
+    ]
+    .map(linear_to_srgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    
+    #[test]
+    fn test_palette_shares_and_names() {
+        // 3/4 blue sky over 1/4 dark green ground
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(120, 80, |_, y| {
+            if y < 60 { Rgb([40, 110, 220]) } else { Rgb([30, 90, 40]) }
+        }));
+        
+        for method in [PaletteMethod::KMeans, PaletteMethod::MedianCut] {
+            let config = ColorConfig { colors: 4, method, ..ColorConfig::default() };
+            let report = ColorAnalyzer::new(config).analyze_image(&image);
+            
+            let top = &report.palette[0];
+            assert_eq!(top.name, "blue");
+            assert!((top.share - 0.75).abs() < 0.02, "share {}", top.share);
+            assert!(report.palette.iter().any(|s| s.name == "green"));
+            assert!((report.palette.iter().map(|s| s.share).sum::<f32>() - 1.0).abs() < 1e-3);
+            assert_eq!(report.tone, "dark");
+            assert!(report.colorfulness > 20.0);
+        }
+    }
+    
+    #[test]
+    fn test_oklab_round_trip_and_neutrals() {
+        for rgb in [[0, 0, 0], [255, 255, 255], [200, 30, 60], [12, 200, 180]] {
+            assert_eq!(oklab_to_srgb(srgb_to_oklab(rgb)), rgb);
+        }
+        assert_eq!(color_name(srgb_to_oklab([128, 128, 128])), "gray");
+        assert_eq!(color_name(srgb_to_oklab([250, 250, 250])), "white");
+        assert_eq!(color_name(srgb_to_oklab([220, 30, 30])), "red");
+        assert_eq!(color_name(srgb_to_oklab([240, 200, 40])), "yellow");
+        
+        let white = DynamicImage::ImageRgb8(RgbImage::from_pixel(10, 10, Rgb([245, 245, 240])));
+        let report = ColorAnalyzer::new(ColorConfig::default()).analyze_image(&white);
+        assert_eq!(report.palette.len(), 1);
+        assert_eq!(report.tone, "light");
+        assert!(report.average.light);
+    }
+}
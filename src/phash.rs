@@ -0,0 +1,277 @@
+//! Perceptual image hashing
+//!
+//! DCT-based pHash: the image is reduced to 32x32 grayscale, transformed with a
+//! 2D DCT, and the 8x8 low-frequency block (minus the DC term) is thresholded
+//! against its median. Visually similar images produce hashes with a small
+//! Hamming distance.
+//!
+//! Also provides aHash (mean), dHash (horizontal gradient) and a Haar wavelet
+//! hash, all 64-bit, plus Hamming-threshold clustering for near-duplicates.
+
+use crate::ffmpeg::FfmpegError;
+use crate::image::load_any_image;
+use crate::raw::PreviewOptions;
+use image::DynamicImage;
+use image::imageops::FilterType;
+use std::path::Path;
+
+/// Side of the grayscale image fed to the DCT
+const DCT_SIZE: usize = 32;
+
+/// Side of the low-frequency block kept from the DCT
+const HASH_SIZE: usize = 8;
+
+/// Perceptual hash algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// DCT low frequencies vs. median (robust to resize, gamma, compression)
+    PHash,
+    /// Sign of horizontal gradients on a 9x8 thumbnail (fast, crop-sensitive)
+    DHash,
+    /// 8x8 thumbnail vs. its mean (fastest, least robust)
+    AHash,
+    /// Haar wavelet LL band vs. median
+    WHash,
+}
+
+impl HashAlgorithm {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "phash" => Some(HashAlgorithm::PHash),
+            "dhash" => Some(HashAlgorithm::DHash),
+            "ahash" => Some(HashAlgorithm::AHash),
+            "whash" | "wavelet" => Some(HashAlgorithm::WHash),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &str {
+        match self {
+            HashAlgorithm::PHash => "phash",
+            HashAlgorithm::DHash => "dhash",
+            HashAlgorithm::AHash => "ahash",
+            HashAlgorithm::WHash => "whash",
+        }
+    }
+    
+    /// Hash a decoded image
+    pub fn hash(&self, image: &DynamicImage) -> u64 {
+        match self {
+            HashAlgorithm::PHash => phash(image),
+            HashAlgorithm::DHash => dhash(image),
+            HashAlgorithm::AHash => ahash(image),
+            HashAlgorithm::WHash => whash(image),
+        }
+    }
+    
+    /// Hash an image file (RAW files via their embedded preview)
+    pub fn hash_file(&self, path: impl AsRef<Path>) -> Result<u64, FfmpegError> {
+        // Hashes only look at a 32-64px thumbnail, so a small preview is plenty
+        let preview = PreviewOptions { max_dimension: Some(512), ..PreviewOptions::default() };
+        let image = load_any_image(path.as_ref(), &preview)?;
+        Ok(self.hash(&image))
+    }
+}
+
+/// 64-bit DCT perceptual hash
+pub(crate) fn phash(image: &DynamicImage) -> u64 {
+    let gray = image
+        .resize_exact(DCT_SIZE as u32, DCT_SIZE as u32, FilterType::Triangle)
+        .to_luma8();
+    let pixels: Vec<f64> = gray.as_raw().iter().map(|&p| p as f64).collect();
+    
+    let dct = dct_2d(&pixels, DCT_SIZE);
+    
+    let mut block = Vec::with_capacity(HASH_SIZE * HASH_SIZE);
+    for y in 0..HASH_SIZE {
+        for x in 0..HASH_SIZE {
+            block.push(dct[y * DCT_SIZE + x]);
+        }
+    }
+    
+    // Median of the AC coefficients (DC dominates and would skew it)
+    let mut sorted: Vec<f64> = block[1..].to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let median = sorted[sorted.len() / 2];
+    
+    block.iter().enumerate().fold(0u64, |hash, (i, &value)| {
+        if i > 0 && value > median { hash | (1 << i) } else { hash }
+    })
+}
+
+/// 64-bit difference hash: is each pixel brighter than its right neighbour
+pub(crate) fn dhash(image: &DynamicImage) -> u64 {
+    let gray = image
+        .resize_exact(HASH_SIZE as u32 + 1, HASH_SIZE as u32, FilterType::Triangle)
+        .to_luma8();
+    
+    let mut hash = 0u64;
+    for y in 0..HASH_SIZE as u32 {
+        for x in 0..HASH_SIZE as u32 {
+            if gray.get_pixel(x, y)[0] > gray.get_pixel(x + 1, y)[0] {
+                hash |= 1 << (y * HASH_SIZE as u32 + x);
+            }
+        }
+    }
+    hash
+}
+
+/// 64-bit average hash: is each pixel of an 8x8 thumbnail above the mean
+pub(crate) fn ahash(image: &DynamicImage) -> u64 {
+    let gray = image
+        .resize_exact(HASH_SIZE as u32, HASH_SIZE as u32, FilterType::Triangle)
+        .to_luma8();
+    let pixels: Vec<f64> = gray.as_raw().iter().map(|&p| p as f64).collect();
+    let mean = pixels.iter().sum::<f64>() / pixels.len() as f64;
+    
+    threshold_bits(&pixels, mean)
+}
+
+/// 64-bit wavelet hash: LL band of a 3-level Haar transform vs. its median
+pub(crate) fn whash(image: &DynamicImage) -> u64 {
+    let size = HASH_SIZE * 8;
+    let gray = image
+        .resize_exact(size as u32, size as u32, FilterType::Triangle)
+        .to_luma8();
+    let mut band: Vec<f64> = gray.as_raw().iter().map(|&p| p as f64 / 255.0).collect();
+    
+    // Each level keeps the 2x2 averages (LL band) and halves the side
+    let mut side = size;
+    while side > HASH_SIZE {
+        let half = side / 2;
+        let mut next = vec![0.0; half * half];
+        for y in 0..half {
+            for x in 0..half {
+                let i = 2 * y * side + 2 * x;
+                next[y * half + x] = (band[i] + band[i + 1] + band[i + side] + band[i + side + 1]) / 4.0;
+            }
+        }
+        band = next;
+        side = half;
+    }
+    
+    let mut sorted = band.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    threshold_bits(&band, sorted[sorted.len() / 2])
+}
+
+/// Set bit `i` where `values[i] > threshold`
+fn threshold_bits(values: &[f64], threshold: f64) -> u64 {
+    values.iter().enumerate().fold(0u64, |hash, (i, &value)| {
+        if value > threshold { hash | (1 << i) } else { hash }
+    })
+}
+
+/// Number of differing bits between two hashes
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Group hashes whose Hamming distance is within `threshold` (single linkage)
+///
+/// Returns clusters of two or more indices into `hashes`, each sorted, in
+/// order of their first member. Singletons are left out.
+pub fn cluster_hashes(hashes: &[u64], threshold: u32) -> Vec<Vec<usize>> {
+    // Union-find over all pairs; O(n²) popcounts is fine for library batches
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    
+    for i in 0..hashes.len() {
+        for j in i + 1..hashes.len() {
+            if hamming_distance(hashes[i], hashes[j]) <= threshold {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                if a != b {
+                    parent[b.max(a)] = a.min(b);
+                }
+            }
+        }
+    }
+    
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    let mut cluster_of_root = std::collections::HashMap::new();
+    for i in 0..hashes.len() {
+        let r = root(&mut parent, i);
+        let index = *cluster_of_root.entry(r).or_insert_with(|| {
+            clusters.push(Vec::new());
+            clusters.len() - 1
+        });
+        clusters[index].push(i);
+    }
+    
+    clusters.retain(|cluster| cluster.len() > 1);
+    clusters
+}
+
+/// Separable type-II DCT of a square `n`x`n` image
+fn dct_2d(pixels: &[f64], n: usize) -> Vec<f64> {
+    let mut cos_table = vec![0.0; n * n];
+    for k in 0..n {
+        for i in 0..n {
+            cos_table[k * n + i] = (std::f64::consts::PI * (2 * i + 1) as f64 * k as f64 / (2 * n) as f64).cos();
+        }
+    }
+    
+    let mut rows = vec![0.0; n * n];
+    for y in 0..n {
+        for k in 0..n {
+            rows[y * n + k] = (0..n).map(|i| pixels[y * n + i] * cos_table[k * n + i]).sum();
+        }
+    }
+    
+    let mut out = vec![0.0; n * n];
+    for x in 0..n {
+        for k in 0..n {
+            out[k * n + x] = (0..n).map(|i| rows[i * n + x] * cos_table[k * n + i]).sum();
+        }
+    }
+    
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    
+    fn texture(transpose: bool) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(64, 64, |x, y| {
+            let (x, y) = if transpose { (y as f32, x as f32) } else { (x as f32, y as f32) };
+            let v = 128.0 + 60.0 * (x * 0.21).sin() + 40.0 * (y * 0.33 + x * 0.1).cos();
+            Rgb([v as u8, v as u8, v as u8])
+        }))
+    }
+    
+    #[test]
+    fn test_phash_similarity() {
+        let a = texture(false);
+        let resized = a.resize_exact(48, 48, FilterType::Triangle).brighten(10);
+        let other = texture(true);
+        
+        assert_eq!(hamming_distance(phash(&a), phash(&a)), 0);
+        assert!(hamming_distance(phash(&a), phash(&resized)) <= 4);
+        assert!(hamming_distance(phash(&a), phash(&other)) > 10);
+    }
+    
+    #[test]
+    fn test_other_hashes_and_clustering() {
+        let a = texture(false);
+        let resized = a.resize_exact(48, 48, FilterType::Triangle);
+        let other = texture(true);
+        
+        for algorithm in [HashAlgorithm::DHash, HashAlgorithm::AHash, HashAlgorithm::WHash] {
+            let (ha, hr, ho) = (algorithm.hash(&a), algorithm.hash(&resized), algorithm.hash(&other));
+            assert!(hamming_distance(ha, hr) <= 6, "{:?}", algorithm);
+            assert!(hamming_distance(ha, ho) > 10, "{:?}", algorithm);
+        }
+        
+        let hashes = [0u64, 0xFFFF_0000_0000_0000, 0b111, u64::MAX, 0xFFFF_0000_0000_0001];
+        assert_eq!(cluster_hashes(&hashes, 3), vec![vec![0, 2], vec![1, 4]]);
+        assert!(cluster_hashes(&hashes, 0).is_empty());
+    }
+}
@@ -0,0 +1,314 @@
+//! BlurHash and ThumbHash placeholders
+//!
+//! Both encode a handful of DCT coefficients of a tiny copy of the image into
+//! a short string a web client can paint as a blurred stand-in while the
+//! real image loads. BlurHash (base83) is the widely supported one; ThumbHash
+//! (binary, sent base64) also keeps the aspect ratio and alpha and renders
+//! closer to the original.
+//!
+//! Hashes are computed from pixels that are already decoded (the output of
+//! `image.preprocess`, a RAW preview, extracted frames), so nothing is
+//! decoded a second time.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use soma_media::placeholders;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let image = image::open("photo.jpg")?;
+//! let hashes = placeholders(&image);
+//! println!("{} {}", hashes.blurhash, hashes.thumbhash);
+//! # Ok(())
+//! # }
+//! ```
+
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Long edge BlurHash is computed at; more pixels don't change the result
+const BLURHASH_SIZE: u32 = 64;
+
+/// ThumbHash input must fit in 100x100
+const THUMBHASH_SIZE: u32 = 100;
+
+const BASE83: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// Placeholder strings for one image
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Placeholders {
+    pub blurhash: String,
+    /// Base64 (standard alphabet, padded)
+    pub thumbhash: String,
+}
+
+/// BlurHash (4x3 components, 3x4 for portrait) and ThumbHash of an image
+pub fn placeholders(image: &DynamicImage) -> Placeholders {
+    let (x, y) = if image.height() > image.width() { (3, 4) } else { (4, 3) };
+    Placeholders {
+        blurhash: blurhash(image, x, y),
+        thumbhash: base64(&thumbhash(image)),
+    }
+}
+
+/// Encode a BlurHash with `components_x` x `components_y` (1-9 each) terms
+pub fn blurhash(image: &DynamicImage, components_x: u32, components_y: u32) -> String {
+    let (cx, cy) = (components_x.clamp(1, 9) as usize, components_y.clamp(1, 9) as usize);
+    let rgb = shrink(image, BLURHASH_SIZE).to_rgb8();
+    let (width, height) = (rgb.width() as usize, rgb.height() as usize);
+    let linear: Vec<[f64; 3]> = rgb.pixels().map(|p| p.0.map(srgb_to_linear)).collect();
+    
+    let mut factors = Vec::with_capacity(cx * cy);
+    for j in 0..cy {
+        for i in 0..cx {
+            let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+            let mut factor = [0.0f64; 3];
+            for y in 0..height {
+                let basis_y = (PI * j as f64 * y as f64 / height as f64).cos();
+                for x in 0..width {
+                    let basis = basis_y * (PI * i as f64 * x as f64 / width as f64).cos();
+                    let pixel = linear[y * width + x];
+                    for c in 0..3 {
+                        factor[c] += basis * pixel[c];
+                    }
+                }
+            }
+            let scale = normalisation / (width * height) as f64;
+            factors.push(factor.map(|f| f * scale));
+        }
+    }
+    
+    let mut hash = String::new();
+    encode83((cx - 1) + (cy - 1) * 9, 1, &mut hash);
+    
+    let (dc, ac) = factors.split_first().expect("at least one component");
+    let maximum = if ac.is_empty() {
+        encode83(0, 1, &mut hash);
+        1.0
+    } else {
+        let actual = ac.iter().flatten().fold(0.0f64, |m, f| m.max(f.abs()));
+        let quantised = ((actual * 166.0 - 0.5).floor()).clamp(0.0, 82.0) as usize;
+        encode83(quantised, 1, &mut hash);
+        (quantised + 1) as f64 / 166.0
+    };
+    
+    let [r, g, b] = dc.map(linear_to_srgb);
+    encode83(((r as usize) << 16) | ((g as usize) << 8) | b as usize, 4, &mut hash);
+    for factor in ac {
+        let [r, g, b] = factor.map(|f| {
+            let v = (f / maximum).signum() * (f / maximum).abs().sqrt();
+            (v * 9.0 + 9.5).floor().clamp(0.0, 18.0) as usize
+        });
+        encode83(r * 19 * 19 + g * 19 + b, 2, &mut hash);
+    }
+    hash
+}
+
+/// Encode a ThumbHash (raw bytes; see [`placeholders`] for the base64 form)
+pub fn thumbhash(image: &DynamicImage) -> Vec<u8> {
+    let rgba = shrink(image, THUMBHASH_SIZE).to_rgba8();
+    let (w, h) = (rgba.width() as usize, rgba.height() as usize);
+    // Operation order follows the reference encoder so rounding matches it
+    let pixels: Vec<[f64; 4]> = rgba.pixels().map(|p| p.0.map(|v| v as f64)).collect();
+    
+    // Average colour, weighted by alpha
+    let (mut avg_r, mut avg_g, mut avg_b, mut avg_a) = (0.0f64, 0.0f64, 0.0f64, 0.0f64);
+    for [r, g, b, a] in &pixels {
+        let a = a / 255.0;
+        avg_r += a / 255.0 * r;
+        avg_g += a / 255.0 * g;
+        avg_b += a / 255.0 * b;
+        avg_a += a;
+    }
+    if avg_a > 0.0 {
+        avg_r /= avg_a;
+        avg_g /= avg_a;
+        avg_b /= avg_a;
+    }
+    
+    let has_alpha = avg_a < (w * h) as f64;
+    let l_limit = if has_alpha { 5.0 } else { 7.0 };  // Fewer luminance bits with alpha
+    let long = w.max(h) as f64;
+    let lx = ((l_limit * w as f64 / long).round() as usize).max(1);
+    let ly = ((l_limit * h as f64 / long).round() as usize).max(1);
+    
+    // RGBA → LPQA, composited over the average colour
+    let mut channels = [vec![0.0f64; w * h], vec![0.0f64; w * h], vec![0.0f64; w * h], vec![0.0f64; w * h]];
+    for (i, [r, g, b, a]) in pixels.iter().enumerate() {
+        let a = a / 255.0;
+        let r = avg_r * (1.0 - a) + a / 255.0 * r;
+        let g = avg_g * (1.0 - a) + a / 255.0 * g;
+        let b = avg_b * (1.0 - a) + a / 255.0 * b;
+        channels[0][i] = (r + g + b) / 3.0;
+        channels[1][i] = (r + g) / 2.0 - b;
+        channels[2][i] = r - g;
+        channels[3][i] = a;
+    }
+    
+    let (l_dc, l_ac, l_scale) = encode_channel(&channels[0], w, h, lx.max(3), ly.max(3));
+    let (p_dc, p_ac, p_scale) = encode_channel(&channels[1], w, h, 3, 3);
+    let (q_dc, q_ac, q_scale) = encode_channel(&channels[2], w, h, 3, 3);
+    let alpha = has_alpha.then(|| encode_channel(&channels[3], w, h, 5, 5));
+    
+    let landscape = w > h;
+    let header24 = (63.0 * l_dc).round() as u32
+        | ((31.5 + 31.5 * p_dc).round() as u32) << 6
+        | ((31.5 + 31.5 * q_dc).round() as u32) << 12
+        | ((31.0 * l_scale).round() as u32) << 18
+        | (has_alpha as u32) << 23;
+    let header16 = (if landscape { ly } else { lx }) as u32
+        | ((63.0 * p_scale).round() as u32) << 3
+        | ((63.0 * q_scale).round() as u32) << 9
+        | (landscape as u32) << 15;
+    let mut hash = vec![
+        header24 as u8,
+        (header24 >> 8) as u8,
+        (header24 >> 16) as u8,
+        header16 as u8,
+        (header16 >> 8) as u8,
+    ];
+    if let Some((a_dc, _, a_scale)) = &alpha {
+        hash.push((15.0 * a_dc).round() as u8 | ((15.0 * a_scale).round() as u8) << 4);
+    }
+    
+    // AC terms, two 4-bit values per byte
+    let mut acs = vec![&l_ac, &p_ac, &q_ac];
+    if let Some((_, a_ac, _)) = &alpha {
+        acs.push(a_ac);
+    }
+    let start = hash.len();
+    for (index, f) in acs.into_iter().flatten().enumerate() {
+        if start + index / 2 >= hash.len() {
+            hash.push(0);
+        }
+        hash[start + index / 2] |= ((15.0 * f).round() as u8) << ((index & 1) * 4);
+    }
+    hash
+}
+
+/// DCT of one channel: DC term, AC terms normalised to 0-1, and their scale
+fn encode_channel(channel: &[f64], w: usize, h: usize, nx: usize, ny: usize) -> (f64, Vec<f64>, f64) {
+    let (mut dc, mut ac, mut scale) = (0.0f64, Vec::new(), 0.0f64);
+    for cy in 0..ny {
+        // Triangular selection: fewer horizontal terms in higher rows
+        let mut cx = 0;
+        while cx * ny < nx * (ny - cy) {
+            let fx: Vec<f64> = (0..w).map(|x| (PI / w as f64 * cx as f64 * (x as f64 + 0.5)).cos()).collect();
+            let mut f = 0.0f64;
+            for y in 0..h {
+                let fy = (PI / h as f64 * cy as f64 * (y as f64 + 0.5)).cos();
+                for x in 0..w {
+                    f += channel[x + y * w] * fx[x] * fy;
+                }
+            }
+            f /= (w * h) as f64;
+            if cx > 0 || cy > 0 {
+                ac.push(f);
+                scale = scale.max(f.abs());
+            } else {
+                dc = f;
+            }
+            cx += 1;
+        }
+    }
+    if scale > 0.0 {
+        for f in &mut ac {
+            *f = 0.5 + 0.5 / scale * *f;
+        }
+    }
+    (dc, ac, scale)
+}
+
+/// Downscale so the long edge is at most `size`
+fn shrink(image: &DynamicImage, size: u32) -> DynamicImage {
+    if image.width().max(image.height()) > size {
+        image.resize(size, size, FilterType::Triangle)
+    } else {
+        image.clone()
+    }
+}
+
+fn encode83(value: usize, length: u32, out: &mut String) {
+    for i in 1..=length {
+        let digit = (value / 83usize.pow(length - i)) % 83;
+        out.push(BASE83[digit] as char);
+    }
+}
+
+fn srgb_to_linear(v: u8) -> f64 {
+    let v = v as f64 / 255.0;
+    if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(v: f64) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let v = if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+    (v * 255.0 + 0.5) as u8
+}
+
+/// Standard padded base64
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+    
+    #[test]
+    fn test_blurhash_layout() {
+        let flat = DynamicImage::ImageRgb8(RgbImage::from_pixel(32, 24, Rgb([255, 0, 0])));
+        // Size flag 3 + 2 * 9 = 21, then max AC, 4-digit DC and 11 2-digit AC terms
+        let hash = blurhash(&flat, 4, 3);
+        assert_eq!(hash.len(), 1 + 1 + 4 + 2 * 11);
+        assert!(hash.starts_with('L'), "{}", hash);
+        assert_eq!(&hash[2..6], "TI:j");  // 0xff0000 in base83
+        
+        let gradient = DynamicImage::ImageRgb8(RgbImage::from_fn(64, 48, |x, _| Rgb([(x * 4) as u8, 80, 200])));
+        assert_ne!(blurhash(&gradient, 4, 3)[1..2], hash[1..2]);  // stronger AC terms
+        let portrait = placeholders(&DynamicImage::ImageRgb8(RgbImage::from_pixel(20, 40, Rgb([9, 9, 9]))));
+        assert!(portrait.blurhash.starts_with('T'));  // 3x4: 2 + 3 * 9 = 29
+    }
+    
+    #[test]
+    fn test_matches_reference_encoders() {
+        // Expected strings from the reference JavaScript encoders
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(60, 40, |x, y| Rgb([(x * 4) as u8, (y * 6) as u8, ((x + y) * 2) as u8])));
+        assert_eq!(blurhash(&image, 4, 3), "LrG94$2R$7Sdl[a$jtf7gKfjfQfj");
+        assert_eq!(base64(&thumbhash(&image)), "mwgOFZhwd3eBiHh3iHiIh3hwCPd3");
+    }
+    
+    #[test]
+    fn test_thumbhash_header_and_alpha() {
+        let landscape = DynamicImage::ImageRgb8(RgbImage::from_fn(100, 50, |x, y| Rgb([(x * 2) as u8, (y * 4) as u8, 128])));
+        let hash = thumbhash(&landscape);
+        assert_eq!(hash[2] >> 7, 0);  // no alpha
+        assert_eq!(hash[4] >> 7, 1);  // landscape
+        assert_eq!(hash[3] & 7, 4);  // 7 * 50 / 100 luminance rows
+        
+        let transparent = DynamicImage::ImageRgba8(RgbaImage::from_fn(40, 40, |x, _| Rgba([200, 50, 50, if x < 20 { 0 } else { 255 }])));
+        let hash = thumbhash(&transparent);
+        assert_eq!(hash[2] >> 7, 1);  // alpha flag
+        
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+    }
+}
@@ -0,0 +1,317 @@
+//! No-reference image quality scoring for culling
+//!
+//! Scores a still image (RAW files via their embedded preview) without a
+//! reference: sharpness as variance of Laplacian, globally and per grid cell
+//! (focus map), exposure clipping, a noise estimate (Immerkær), RMS contrast,
+//! colourfulness (Hasler–Süsstrunk) and a rough aesthetic score combining
+//! them with a rule-of-thirds check on where the sharpest region sits.
+//!
+//! All measurements run on a copy scaled to a fixed long edge so scores are
+//! comparable between a 1.6MP embedded preview and a 45MP JPEG.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use soma_media::{QualityAnalyzer, QualityConfig};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let report = QualityAnalyzer::new(QualityConfig::default()).analyze_path("IMG_0412.CR3")?;
+//! if report.likely_reject {
+//!     println!("Reject: {:?}", report.reject_reasons);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::ffmpeg::FfmpegError;
+use crate::image::load_any_image;
+use crate::inspect::laplacian_variance;
+use crate::raw::PreviewOptions;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Thresholds and analysis settings for [`QualityAnalyzer`]
+#[derive(Debug, Clone)]
+pub struct QualityConfig {
+    /// Long edge the image is scaled to before scoring
+    pub analysis_size: u32,
+    /// Focus map is `grid` x `grid` cells
+    pub grid: u32,
+    /// Luma at or below which a pixel counts as crushed shadow
+    pub shadow_clip_level: u8,
+    /// Channel value at or above which a pixel counts as blown highlight
+    pub highlight_clip_level: u8,
+    /// Peak focus (sharpest cell) below which the image is flagged blurry
+    pub blur_threshold: f64,
+    /// Percentage of blown pixels above which the image is flagged overexposed
+    pub max_highlight_clip: f64,
+    /// Percentage of crushed pixels above which the image is flagged underexposed
+    pub max_shadow_clip: f64,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            analysis_size: 1024,
+            grid: 4,
+            shadow_clip_level: 2,
+            highlight_clip_level: 253,
+            blur_threshold: 100.0,  // Same VoL cut-off as video.inspect
+            max_highlight_clip: 5.0,
+            max_shadow_clip: 25.0,
+        }
+    }
+}
+
+/// Result of [`QualityAnalyzer::analyze_image`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityReport {
+    /// Analysed size (after scaling)
+    pub width: u32,
+    pub height: u32,
+    /// Variance of Laplacian over the whole frame
+    pub sharpness: f64,
+    /// Variance of Laplacian per grid cell, row-major (`grid` rows)
+    pub focus_map: Vec<Vec<f64>>,
+    /// Sharpest cell; shallow depth-of-field shots are judged by their subject
+    pub peak_focus: f64,
+    /// Percentage of pixels with crushed shadows
+    pub shadows_clipped: f64,
+    /// Percentage of pixels with at least one blown channel
+    pub highlights_clipped: f64,
+    /// Mean luma (0.0-1.0)
+    pub brightness: f64,
+    /// RMS contrast: standard deviation of luma (0.0-0.5)
+    pub contrast: f64,
+    /// Estimated noise standard deviation in 8-bit levels
+    pub noise: f64,
+    /// Hasler–Süsstrunk colourfulness (0 = gray, ~100+ = vivid)
+    pub colorfulness: f64,
+    /// Heuristic 0.0-1.0 score from the classical features above
+    pub aesthetic: f64,
+    pub likely_reject: bool,
+    pub reject_reasons: Vec<String>,
+}
+
+/// No-reference quality scorer
+pub struct QualityAnalyzer {
+    config: QualityConfig,
+}
+
+impl QualityAnalyzer {
+    pub fn new(config: QualityConfig) -> Self {
+        Self { config }
+    }
+    
+    /// Score an image file (RAW files via their embedded preview)
+    pub fn analyze_path(&self, path: impl AsRef<Path>) -> Result<QualityReport, FfmpegError> {
+        let preview = PreviewOptions {
+            max_dimension: Some(self.config.analysis_size),
+            ..PreviewOptions::default()
+        };
+        let image = load_any_image(path.as_ref(), &preview)?;
+        Ok(self.analyze_image(&image))
+    }
+    
+    /// Score a decoded image
+    pub fn analyze_image(&self, image: &DynamicImage) -> QualityReport {
+        let size = self.config.analysis_size;
+        let image = if image.width().max(image.height()) > size {
+            image.resize(size, size, FilterType::Triangle)
+        } else {
+            image.clone()
+        };
+        
+        let rgb = image.to_rgb8();
+        let gray = image.to_luma8();
+        let (width, height) = (gray.width() as usize, gray.height() as usize);
+        let pixel_count = (width * height).max(1) as f64;
+        
+        let sharpness = laplacian_variance(gray.as_raw(), width, height);
+        let focus_map = self.focus_map(gray.as_raw(), width, height);
+        let peak_focus = focus_map.iter().flatten().cloned().fold(0.0, f64::max);
+        
+        // Exposure and contrast from luma
+        let luma: Vec<f64> = gray.as_raw().iter().map(|&p| p as f64 / 255.0).collect();
+        let brightness = luma.iter().sum::<f64>() / pixel_count;
+        let contrast = (luma.iter().map(|l| (l - brightness).powi(2)).sum::<f64>() / pixel_count).sqrt();
+        
+        let shadows = gray.as_raw().iter().filter(|&&p| p <= self.config.shadow_clip_level).count();
+        let highlights = rgb.pixels()
+            .filter(|p| p.0.iter().any(|&c| c >= self.config.highlight_clip_level))
+            .count();
+        let shadows_clipped = 100.0 * shadows as f64 / pixel_count;
+        let highlights_clipped = 100.0 * highlights as f64 / pixel_count;
+        
+        let noise = estimate_noise(gray.as_raw(), width, height);
+        let colorfulness = colorfulness(rgb.as_raw());
+        
+        let mut report = QualityReport {
+            width: width as u32,
+            height: height as u32,
+            sharpness,
+            focus_map,
+            peak_focus,
+            shadows_clipped,
+            highlights_clipped,
+            brightness,
+            contrast,
+            noise,
+            colorfulness,
+            aesthetic: 0.0,
+            likely_reject: false,
+            reject_reasons: Vec::new(),
+        };
+        report.aesthetic = self.aesthetic_score(&report);
+        
+        if peak_focus < self.config.blur_threshold {
+            report.reject_reasons.push("blurry".to_string());
+        }
+        if highlights_clipped > self.config.max_highlight_clip {
+            report.reject_reasons.push("overexposed".to_string());
+        }
+        if shadows_clipped > self.config.max_shadow_clip || brightness < 0.08 {
+            report.reject_reasons.push("underexposed".to_string());
+        }
+        report.likely_reject = !report.reject_reasons.is_empty();
+        report
+    }
+    
+    /// Variance of Laplacian per grid cell
+    fn focus_map(&self, gray: &[u8], width: usize, height: usize) -> Vec<Vec<f64>> {
+        let grid = self.config.grid.max(1) as usize;
+        let (cell_w, cell_h) = (width / grid, height / grid);
+        
+        (0..grid)
+            .map(|gy| {
+                (0..grid)
+                    .map(|gx| {
+                        let mut cell = Vec::with_capacity(cell_w * cell_h);
+                        for y in gy * cell_h..(gy + 1) * cell_h {
+                            cell.extend_from_slice(&gray[y * width + gx * cell_w..y * width + (gx + 1) * cell_w]);
+                        }
+                        laplacian_variance(&cell, cell_w, cell_h)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+    
+    /// Weighted blend of sharpness, exposure, contrast, colour and composition
+    fn aesthetic_score(&self, report: &QualityReport) -> f64 {
+        // Log scale: 100 (soft) → ~0.67, 1000+ (crisp) → 1.0
+        let sharp = ((1.0 + report.peak_focus).log10() / 3.0).min(1.0);
+        let exposure = (1.0
+            - (report.brightness - 0.5).abs()
+            - (report.highlights_clipped + report.shadows_clipped) / 50.0)
+            .clamp(0.0, 1.0);
+        let contrast = (report.contrast / 0.25).min(1.0);
+        let color = (report.colorfulness / 80.0).min(1.0);
+        let noise = (1.0 - report.noise / 20.0).clamp(0.0, 1.0);
+        let composition = thirds_score(&report.focus_map);
+        
+        0.35 * sharp + 0.2 * exposure + 0.15 * contrast + 0.1 * color + 0.05 * noise + 0.15 * composition
+    }
+}
+
+/// How close the focus-weighted centroid sits to a rule-of-thirds point (1.0 = on it)
+fn thirds_score(focus_map: &[Vec<f64>]) -> f64 {
+    let rows = focus_map.len();
+    let cols = focus_map.first().map_or(0, |r| r.len());
+    let total: f64 = focus_map.iter().flatten().sum();
+    if rows == 0 || cols == 0 || total <= 0.0 {
+        return 0.5;
+    }
+    
+    let (mut cx, mut cy) = (0.0, 0.0);
+    for (y, row) in focus_map.iter().enumerate() {
+        for (x, &value) in row.iter().enumerate() {
+            cx += (x as f64 + 0.5) / cols as f64 * value;
+            cy += (y as f64 + 0.5) / rows as f64 * value;
+        }
+    }
+    let (cx, cy) = (cx / total, cy / total);
+    
+    let nearest = [(1.0 / 3.0, 1.0 / 3.0), (2.0 / 3.0, 1.0 / 3.0), (1.0 / 3.0, 2.0 / 3.0), (2.0 / 3.0, 2.0 / 3.0)]
+        .iter()
+        .map(|(tx, ty): &(f64, f64)| ((cx - tx).powi(2) + (cy - ty).powi(2)).sqrt())
+        .fold(f64::MAX, f64::min);
+    
+    // Centre is ~0.24 from every thirds point; treat that as neutral
+    (1.0 - nearest / 0.47).clamp(0.0, 1.0)
+}
+
+/// Immerkær fast noise variance estimation, as a standard deviation
+fn estimate_noise(gray: &[u8], width: usize, height: usize) -> f64 {
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+    
+    let mut sum = 0.0f64;
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let p = |dx: usize, dy: usize| gray[(y + dy - 1) * width + x + dx - 1] as f64;
+            // Difference of two Laplacians cancels structure, leaving noise
+            let response = p(0, 0) - 2.0 * p(1, 0) + p(2, 0)
+                - 2.0 * p(0, 1) + 4.0 * p(1, 1) - 2.0 * p(2, 1)
+                + p(0, 2) - 2.0 * p(1, 2) + p(2, 2);
+            sum += response.abs();
+        }
+    }
+    
+    sum * (std::f64::consts::PI / 2.0).sqrt() / (6.0 * (width - 2) as f64 * (height - 2) as f64)
+}
+
+/// Hasler–Süsstrunk colourfulness metric
+pub(crate) fn colorfulness(rgb: &[u8]) -> f64 {
+    let n = (rgb.len() / 3).max(1) as f64;
+    let (mut rg_sum, mut yb_sum, mut rg_sq, mut yb_sq) = (0.0, 0.0, 0.0, 0.0);
+    for pixel in rgb.chunks_exact(3) {
+        let (r, g, b) = (pixel[0] as f64, pixel[1] as f64, pixel[2] as f64);
+        let rg = r - g;
+        let yb = 0.5 * (r + g) - b;
+        rg_sum += rg;
+        yb_sum += yb;
+        rg_sq += rg * rg;
+        yb_sq += yb * yb;
+    }
+    
+    let (rg_mean, yb_mean) = (rg_sum / n, yb_sum / n);
+    let rg_std = (rg_sq / n - rg_mean * rg_mean).max(0.0).sqrt();
+    let yb_std = (yb_sq / n - yb_mean * yb_mean).max(0.0).sqrt();
+    
+    (rg_std * rg_std + yb_std * yb_std).sqrt() + 0.3 * (rg_mean * rg_mean + yb_mean * yb_mean).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    
+    fn texture() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(256, 192, |x, y| {
+            let v = if (x / 4 + y / 4) % 2 == 0 { 40 } else { 210 };
+            Rgb([v, (v as u32 * 3 / 4) as u8, 255 - v])
+        }))
+    }
+    
+    #[test]
+    fn test_sharp_vs_blurred_and_clipping() {
+        let analyzer = QualityAnalyzer::new(QualityConfig::default());
+        
+        let sharp = analyzer.analyze_image(&texture());
+        let blurred = analyzer.analyze_image(&texture().blur(6.0));
+        assert!(sharp.peak_focus > 10.0 * blurred.peak_focus);
+        assert_eq!(sharp.focus_map.len(), 4);
+        assert!(!sharp.reject_reasons.contains(&"blurry".to_string()));
+        assert!(blurred.reject_reasons.contains(&"blurry".to_string()));
+        
+        let white = analyzer.analyze_image(&DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 64, Rgb([255, 255, 255]))));
+        assert_eq!(white.highlights_clipped, 100.0);
+        assert!(white.likely_reject);
+        assert!(white.reject_reasons.contains(&"overexposed".to_string()));
+        assert!(white.noise < 1e-9 && white.contrast < 1e-9);
+    }
+}
@@ -42,6 +42,8 @@
 
 use crate::error::Result;
 use crate::metadata::RawMetadata;
+use crate::geometry::{CropRect, ResizeMode};
+use crate::smartcrop::{self, FocusRegion};
 use std::path::Path;
 use rsraw::RawImage;
 use rsraw_sys as sys;
@@ -89,7 +91,7 @@ pub struct RawOptions {
     /// Output bit depth (8 or 16)
     pub bit_depth: u8,
     
-    /// Output color space
+    /// Output color space (LibRaw `output_color`; outputs embed the matching ICC profile)
     pub color_space: ColorSpace,
     
     /// Brightness adjustment (0.25 to 8.0, default 1.0)
@@ -113,7 +115,8 @@ pub struct RawOptions {
     /// Set to None to use brightness instead
     pub exposure_compensation: Option<f32>,
     
-    /// Gamma curve (power, slope) - typical: (2.222, 4.5) for sRGB
+    /// Gamma curve (power, slope) - typical: (2.222, 4.5) for BT.709
+    /// None uses the curve of `color_space` (see `RawOptions::transfer_curve`)
     pub gamma: Option<(f32, f32)>,
     
     /// Highlight recovery mode
@@ -190,7 +193,7 @@ impl Default for RawOptions {
             auto_exposure: true,  // Enable by default for optimal results
             use_camera_exposure_compensation: true,  // Apply in-camera EV compensation
             exposure_compensation: None,  // No manual exposure adjustment
-            gamma: None, // Use libraw's default
+            gamma: None, // Curve of the output colour space
             highlight_mode: 0,
             chromatic_aberration: None,
             noise_threshold: 0.0,
@@ -249,7 +252,9 @@ impl RawOptions {
 
     /// Maximum quality preset - for final processing and archival
     /// Use for: Client deliverables, prints, archival, reprocessing
-    /// Preserves all sensor data without adjustments but applies noise reduction
+    /// Preserves all sensor data without adjustments but applies noise reduction.
+    /// Renders into ProPhoto RGB (gamma 1.8, tagged) rather than sRGB so 16-bit
+    /// archival output keeps the camera gamut
     pub fn maximum() -> Self {
         Self {
             white_balance: WhiteBalance::None, // Preserve sensor data
@@ -260,7 +265,7 @@ impl RawOptions {
             auto_exposure: false,  // Preserve original exposure for archival
             use_camera_exposure_compensation: true,  // Apply in-camera EV
             exposure_compensation: None,  // No manual adjustment for archival
-            gamma: None, // Linear for post-processing
+            gamma: None, // ProPhoto's 1.8 curve, matching the embedded profile
             highlight_mode: 3, // Rebuild (best highlight recovery)
             chromatic_aberration: Some((1.0, 1.0)),
             noise_threshold: 100.0, // Light noise reduction
@@ -314,6 +319,20 @@ impl RawOptions {
             bad_pixels_path: None,
         }
     }
+    
+    /// Gamma curve (power, slope) LibRaw renders with
+    ///
+    /// An explicit `gamma` wins; otherwise the curve of `color_space`, so the
+    /// pixels match the ICC profile embedded on output.
+    pub fn transfer_curve(&self) -> (f32, f32) {
+        self.gamma.unwrap_or(match self.color_space {
+            ColorSpace::SRGB => (2.4, 12.92),
+            ColorSpace::AdobeRGB => (563.0 / 256.0, 0.0),
+            ColorSpace::ProPhotoRGB => (1.8, 0.0),
+            // LibRaw's default (BT.709)
+            ColorSpace::Raw | ColorSpace::WideGamutRGB | ColorSpace::XYZ => (1.0 / 0.45, 4.5),
+        })
+    }
 }
 
 impl RawProcessor {
@@ -433,10 +452,42 @@ impl RawProcessor {
     }
     
     /// Process RAW data from memory and return (RGB data, width, height)
+    ///
+    /// Always 8 bits per sample; use [`Self::process_raw_from_memory_16`]
+    /// for `bit_depth: 16`.
     pub fn process_raw_from_memory(
         &self,
         file_data: &[u8],
         options: &RawOptions,
+    ) -> Result<(Vec<u8>, u32, u32)> {
+        self.render_ppm(file_data, options, 8)
+    }
+    
+    /// Process RAW data from memory into 16-bit RGB samples (width, height)
+    ///
+    /// Keeps LibRaw's full output range (0-65535) for archival TIFF/PNG-16.
+    pub fn process_raw_from_memory_16(
+        &self,
+        file_data: &[u8],
+        options: &RawOptions,
+    ) -> Result<(Vec<u16>, u32, u32)> {
+        let (data, width, height) = self.render_ppm(file_data, options, 16)?;
+        
+        // 16-bit PPM samples are big-endian
+        let samples = data
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        Ok((samples, width, height))
+    }
+    
+    /// Demosaic with `options` and return the interleaved RGB samples of
+    /// LibRaw's PPM output at `output_bps` (8 or 16) bits
+    fn render_ppm(
+        &self,
+        file_data: &[u8],
+        options: &RawOptions,
+        output_bps: u8,
     ) -> Result<(Vec<u8>, u32, u32)> {
         // Open RAW file from buffer
         let mut raw = RawImage::open(file_data)
@@ -522,13 +573,16 @@ impl RawProcessor {
             
             // Brightness (post-processing)
             params.no_auto_bright = if options.auto_brightness { 0 } else { 1 };
+            
+            // Output colour space (matching ICC profile is embedded on output)
+            params.output_color = options.color_space as i32;
+            params.output_bps = output_bps as i32;
             params.bright = options.brightness;
             
-            // Gamma curve
-            if let Some((power, slope)) = options.gamma {
-                params.gamm[0] = 1.0 / power as f64;
-                params.gamm[1] = slope as f64;
-            }
+            // Gamma curve (the embedded profile is built from the same curve)
+            let (power, slope) = options.transfer_curve();
+            params.gamm[0] = 1.0 / power as f64;
+            params.gamm[1] = slope as f64;
             
             // Highlight recovery
             params.highlight = options.highlight_mode as i32;
@@ -595,13 +649,15 @@ impl RawProcessor {
             }
         }
         
-        // Write to temp PPM file (dcraw_make_mem_image has wrong format)
-        let temp_ppm = std::env::temp_dir().join(format!("libraw_mem_{}.ppm", std::process::id()));
+        // Write to temp PPM file (dcraw_make_mem_image has wrong format); the
+        // name is unique per call since batches render on several threads
+        let temp_ppm = crate::ffmpeg::temp_path("libraw", "ppm");
         let c_path = std::ffi::CString::new(temp_ppm.to_str().unwrap()).unwrap();
         
         unsafe {
             let write_ret = sys::libraw_dcraw_ppm_tiff_writer(raw_ptr, c_path.as_ptr());
             if write_ret != 0 {
+                let _ = std::fs::remove_file(&temp_ppm);
                 return Err(crate::error::MediaError::ProcessingError(
                     format!("libraw_dcraw_ppm_tiff_writer failed with code {}", write_ret)
                 ));
@@ -616,11 +672,12 @@ impl RawProcessor {
         let _ = std::fs::remove_file(&temp_ppm);
         
         // Parse PPM header to get actual dimensions and find data start
-        // Format: P6\nWIDTH HEIGHT\n255\n
+        // Format: P6\nWIDTH HEIGHT\n255\n (65535 for 16-bit)
         let header_str = String::from_utf8_lossy(&ppm_data[..100]);
         let lines: Vec<&str> = header_str.lines().collect();
+        let max_value = if output_bps == 16 { "65535" } else { "255" };
         
-        if lines.len() < 3 || lines[0] != "P6" {
+        if lines.len() < 3 || lines[0] != "P6" || lines[2] != max_value {
             return Err(crate::error::MediaError::ProcessingError(
                 "Invalid PPM format".to_string()
             ));
@@ -735,13 +792,15 @@ impl RawProcessor {
             
             // Brightness (post-processing)
             params.no_auto_bright = if options.auto_brightness { 0 } else { 1 };
+            
+            // Output colour space (matching ICC profile is embedded on output)
+            params.output_color = options.color_space as i32;
             params.bright = options.brightness;
             
-            // Gamma curve
-            if let Some((power, slope)) = options.gamma {
-                params.gamm[0] = 1.0 / power as f64;
-                params.gamm[1] = slope as f64;
-            }
+            // Gamma curve (the embedded profile is built from the same curve)
+            let (power, slope) = options.transfer_curve();
+            params.gamm[0] = 1.0 / power as f64;
+            params.gamm[1] = slope as f64;
             
             // Highlight recovery
             params.highlight = options.highlight_mode as i32;
@@ -971,7 +1030,7 @@ impl RawProcessor {
 /// Options for RAW preview extraction
 #[derive(Debug, Clone)]
 pub struct PreviewOptions {
-    /// WebP quality (1-100, default: 92)
+    /// WebP/JPEG XL quality (1-100, default: 92)
     pub quality: u8,
     
     /// Maximum dimension in pixels (default: 2048)
@@ -979,6 +1038,11 @@ pub struct PreviewOptions {
     
     /// Force RAW processing instead of using embedded preview (default: false)
     pub force_raw_processing: bool,
+    
+    /// How the preview is fitted to `max_dimension` (default: Fit). `Fill`,
+    /// `Smart` and `Stretch` produce a square; `Smart` keeps the AF point and
+    /// XMP face regions in frame (see [`crate::focus_hints`])
+    pub resize: ResizeMode,
 }
 
 impl Default for PreviewOptions {
@@ -987,10 +1051,20 @@ impl Default for PreviewOptions {
             quality: 92,  // Sweet spot for WebP quality/size
             max_dimension: Some(2048),
             force_raw_processing: false,  // Prefer embedded previews
+            resize: ResizeMode::Fit,
         }
     }
 }
 
+/// How RAW inputs are decoded by the sequence, variant and tile pipelines
+#[derive(Debug, Clone)]
+pub enum RawDecode {
+    /// Embedded JPEG preview, falling back to a half-size decode (fast)
+    Preview,
+    /// Full LibRaw processing with the given options
+    Full(RawOptions),
+}
+
 
 impl RawProcessor {
     /// Extract preview from RAW file and convert to WebP
@@ -1006,20 +1080,112 @@ impl RawProcessor {
         path: &Path,
         options: &PreviewOptions,
     ) -> Result<Vec<u8>> {
-        let img = if options.force_raw_processing {
+        let img = self.preview_image(path, options)?;
+        
+        // Convert to WebP at specified quality
+        self.image_to_webp(&img, options.quality)
+    }
+    
+    /// [`Self::extract_preview_webp`] for RAW data already in memory
+    pub fn extract_preview_webp_from_memory(
+        &self,
+        file_data: &[u8],
+        options: &PreviewOptions,
+    ) -> Result<Vec<u8>> {
+        let img = self.preview_image_from_memory(file_data, options)?;
+        self.image_to_webp(&img, options.quality)
+    }
+    
+    /// Extract preview from RAW file and encode it as JPEG XL
+    ///
+    /// Same preview selection as [`Self::extract_preview_webp`]; `quality`
+    /// follows `cjxl -q` (smaller archival derivatives than JPEG).
+    pub fn extract_preview_jxl(
+        &self,
+        path: &Path,
+        options: &PreviewOptions,
+    ) -> Result<Vec<u8>> {
+        let img = self.preview_image(path, options)?;
+        Ok(crate::jxl::encode_jxl(&img, options.quality)?)
+    }
+    
+    /// Decode a preview image (embedded JPEG or half-size RAW), resized per `options`
+    ///
+    /// HEIC/HEIF/AVIF files are decoded (and oriented) via libheif instead.
+    pub fn preview_image(&self, path: &Path, options: &PreviewOptions) -> Result<DynamicImage> {
+        self.preview_image_with_crop(path, options).map(|(img, _)| img)
+    }
+    
+    /// Decode a preview image, also returning the window kept by `Fill`/`Smart`
+    /// resizing (in preview pixels before scaling)
+    pub fn preview_image_with_crop(&self, path: &Path, options: &PreviewOptions) -> Result<(DynamicImage, Option<CropRect>)> {
+        let img = if crate::heif::is_heif(path) {
+            crate::heif::decode_heif(path, true)?.image
+        } else {
+            let file_data = std::fs::read(path)
+                .map_err(crate::error::MediaError::Io)?;
+            self.decode_preview(&file_data, options)?
+        };
+        self.fit_preview(img, options, || smartcrop::focus_hints(path))
+    }
+    
+    /// Decode a preview image from RAW data in memory, resized per `options`
+    ///
+    /// `Smart` resizing works from the saliency map alone (AF point and face
+    /// hints are read from a file by ExifTool).
+    pub fn preview_image_from_memory(&self, file_data: &[u8], options: &PreviewOptions) -> Result<DynamicImage> {
+        let img = self.decode_preview(file_data, options)?;
+        self.fit_preview(img, options, Vec::new).map(|(img, _)| img)
+    }
+    
+    /// Embedded JPEG (unless `force_raw_processing`), else a half-size RAW render
+    fn decode_preview(&self, file_data: &[u8], options: &PreviewOptions) -> Result<DynamicImage> {
+        if options.force_raw_processing {
             // Always process RAW (highest quality)
-            self.generate_preview_from_raw(path)?
+            self.generate_preview_from_memory(file_data)
         } else {
             // Try embedded first (fast, good for ML)
-            self.extract_embedded_preview(path)
-                .or_else(|_| self.generate_preview_from_raw(path))?
+            self.embedded_preview_from_memory(file_data)
+                .or_else(|_| self.generate_preview_from_memory(file_data))
+        }
+    }
+    
+    /// Fit a decoded preview to `options`; `focus` supplies the `Smart` hints
+    fn fit_preview(
+        &self,
+        img: DynamicImage,
+        options: &PreviewOptions,
+        focus: impl FnOnce() -> Vec<FocusRegion>,
+    ) -> Result<(DynamicImage, Option<CropRect>)> {
+        let short_edge = img.width().min(img.height());
+        let side = options.max_dimension.map_or(short_edge, |max| max.min(short_edge)).max(1);
+        let crop = match options.resize {
+            ResizeMode::Fit => return Ok((self.maybe_resize(img, options.max_dimension)?, None)),
+            ResizeMode::Stretch => None,
+            ResizeMode::Fill => Some(smartcrop::cover_rect(img.width(), img.height(), 1, 1)),
+            ResizeMode::Smart => Some(smartcrop::smart_crop_rect(&img, 1, 1, &focus())),
         };
         
-        // Resize if needed
-        let img = self.maybe_resize(img, options.max_dimension)?;
+        let window = match crop {
+            Some(rect) => img.crop_imm(rect.x, rect.y, rect.width, rect.height),
+            None => img,
+        };
+        Ok((window.resize_exact(side, side, image::imageops::FilterType::Lanczos3), crop))
+    }
+    
+    /// Fully process a RAW file with `options` into an 8-bit RGB image
+    pub fn decode_image(&self, path: &Path, options: &RawOptions) -> Result<DynamicImage> {
+        let file_data = std::fs::read(path)
+            .map_err(crate::error::MediaError::Io)?;
         
-        // Convert to WebP at specified quality
-        self.image_to_webp(&img, options.quality)
+        let (rgb, width, height) = self.process_raw_from_memory(&file_data, options)?;
+        
+        Ok(DynamicImage::ImageRgb8(
+            image::RgbImage::from_raw(width, height, rgb)
+                .ok_or_else(|| crate::error::MediaError::ProcessingError(
+                    "Failed to create RGB image from RAW data".into()
+                ))?
+        ))
     }
     
     /// Extract embedded JPEG preview from RAW file
@@ -1029,8 +1195,12 @@ impl RawProcessor {
     fn extract_embedded_preview(&self, path: &Path) -> Result<DynamicImage> {
         let file_data = std::fs::read(path)
             .map_err(crate::error::MediaError::Io)?;
-            
-        let raw = RawImage::open(&file_data)
+        self.embedded_preview_from_memory(&file_data)
+    }
+    
+    /// Decode the embedded JPEG preview of RAW data in memory, EXIF-oriented
+    pub fn embedded_preview_from_memory(&self, file_data: &[u8]) -> Result<DynamicImage> {
+        let raw = RawImage::open(file_data)
             .map_err(|e| crate::error::MediaError::ProcessingError(
                 format!("Failed to open RAW file: {:?}", e)
             ))?;
@@ -1066,7 +1236,7 @@ impl RawProcessor {
                 ))?;
                 
                 // Apply EXIF orientation correction
-                return self.apply_exif_orientation(img, path);
+                return self.apply_exif_orientation(img, file_data);
             }
         }
         
@@ -1082,11 +1252,8 @@ impl RawProcessor {
     /// 3 = Rotate 180
     /// 6 = Rotate 90 CW
     /// 8 = Rotate 270 CW (90 CCW)
-    fn apply_exif_orientation(&self, img: DynamicImage, path: &Path) -> Result<DynamicImage> {
-        let file_data = std::fs::read(path)
-            .map_err(crate::error::MediaError::Io)?;
-            
-        let raw = RawImage::open(&file_data)
+    fn apply_exif_orientation(&self, img: DynamicImage, file_data: &[u8]) -> Result<DynamicImage> {
+        let raw = RawImage::open(file_data)
             .map_err(|e| crate::error::MediaError::ProcessingError(
                 format!("Failed to open RAW file: {:?}", e)
             ))?;
@@ -1118,11 +1285,15 @@ impl RawProcessor {
     
     /// Generate preview by processing RAW (half-size for speed)
     fn generate_preview_from_raw(&self, path: &Path) -> Result<DynamicImage> {
-        let opts = RawOptions::fast_preview();  // Uses half_size = true
         let file_data = std::fs::read(path)
             .map_err(crate::error::MediaError::Io)?;
-            
-        let (rgb, width, height) = self.process_raw_from_memory(&file_data, &opts)?;
+        self.generate_preview_from_memory(&file_data)
+    }
+    
+    /// Half-size RAW render of RAW data in memory
+    fn generate_preview_from_memory(&self, file_data: &[u8]) -> Result<DynamicImage> {
+        let opts = RawOptions::fast_preview();  // Uses half_size = true
+        let (rgb, width, height) = self.process_raw_from_memory(file_data, &opts)?;
         
         let img = DynamicImage::ImageRgb8(
             image::RgbImage::from_raw(width, height, rgb)
@@ -1148,7 +1319,7 @@ impl RawProcessor {
     }
     
     /// Convert DynamicImage to WebP format
-    fn image_to_webp(&self, img: &DynamicImage, quality: u8) -> Result<Vec<u8>> {
+    pub(crate) fn image_to_webp(&self, img: &DynamicImage, quality: u8) -> Result<Vec<u8>> {
         use webp::Encoder;
         
         let (width, height) = img.dimensions();
@@ -1171,6 +1342,13 @@ impl RawProcessor {
             .map_err(|e| crate::error::MediaError::ProcessingError(e.to_string()))
     }
     
+    /// [`Self::extract_metadata`] for a file already in memory
+    ///
+    /// See [`crate::metadata::extract_metadata_from_memory`].
+    pub fn extract_metadata_from_memory(&self, file_data: &[u8]) -> Result<RawMetadata> {
+        crate::metadata::extract_metadata_from_memory(file_data)
+    }
+    
     /// Extract preview with GPU acceleration (via soma_compute UMA)
     /// 
     /// Automatically uses best available backend:
@@ -1486,3 +1664,91 @@ impl RawProcessor {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Minimal little-endian linear (demosaiced) DNG filled with one colour
+    fn linear_dng(width: u32, height: u32, rgb: [u16; 3]) -> Vec<u8> {
+        let pixels: Vec<u8> = (0..width * height)
+            .flat_map(|_| rgb)
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let short = |v: u16| v.to_le_bytes().to_vec();
+        let long = |v: u32| v.to_le_bytes().to_vec();
+        
+        // (tag, type, count, value); pixels follow the IFD directly
+        let entries: Vec<(u16, u16, u32, Vec<u8>)> = vec![
+            (256, 4, 1, long(width)),
+            (257, 4, 1, long(height)),
+            (258, 3, 3, [16u16; 3].iter().flat_map(|v| v.to_le_bytes()).collect()),
+            (259, 3, 1, short(1)),
+            (262, 3, 1, short(34892)),  // LinearRaw
+            (271, 2, 5, b"Soma\0".to_vec()),
+            (272, 2, 5, b"Test\0".to_vec()),
+            (273, 4, 1, Vec::new()),  // StripOffsets, filled below
+            (277, 3, 1, short(3)),
+            (278, 4, 1, long(height)),
+            (279, 4, 1, long(pixels.len() as u32)),
+            (284, 3, 1, short(1)),
+            (50706, 1, 4, vec![1, 4, 0, 0]),  // DNGVersion
+            (50708, 2, 10, b"Soma Test\0".to_vec()),  // UniqueCameraModel
+        ];
+        let ifd_end = 8 + 2 + 12 * entries.len() + 4;
+        let mut extra_offset = ifd_end + pixels.len();
+        
+        let mut out = b"II*\0".to_vec();
+        out.extend(long(8));
+        out.extend(short(entries.len() as u16));
+        let mut extra = Vec::new();
+        for (tag, kind, count, value) in entries {
+            let value = if tag == 273 { long(ifd_end as u32) } else { value };
+            out.extend(short(tag));
+            out.extend(short(kind));
+            out.extend(long(count));
+            if value.len() <= 4 {
+                let mut value = value;
+                value.resize(4, 0);
+                out.extend(value);
+            } else {
+                out.extend(long(extra_offset as u32));
+                extra_offset += value.len();
+                extra.extend(value);
+            }
+        }
+        out.extend(long(0));
+        out.extend(pixels);
+        out.extend(extra);
+        out
+    }
+    
+    #[test]
+    fn test_concurrent_decodes_do_not_share_temp_files() {
+        let processor = RawProcessor::new().unwrap();
+        let options = RawOptions { half_size: false, ..RawOptions::default() };
+        let inputs = [linear_dng(64, 48, [60000, 2000, 2000]), linear_dng(32, 96, [2000, 2000, 60000])];
+        
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = inputs
+                .iter()
+                .map(|dng| scope.spawn(|| (0..8)
+                    .map(|_| processor.process_raw_from_memory(dng, &options).unwrap())
+                    .collect::<Vec<_>>()))
+                .collect();
+            let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+            
+            for (i, runs) in results.iter().enumerate() {
+                for (rgb, width, height) in runs {
+                    assert_eq!((*width, *height), if i == 0 { (64, 48) } else { (32, 96) });
+                    let centre = ((*height / 2 * *width + *width / 2) * 3) as usize;
+                    let pixel = &rgb[centre..centre + 3];
+                    if i == 0 {
+                        assert!(pixel[0] > pixel[2], "red input decoded as {:?}", pixel);
+                    } else {
+                        assert!(pixel[2] > pixel[0], "blue input decoded as {:?}", pixel);
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,725 @@
+//! Timelapse and slideshow assembly from image sequences
+//!
+//! Decodes an ordered list of images (including RAW files via LibRaw, either
+//! the embedded preview or a full decode), fits them to the output resolution
+//! in Rust and pipes raw RGB frames straight into an FFmpeg encoder, so no
+//! intermediate JPEGs are written.
+//!
+//! Two modes:
+//!
+//! - **Timelapse** (`hold: None`): every image becomes one frame at `fps`
+//! - **Slideshow** (`hold: Some(secs)`): every image is shown for `secs`,
+//!   optionally with crossfades and a Ken Burns pan/zoom
+//!
+//! CinemaDNG folders and DNG sequences can also be treated as clips through
+//! [`DngSequence`], which takes its timing from the DNG `FrameRate` tag.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use soma_media::{SequenceEncoder, SequenceConfig};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let images = soma_media::expand_glob("shoot/*.CR3")?;
+//! let report = SequenceEncoder::new(SequenceConfig::default())
+//!     .encode(&images, "timelapse.mp4")?;
+//! println!("{} frames, {:.1}s", report.frame_count, report.duration);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::ffmpeg::{FfmpegCommand, FfmpegError, FfmpegProcess};
+use crate::geometry::{active_area, fit_image, CropRect, ResizeMode};
+use crate::image::{ImageConfig, ImagePreprocessor};
+use crate::raw::{PreviewOptions, RawDecode, RawOptions, RawProcessor};
+use crate::watermark::{self, OverlayFile, Watermark};
+use crate::video::{FrameExtraction, TranscodeConfig, VideoCodec, VideoConfig, CROP_LIMIT, CROP_SAMPLE_POINTS};
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbImage};
+use std::path::{Path, PathBuf};
+
+/// Extra canvas around the frame that Ken Burns pans/zooms across
+const KEN_BURNS_ZOOM: f64 = 1.15;
+
+/// Frame rate assumed when a DNG sequence carries no `FrameRate` tag
+const DEFAULT_DNG_FPS: f64 = 24.0;
+
+/// Settings for [`SequenceEncoder`]
+#[derive(Debug, Clone)]
+pub struct SequenceConfig {
+    pub fps: f64,
+    pub width: u32,
+    pub height: u32,
+    pub resize: ResizeMode,
+    pub codec: VideoCodec,
+    /// Constant rate factor (lower is higher quality)
+    pub crf: u8,
+    /// Encoder preset, None uses the encoder default
+    pub preset: Option<String>,
+    pub raw_decode: RawDecode,
+    /// Seconds each image is shown; None renders one frame per image (timelapse)
+    pub hold: Option<f64>,
+    /// Crossfade between images in seconds (slideshow only)
+    pub crossfade: Option<f64>,
+    /// Slow pan/zoom across each image (slideshow only)
+    pub ken_burns: bool,
+    /// Logo or text burnt into every frame (FFmpeg `overlay`)
+    pub watermark: Option<Watermark>,
+}
+
+impl Default for SequenceConfig {
+    fn default() -> Self {
+        Self {
+            fps: 24.0,
+            width: 1920,  // 1080p
+            height: 1080,
+            resize: ResizeMode::Fill,  // Timelapses look wrong with bars
+            codec: VideoCodec::H264,
+            crf: 20,
+            preset: None,
+            raw_decode: RawDecode::Preview,  // Embedded previews are plenty for 1080p
+            hold: None,
+            crossfade: None,
+            ken_burns: false,
+            watermark: None,
+        }
+    }
+}
+
+/// Result of [`SequenceEncoder::encode`]
+#[derive(Debug, Clone)]
+pub struct SequenceReport {
+    pub output: PathBuf,
+    /// Images that made it into the video
+    pub image_count: usize,
+    pub frame_count: usize,
+    /// Duration in seconds
+    pub duration: f64,
+    /// Images that failed to decode, with the reason
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+/// Encodes image sequences to video
+pub struct SequenceEncoder {
+    config: SequenceConfig,
+}
+
+impl SequenceEncoder {
+    pub fn new(config: SequenceConfig) -> Self {
+        Self { config }
+    }
+    
+    /// Encode `images` (in order) to `output`
+    ///
+    /// Images are decoded in parallel batches; undecodable files are skipped
+    /// and reported rather than aborting the render.
+    pub fn encode<P: AsRef<Path> + Sync>(&self, images: &[P], output: impl AsRef<Path>) -> Result<SequenceReport, FfmpegError> {
+        self.encode_from(images, output, None)
+    }
+    
+    /// [`Self::encode`], reusing `first` as the already decoded `images[0]`
+    pub(crate) fn encode_from<P: AsRef<Path> + Sync>(
+        &self,
+        images: &[P],
+        output: impl AsRef<Path>,
+        first: Option<DynamicImage>,
+    ) -> Result<SequenceReport, FfmpegError> {
+        use rayon::prelude::*;
+        
+        if images.is_empty() {
+            return Err(FfmpegError::InvalidOutput("No input images".into()));
+        }
+        if self.config.fps <= 0.0 || self.config.width == 0 || self.config.height == 0 {
+            return Err(FfmpegError::InvalidOutput("fps, width and height must be positive".into()));
+        }
+        // yuv420p subsamples chroma 2x2
+        if self.config.width % 2 == 1 || self.config.height % 2 == 1 {
+            return Err(FfmpegError::InvalidOutput(format!(
+                "width and height must be even, got {}x{}", self.config.width, self.config.height
+            )));
+        }
+        
+        let raw = RawProcessor::new().ok();
+        let loader = ImagePreprocessor::new(ImageConfig::default());
+        let overlay = match &self.config.watermark {
+            Some(mark) => Some(mark.render()?.overlay_file(self.config.width, self.config.height)?),
+            None => None,
+        };
+        let mut encoder = self.spawn_encoder(output.as_ref(), overlay.as_ref())?;
+        let mut state = RenderState::default();
+        let mut skipped = Vec::new();
+        
+        let mut remaining = images;
+        if let Some(first) = first {
+            if let Err(e) = self.push_image(&mut state, self.fit_canvas(&first), &mut encoder) {
+                return Err(encoder.finish().err().unwrap_or(e));
+            }
+            remaining = &images[1..];
+        }
+        
+        // Decode a few images per worker at a time to bound memory
+        let batch = rayon::current_num_threads().max(1) * 2;
+        for chunk in remaining.chunks(batch) {
+            let decoded: Vec<Result<RgbImage, String>> = chunk
+                .par_iter()
+                .map(|path| self.load_canvas(path.as_ref(), raw.as_ref(), &loader))
+                .collect();
+            
+            for (path, canvas) in chunk.iter().zip(decoded) {
+                match canvas {
+                    Ok(canvas) => {
+                        if let Err(e) = self.push_image(&mut state, canvas, &mut encoder) {
+                            return Err(encoder.finish().err().unwrap_or(e));
+                        }
+                    }
+                    Err(reason) => {
+                        tracing::warn!("Skipping {}: {}", path.as_ref().display(), reason);
+                        skipped.push((path.as_ref().to_path_buf(), reason));
+                    }
+                }
+            }
+        }
+        
+        if let Some(last) = state.current.take() {
+            if let Err(e) = self.emit_image(&mut state, &last, None, &mut encoder) {
+                return Err(encoder.finish().err().unwrap_or(e));
+            }
+        }
+        
+        if state.image_count == 0 {
+            let _ = encoder.finish();
+            return Err(FfmpegError::InvalidOutput("No input image could be decoded".into()));
+        }
+        encoder.finish()?;
+        
+        Ok(SequenceReport {
+            output: output.as_ref().to_path_buf(),
+            image_count: state.image_count,
+            frame_count: state.frame_count,
+            duration: state.frame_count as f64 / self.config.fps,
+            skipped,
+        })
+    }
+    
+    /// Start an encoder reading raw RGB frames from stdin, compositing
+    /// `overlay` (a full-frame watermark) when given
+    fn spawn_encoder(&self, output: &Path, overlay: Option<&OverlayFile>) -> Result<FfmpegProcess, FfmpegError> {
+        let size = format!("{}x{}", self.config.width, self.config.height);
+        let fps = self.config.fps.to_string();
+        let crf = self.config.crf.to_string();
+        
+        let mut cmd = FfmpegCommand::new()
+            .args(&[
+                "-y", "-hide_banner", "-loglevel", "error",
+                "-f", "rawvideo", "-pix_fmt", "rgb24", "-s", &size, "-r", &fps,
+            ])
+            .input("-");
+        if let Some(overlay) = overlay {
+            cmd = cmd
+                .input(overlay.path())
+                .args(&["-filter_complex", &watermark::overlay_graph(&[], &[]), "-map", "[v]"]);
+        }
+        cmd = cmd.args(&[
+            "-c:v", self.config.codec.encoder(),
+            "-crf", &crf,
+            "-pix_fmt", "yuv420p",
+        ]);
+        
+        if self.config.codec == VideoCodec::Vp9 {
+            cmd = cmd.args(&["-b:v", "0"]);  // Constant quality mode
+        }
+        if let Some(preset) = &self.config.preset {
+            cmd = cmd.args(&["-preset", preset]);
+        }
+        if self.config.codec.extension() == "mp4" {
+            cmd = cmd.args(&["-movflags", "+faststart"]);
+        }
+        
+        cmd.output(output).spawn_with_stdin()
+    }
+    
+    /// Decode an image and fit it to the canvas (oversized when Ken Burns is on)
+    ///
+    /// Non-RAW inputs go through [`ImagePreprocessor::load_source`], so EXIF
+    /// orientation is applied and HEIC/AVIF/JPEG XL frames decode.
+    fn load_canvas(&self, path: &Path, raw: Option<&RawProcessor>, loader: &ImagePreprocessor) -> Result<RgbImage, String> {
+        let image = match raw {
+            Some(raw) if RawProcessor::is_raw_format(path) && !is_plain_image(path) => match &self.config.raw_decode {
+                RawDecode::Preview => raw.preview_image(path, &PreviewOptions {
+                    max_dimension: Some(self.config.width.max(self.config.height) * 2),
+                    ..PreviewOptions::default()
+                }),
+                RawDecode::Full(options) => raw.decode_image(path, options),
+            }
+            .map_err(|e| e.to_string())?,
+            _ => loader.load_source(path, &self.config.raw_decode).map_err(|e| e.to_string())?,
+        };
+        Ok(self.fit_canvas(&image))
+    }
+    
+    /// Fit a decoded image to the canvas
+    fn fit_canvas(&self, image: &DynamicImage) -> RgbImage {
+        let (width, height) = self.canvas_size();
+        fit_image(image, width, height, self.config.resize)
+    }
+    
+    fn canvas_size(&self) -> (u32, u32) {
+        if self.ken_burns_enabled() {
+            (
+                (self.config.width as f64 * KEN_BURNS_ZOOM).round() as u32,
+                (self.config.height as f64 * KEN_BURNS_ZOOM).round() as u32,
+            )
+        } else {
+            (self.config.width, self.config.height)
+        }
+    }
+    
+    fn ken_burns_enabled(&self) -> bool {
+        self.config.ken_burns && self.config.hold.is_some()
+    }
+    
+    /// Frames each image is held for (1 in timelapse mode)
+    fn hold_frames(&self) -> usize {
+        match self.config.hold {
+            Some(secs) => ((secs * self.config.fps).round() as usize).max(1),
+            None => 1,
+        }
+    }
+    
+    /// Frames shared between consecutive images during a crossfade
+    fn crossfade_frames(&self) -> usize {
+        match (self.config.hold, self.config.crossfade) {
+            (Some(_), Some(secs)) => ((secs * self.config.fps).round() as usize).min(self.hold_frames() / 2),
+            _ => 0,
+        }
+    }
+    
+    /// Queue an image; the previous one is emitted now that its successor is known
+    fn push_image(&self, state: &mut RenderState, canvas: RgbImage, encoder: &mut FfmpegProcess) -> Result<(), FfmpegError> {
+        if let Some(previous) = state.current.take() {
+            self.emit_image(state, &previous, Some(&canvas), encoder)?;
+        }
+        state.current = Some(canvas);
+        Ok(())
+    }
+    
+    /// Write the frames of one image, blending its tail into `next` when crossfading
+    fn emit_image(&self, state: &mut RenderState, canvas: &RgbImage, next: Option<&RgbImage>, encoder: &mut FfmpegProcess) -> Result<(), FfmpegError> {
+        let hold = self.hold_frames();
+        let fade = self.crossfade_frames();
+        let index = state.image_count;
+        
+        // The head of this image was already blended into the previous one's tail
+        let start = if index > 0 { fade } else { 0 };
+        
+        for k in start..hold {
+            let mut frame = self.render(canvas, index, k, hold);
+            
+            if let Some(next) = next {
+                if fade > 0 && k >= hold - fade {
+                    let j = k - (hold - fade);
+                    let incoming = self.render(next, index + 1, j, hold);
+                    let alpha = (j + 1) as f32 / (fade + 1) as f32;
+                    blend(&mut frame, &incoming, alpha);
+                }
+            }
+            
+            encoder.write(frame.as_raw())?;
+            state.frame_count += 1;
+        }
+        
+        state.image_count += 1;
+        Ok(())
+    }
+    
+    /// Frame `k` of `hold` for an image (applies Ken Burns when enabled)
+    fn render(&self, canvas: &RgbImage, index: usize, k: usize, hold: usize) -> RgbImage {
+        if !self.ken_burns_enabled() {
+            return canvas.clone();
+        }
+        
+        let t = if hold > 1 { k as f64 / (hold - 1) as f64 } else { 0.0 };
+        let window = ken_burns_window(canvas.width(), canvas.height(), self.config.width, self.config.height, index, t);
+        
+        let view = image::imageops::crop_imm(canvas, window.0, window.1, window.2, window.3).to_image();
+        image::imageops::resize(&view, self.config.width, self.config.height, FilterType::Triangle)
+    }
+}
+
+/// A CinemaDNG folder or DNG image sequence treated as a video clip
+///
+/// Every frame is decoded through LibRaw (in parallel), so frame extraction,
+/// proxy transcodes and previews work without FFmpeg understanding DNG.
+#[derive(Debug, Clone)]
+pub struct DngSequence {
+    frames: Vec<PathBuf>,
+    fps: f64,
+}
+
+impl DngSequence {
+    /// Open a directory of `.dng` frames (sorted by file name)
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, FfmpegError> {
+        let mut frames: Vec<PathBuf> = std::fs::read_dir(dir.as_ref())?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| is_dng(path))
+            .collect();
+        frames.sort();
+        Self::from_frames(frames)
+    }
+    
+    /// Build a clip from an ordered list of DNG frames
+    ///
+    /// The frame rate is read from the first frame's `FrameRate` tag, falling
+    /// back to 24 fps.
+    pub fn from_frames(frames: Vec<PathBuf>) -> Result<Self, FfmpegError> {
+        let first = frames.first()
+            .ok_or_else(|| FfmpegError::InvalidOutput("DNG sequence has no frames".into()))?;
+        
+        let fps = crate::metadata::dng_frame_rate(first).unwrap_or_else(|| {
+            tracing::warn!("No FrameRate tag in {}, assuming {} fps", first.display(), DEFAULT_DNG_FPS);
+            DEFAULT_DNG_FPS
+        });
+        
+        Ok(Self { frames, fps })
+    }
+    
+    /// Override the frame rate from metadata
+    pub fn with_fps(mut self, fps: f64) -> Self {
+        self.fps = fps;
+        self
+    }
+    
+    /// True if `path` is a directory containing DNG frames
+    pub fn is_sequence_dir(path: impl AsRef<Path>) -> bool {
+        std::fs::read_dir(path.as_ref())
+            .map(|mut entries| entries.any(|e| e.map(|e| is_dng(&e.path())).unwrap_or(false)))
+            .unwrap_or(false)
+    }
+    
+    pub fn frames(&self) -> &[PathBuf] {
+        &self.frames
+    }
+    
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+    
+    /// Duration in seconds
+    pub fn duration(&self) -> f64 {
+        self.frames.len() as f64 / self.fps
+    }
+    
+    /// Sample frames at `config.fps`, decode them with LibRaw and save as JPEG
+    ///
+    /// Output matches [`crate::VideoPreprocessor::extract_frames_with_report`]:
+    /// `frame_%04d.jpg` at `config.width`x`config.height` fitted per
+    /// `config.resize`, honouring `crop_detect`, `max_frames` and `dedupe`.
+    /// LibRaw applies the DNG orientation.
+    pub fn extract_frames(
+        &self,
+        output_dir: impl AsRef<Path>,
+        config: &VideoConfig,
+        options: &RawOptions,
+    ) -> Result<FrameExtraction, FfmpegError> {
+        use rayon::prelude::*;
+        
+        let output_dir = output_dir.as_ref();
+        let raw = RawProcessor::new()
+            .map_err(|e| FfmpegError::ExecutionFailed(e.to_string()))?;
+        let indices = sample_indices(self.frames.len(), self.fps, config.fps as f64, config.max_frames);
+        let decode = |index: usize| raw.decode_image(&self.frames[index], options)
+            .map_err(|e| FfmpegError::ExecutionFailed(format!("libraw failed: {}", e)));
+        
+        // Frames sampled for crop detection are kept for the extraction pass
+        let mut decoded: Vec<Option<DynamicImage>> = vec![None; indices.len()];
+        let crop = if config.crop_detect {
+            let positions = crop_sample_positions(indices.len());
+            let samples: Vec<DynamicImage> = positions
+                .par_iter()
+                .map(|&pos| decode(indices[pos]))
+                .collect::<Result<_, FfmpegError>>()?;
+            let crop = detect_crop(&samples);
+            for (pos, image) in positions.into_iter().zip(samples) {
+                decoded[pos] = Some(image);
+            }
+            crop
+        } else {
+            None
+        };
+        
+        let extracted: Vec<(PathBuf, Option<CropRect>)> = decoded
+            .into_par_iter()
+            .zip(indices.par_iter())
+            .enumerate()
+            .map(|(n, (cached, &index))| {
+                let mut image = match cached {
+                    Some(image) => image,
+                    None => decode(index)?,
+                };
+                if let Some(rect) = crop {
+                    image = image.crop_imm(rect.x, rect.y, rect.width, rect.height);
+                }
+                let (frame, smart_crop) = match config.resize {
+                    ResizeMode::Smart => {
+                        let (cropped, rect) = crate::smartcrop::smart_crop(&image, config.width, config.height, &[]);
+                        (cropped.to_rgb8(), Some(rect))
+                    }
+                    mode => (fit_image(&image, config.width, config.height, mode), None),
+                };
+                
+                let path = output_dir.join(format!("frame_{:04}.jpg", n + 1));
+                frame.save(&path)
+                    .map_err(|e| FfmpegError::InvalidOutput(e.to_string()))?;
+                Ok((path, smart_crop))
+            })
+            .collect::<Result<_, FfmpegError>>()?;
+        let mut frames: Vec<PathBuf> = extracted.iter().map(|(path, _)| path.clone()).collect();
+        
+        let dropped = match config.dedupe {
+            Some(dedupe) => crate::video::suppress_duplicates(&mut frames, dedupe)?,
+            None => Vec::new(),
+        };
+        
+        // Smart crop windows of the frames that survived deduplication
+        let smart_crops = extracted
+            .into_iter()
+            .filter(|(path, _)| frames.contains(path))
+            .filter_map(|(_, rect)| rect)
+            .collect();
+        
+        let placeholders = if config.placeholders {
+            crate::video::frame_placeholders(&frames)?
+        } else {
+            Vec::new()
+        };
+        
+        Ok(FrameExtraction {
+            frames,
+            crop,
+            dropped,
+            smart_crops,
+            placeholders,
+            ..Default::default()
+        })
+    }
+    
+    /// Encode a proxy video at the sequence frame rate
+    ///
+    /// The proxy keeps the frame aspect ratio, limited to
+    /// `options.max_dimension` on the long side.
+    pub fn transcode(
+        &self,
+        output: impl AsRef<Path>,
+        options: &TranscodeConfig,
+        raw_options: &RawOptions,
+    ) -> Result<SequenceReport, FfmpegError> {
+        let raw = RawProcessor::new()
+            .map_err(|e| FfmpegError::ExecutionFailed(e.to_string()))?;
+        let first = raw.decode_image(&self.frames[0], raw_options)
+            .map_err(|e| FfmpegError::ExecutionFailed(format!("libraw failed: {}", e)))?;
+        let (width, height) = proxy_size(first.width(), first.height(), options.max_dimension);
+        
+        // The first frame is decoded once, for the proxy size and as frame 1
+        
+        let encoder = SequenceEncoder::new(SequenceConfig {
+            fps: self.fps,
+            width,
+            height,
+            resize: ResizeMode::Fit,
+            codec: options.codec,
+            crf: options.crf,
+            preset: options.preset.clone(),
+            raw_decode: RawDecode::Full(raw_options.clone()),
+            hold: None,
+            crossfade: None,
+            ken_burns: false,
+            watermark: options.watermark.clone(),
+        });
+        encoder.encode_from(&self.frames, output, Some(first))
+    }
+    
+    /// Middle frame, used as the sequence's poster image
+    pub fn poster_frame(&self) -> &Path {
+        &self.frames[self.frames.len() / 2]
+    }
+    
+    /// WebP poster image from the middle frame (embedded preview when available)
+    pub fn preview(&self, options: &PreviewOptions) -> crate::error::Result<Vec<u8>> {
+        let raw = RawProcessor::new()?;
+        raw.extract_preview_webp(self.poster_frame(), options)
+    }
+}
+
+/// Source frame indices sampled at `target_fps` from a clip at `source_fps`
+fn sample_indices(frame_count: usize, source_fps: f64, target_fps: f64, max_frames: Option<usize>) -> Vec<usize> {
+    if frame_count == 0 || source_fps <= 0.0 || target_fps <= 0.0 {
+        return Vec::new();
+    }
+    
+    let step = source_fps / target_fps;
+    let mut indices: Vec<usize> = (0..)
+        .map(|n| (n as f64 * step).round() as usize)
+        .take_while(|&i| i < frame_count)
+        .collect();
+    indices.dedup();
+    
+    if let Some(max) = max_frames {
+        indices.truncate(max);
+    }
+    indices
+}
+
+/// Positions in a list of `count` sampled frames used for crop detection
+fn crop_sample_positions(count: usize) -> Vec<usize> {
+    let points = CROP_SAMPLE_POINTS.min(count);
+    (0..points).map(|i| (2 * i + 1) * count / (2 * points)).collect()
+}
+
+/// Active picture area over the sampled frames, None when no frame has
+/// black bars
+fn detect_crop(samples: &[DynamicImage]) -> Option<CropRect> {
+    use rayon::prelude::*;
+    
+    let areas: Vec<(CropRect, (u32, u32))> = samples
+        .par_iter()
+        .map(|image| {
+            let image = image.to_rgb8();
+            (active_area(&image, CROP_LIMIT), image.dimensions())
+        })
+        .collect();
+    
+    // A crop covering the whole picture is no crop at all
+    let &(first, (width, height)) = areas.first()?;
+    let rect = areas.iter().skip(1).fold(first, |acc, (rect, _)| acc.union(rect));
+    if rect.width >= width && rect.height >= height {
+        return None;
+    }
+    Some(rect)
+}
+
+/// Even proxy dimensions preserving aspect ratio (never upscales)
+fn proxy_size(width: u32, height: u32, max_dimension: Option<u32>) -> (u32, u32) {
+    let scale = match max_dimension {
+        Some(max) if width.max(height) > max => max as f64 / width.max(height) as f64,
+        _ => 1.0,
+    };
+    let even = |v: f64| ((v / 2.0).round() as u32 * 2).max(2);
+    (even(width as f64 * scale), even(height as f64 * scale))
+}
+
+fn is_dng(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("dng"))
+        .unwrap_or(false)
+}
+
+#[derive(Default)]
+struct RenderState {
+    current: Option<RgbImage>,
+    image_count: usize,
+    frame_count: usize,
+}
+
+/// Expand a glob pattern into a sorted list of files
+pub fn expand_glob(pattern: &str) -> Result<Vec<PathBuf>, FfmpegError> {
+    let paths = glob::glob(pattern)
+        .map_err(|e| FfmpegError::InvalidOutput(format!("Invalid glob pattern: {}", e)))?;
+    
+    let mut files: Vec<PathBuf> = paths
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Formats `image` decodes directly even though MIME sniffing may call them TIFF
+fn is_plain_image(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref(),
+        Some("jpg") | Some("jpeg") | Some("png") | Some("webp") | Some("bmp") | Some("gif") | Some("tif") | Some("tiff")
+    )
+}
+
+/// Crop window (x, y, w, h) on the canvas at progress `t` (0.0-1.0)
+///
+/// Even images zoom in, odd images zoom out, and the pan direction alternates
+/// so consecutive images don't move the same way.
+fn ken_burns_window(canvas_w: u32, canvas_h: u32, out_w: u32, out_h: u32, index: usize, t: f64) -> (u32, u32, u32, u32) {
+    let t = if index % 2 == 1 { 1.0 - t } else { t };
+    
+    // Window shrinks from the full canvas to the output size
+    let w = (canvas_w as f64 + (out_w as f64 - canvas_w as f64) * t).round() as u32;
+    let h = (canvas_h as f64 + (out_h as f64 - canvas_h as f64) * t).round() as u32;
+    
+    let max_x = canvas_w - w;
+    let max_y = canvas_h - h;
+    let (fx, fy) = match index % 4 {
+        0 => (0.5, 0.5),
+        1 => (0.0, 0.0),
+        2 => (1.0, 0.0),
+        _ => (0.5, 1.0),
+    };
+    
+    ((max_x as f64 * fx).round() as u32, (max_y as f64 * fy).round() as u32, w, h)
+}
+
+/// Blend `incoming` over `frame` with weight `alpha`
+fn blend(frame: &mut RgbImage, incoming: &RgbImage, alpha: f32) {
+    for (dst, src) in frame.as_mut().iter_mut().zip(incoming.as_raw()) {
+        *dst = (*dst as f32 * (1.0 - alpha) + *src as f32 * alpha).round() as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_sample_indices() {
+        // 1 fps from a 24 fps clip of 60 frames
+        assert_eq!(sample_indices(60, 24.0, 1.0, None), vec![0, 24, 48]);
+        // Target faster than source never repeats frames
+        assert_eq!(sample_indices(3, 24.0, 60.0, None), vec![0, 1, 2]);
+        assert_eq!(sample_indices(60, 24.0, 1.0, Some(2)), vec![0, 24]);
+        
+        assert_eq!(proxy_size(4096, 2160, Some(1920)), (1920, 1012));
+        assert_eq!(proxy_size(1280, 720, Some(1920)), (1280, 720));
+    }
+    
+    #[test]
+    fn test_detect_crop() {
+        assert_eq!(crop_sample_positions(20), vec![2, 6, 10, 14, 18]);
+        assert_eq!(crop_sample_positions(2), vec![0, 1]);
+        
+        // 16px letterbox bars on a 64x64 frame
+        let letterboxed = DynamicImage::ImageRgb8(RgbImage::from_fn(64, 64, |_, y| {
+            if (16..48).contains(&y) { image::Rgb([200, 120, 80]) } else { image::Rgb([0, 0, 0]) }
+        }));
+        let rect = detect_crop(&[letterboxed.clone(), letterboxed]).unwrap();
+        assert_eq!((rect.y, rect.height, rect.width), (16, 32, 64));
+        
+        let full = DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 64, image::Rgb([200, 120, 80])));
+        assert!(detect_crop(&[full]).is_none());
+    }
+    
+    #[test]
+    fn test_encode_rejects_odd_size() {
+        let encoder = SequenceEncoder::new(SequenceConfig { width: 1919, ..SequenceConfig::default() });
+        match encoder.encode(&["a.jpg"], "out.mp4") {
+            Err(FfmpegError::InvalidOutput(message)) => assert!(message.contains("even")),
+            other => panic!("expected InvalidOutput, got {:?}", other.map(|_| ())),
+        }
+    }
+    
+    #[test]
+    fn test_ken_burns_window() {
+        // Zoom in: starts at the full canvas, ends at the output size
+        assert_eq!(ken_burns_window(230, 115, 200, 100, 0, 0.0), (0, 0, 230, 115));
+        assert_eq!(ken_burns_window(230, 115, 200, 100, 0, 1.0), (15, 8, 200, 100));
+        
+        // Zoom out on odd images
+        assert_eq!(ken_burns_window(230, 115, 200, 100, 1, 0.0), (0, 0, 200, 100));
+    }
+}
@@ -0,0 +1,410 @@
+//! Model-ready tensor output
+//!
+//! Turns an image, RAW preview, video frame or sampled animation frames into
+//! a normalized float32 tensor laid out exactly as a vision model expects, so
+//! inference services don't re-implement preprocessing.
+//!
+//! Pipeline: decode (EXIF-oriented) → resize/crop → channel order →
+//! scale to 0..1 → `(x - mean) / std` → CHW or HWC layout.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use soma_media::{TensorConverter, TensorPreset};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let converter = TensorConverter::new(TensorPreset::Clip.config());
+//! let tensor = converter.from_path("photo.jpg")?;
+//! assert_eq!(tensor.shape, vec![1, 3, 224, 224]);
+//! tensor.save_npy("photo.npy")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::animation;
+use crate::ffmpeg::{FfmpegCommand, FfmpegError};
+use crate::image::load_any_image;
+use crate::raw::PreviewOptions;
+use crate::geometry::{fit_image, ResizeMode};
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbImage};
+use std::path::Path;
+
+/// ImageNet channel statistics (also used by DINOv2)
+const IMAGENET_MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+const IMAGENET_STD: [f32; 3] = [0.229, 0.224, 0.225];
+
+/// OpenAI CLIP channel statistics
+const CLIP_MEAN: [f32; 3] = [0.481_454_66, 0.457_827_5, 0.408_210_73];
+const CLIP_STD: [f32; 3] = [0.268_629_54, 0.261_302_6, 0.275_777_1];
+
+/// Memory layout of the tensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TensorLayout {
+    /// Channels, height, width (PyTorch)
+    Chw,
+    /// Height, width, channels (TensorFlow/JAX)
+    Hwc,
+}
+
+impl TensorLayout {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "chw" | "nchw" => Some(TensorLayout::Chw),
+            "hwc" | "nhwc" => Some(TensorLayout::Hwc),
+            _ => None,
+        }
+    }
+}
+
+/// Channel order of the tensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelOrder {
+    Rgb,
+    /// OpenCV / Caffe-style models
+    Bgr,
+}
+
+impl ChannelOrder {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "rgb" => Some(ChannelOrder::Rgb),
+            "bgr" => Some(ChannelOrder::Bgr),
+            _ => None,
+        }
+    }
+}
+
+/// Named preprocessing presets matching the reference implementations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TensorPreset {
+    /// OpenAI CLIP: bicubic short-side resize to 224, center crop, CLIP mean/std
+    Clip,
+    /// SigLIP: bicubic resize to 224x224 (no crop), mean/std 0.5
+    Siglip,
+    /// torchvision ImageNet eval: resize 256, center crop 224, ImageNet mean/std
+    ImageNet,
+    /// DINOv2: resize 256, center crop 224, ImageNet mean/std
+    Dinov2,
+}
+
+impl TensorPreset {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "clip" => Some(TensorPreset::Clip),
+            "siglip" => Some(TensorPreset::Siglip),
+            "imagenet" => Some(TensorPreset::ImageNet),
+            "dinov2" => Some(TensorPreset::Dinov2),
+            _ => None,
+        }
+    }
+    
+    pub fn config(&self) -> TensorConfig {
+        match self {
+            TensorPreset::Clip => TensorConfig {
+                mean: CLIP_MEAN,
+                std: CLIP_STD,
+                ..TensorConfig::default()
+            },
+            TensorPreset::Siglip => TensorConfig {
+                resize: ResizeMode::Stretch,
+                mean: [0.5; 3],
+                std: [0.5; 3],
+                ..TensorConfig::default()
+            },
+            TensorPreset::ImageNet | TensorPreset::Dinov2 => TensorConfig {
+                crop_fraction: 0.875,  // 224 / 256
+                mean: IMAGENET_MEAN,
+                std: IMAGENET_STD,
+                ..TensorConfig::default()
+            },
+        }
+    }
+}
+
+/// Tensor preprocessing settings
+#[derive(Debug, Clone)]
+pub struct TensorConfig {
+    pub width: u32,
+    pub height: u32,
+    /// Fill = resize to cover then center crop, Fit = letterbox, Stretch = exact,
+    /// Smart = crop around the most salient region then resize
+    pub resize: ResizeMode,
+    /// Fraction of the resized image kept by the center crop (Fill only, 1.0 = none)
+    pub crop_fraction: f32,
+    pub channel_order: ChannelOrder,
+    pub layout: TensorLayout,
+    /// Per-channel mean subtracted after scaling to 0..1 (in `channel_order`)
+    pub mean: [f32; 3],
+    /// Per-channel std divided after mean subtraction (in `channel_order`)
+    pub std: [f32; 3],
+    /// Prepend a batch dimension of 1
+    pub batch_dim: bool,
+}
+
+impl Default for TensorConfig {
+    fn default() -> Self {
+        Self {
+            width: 224,
+            height: 224,
+            resize: ResizeMode::Fill,
+            crop_fraction: 1.0,
+            channel_order: ChannelOrder::Rgb,
+            layout: TensorLayout::Chw,
+            mean: [0.0; 3],  // Plain 0..1 scaling
+            std: [1.0; 3],
+            batch_dim: true,  // Models take NCHW / NHWC
+        }
+    }
+}
+
+/// Dense float32 tensor (row-major)
+#[derive(Debug, Clone)]
+pub struct Tensor {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+impl Tensor {
+    /// Serialize as NumPy `.npy` (format version 1.0, little-endian float32)
+    pub fn to_npy_bytes(&self) -> Vec<u8> {
+        let shape = match self.shape.len() {
+            1 => format!("({},)", self.shape[0]),
+            _ => format!("({})", self.shape.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")),
+        };
+        let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}", shape);
+        
+        // Magic (6) + version (2) + header length (2) + header must be a multiple of 64
+        let unpadded = 10 + header.len() + 1;
+        header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+        header.push('\n');
+        
+        let mut bytes = Vec::with_capacity(10 + header.len() + self.data.len() * 4);
+        bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        for value in &self.data {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+    
+    /// Serialize as `.safetensors` with a single tensor called `name`
+    pub fn to_safetensors_bytes(&self, name: &str) -> Vec<u8> {
+        let byte_len = self.data.len() * 4;
+        let header = serde_json::json!({
+            name: {
+                "dtype": "F32",
+                "shape": self.shape,
+                "data_offsets": [0, byte_len],
+            }
+        });
+        let mut header = header.to_string();
+        
+        // Pad the header so the data section is 8-byte aligned
+        header.push_str(&" ".repeat((8 - header.len() % 8) % 8));
+        
+        let mut bytes = Vec::with_capacity(8 + header.len() + byte_len);
+        bytes.extend_from_slice(&(header.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        for value in &self.data {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+    
+    pub fn save_npy(&self, path: impl AsRef<Path>) -> Result<(), FfmpegError> {
+        std::fs::write(path, self.to_npy_bytes())?;
+        Ok(())
+    }
+    
+    /// Save as `.safetensors` under the key `pixel_values`
+    pub fn save_safetensors(&self, path: impl AsRef<Path>) -> Result<(), FfmpegError> {
+        std::fs::write(path, self.to_safetensors_bytes("pixel_values"))?;
+        Ok(())
+    }
+}
+
+/// Converts images to model input tensors
+pub struct TensorConverter {
+    config: TensorConfig,
+}
+
+impl TensorConverter {
+    pub fn new(config: TensorConfig) -> Self {
+        Self { config }
+    }
+    
+    /// Load an image (RAW files via their preview) and convert it
+    pub fn from_path(&self, path: impl AsRef<Path>) -> Result<Tensor, FfmpegError> {
+        let image = load_any_image(path.as_ref(), &PreviewOptions::default())?;
+        Ok(self.from_image(&image))
+    }
+    
+    /// Grab the video frame at `timestamp` seconds and convert it
+    pub fn from_video_frame(&self, video: impl AsRef<Path>, timestamp: f64) -> Result<Tensor, FfmpegError> {
+        let output = FfmpegCommand::new()
+            .args(&["-ss", &format!("{:.3}", timestamp)])
+            .input(video)
+            .args(&["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png"])
+            .output("-")
+            .execute()?;
+        
+        let image = image::load_from_memory_with_format(&output.stdout, image::ImageFormat::Png)
+            .map_err(|e| FfmpegError::InvalidOutput(format!("No frame at {}s: {}", timestamp, e)))?;
+        
+        Ok(self.from_image(&image))
+    }
+    
+    /// Sample `count` frames evenly over a GIF/APNG/animated WebP's running
+    /// time and stack them on a leading frame axis (in place of the batch
+    /// dimension), e.g. `[count, 3, 224, 224]`
+    ///
+    /// Returns the tensor and the source frame indices used.
+    pub fn from_animation(&self, path: impl AsRef<Path>, count: usize) -> Result<(Tensor, Vec<usize>), FfmpegError> {
+        let frames = animation::decode_frames(path)?;
+        let indices = animation::sample_frames(&frames, count.max(1));
+        
+        let mut shape = Vec::new();
+        let mut data = Vec::new();
+        for &index in &indices {
+            let tensor = self.from_image(&DynamicImage::ImageRgba8(frames[index].image.clone()));
+            shape = tensor.shape;
+            data.extend(tensor.data);
+        }
+        if self.config.batch_dim {
+            shape[0] = indices.len();
+        } else {
+            shape.insert(0, indices.len());
+        }
+        
+        Ok((Tensor { shape, data }, indices))
+    }
+    
+    /// Resize, normalize and lay out an already-decoded image
+    pub fn from_image(&self, image: &DynamicImage) -> Tensor {
+        let rgb = self.resize(image);
+        let (width, height) = (rgb.width() as usize, rgb.height() as usize);
+        let plane = width * height;
+        
+        let channels: [usize; 3] = match self.config.channel_order {
+            ChannelOrder::Rgb => [0, 1, 2],
+            ChannelOrder::Bgr => [2, 1, 0],
+        };
+        
+        let mut data = vec![0.0f32; plane * 3];
+        for (i, pixel) in rgb.pixels().enumerate() {
+            for (c, &source) in channels.iter().enumerate() {
+                let value = (pixel.0[source] as f32 / 255.0 - self.config.mean[c]) / self.config.std[c];
+                let index = match self.config.layout {
+                    TensorLayout::Chw => c * plane + i,
+                    TensorLayout::Hwc => i * 3 + c,
+                };
+                data[index] = value;
+            }
+        }
+        
+        let mut shape = match self.config.layout {
+            TensorLayout::Chw => vec![3, height, width],
+            TensorLayout::Hwc => vec![height, width, 3],
+        };
+        if self.config.batch_dim {
+            shape.insert(0, 1);
+        }
+        
+        Tensor { shape, data }
+    }
+    
+    /// Resize/crop to the configured size (bicubic, as the reference processors use)
+    fn resize(&self, image: &DynamicImage) -> RgbImage {
+        let (width, height) = (self.config.width, self.config.height);
+        
+        match self.config.resize {
+            ResizeMode::Stretch => image.resize_exact(width, height, FilterType::CatmullRom).to_rgb8(),
+            ResizeMode::Fit => fit_image(image, width, height, ResizeMode::Fit),
+            ResizeMode::Smart => {
+                let rect = crate::smartcrop::smart_crop_rect(image, width, height, &[]);
+                image.crop_imm(rect.x, rect.y, rect.width, rect.height)
+                    .resize_exact(width, height, FilterType::CatmullRom)
+                    .to_rgb8()
+            }
+            ResizeMode::Fill => {
+                let fraction = self.config.crop_fraction.clamp(0.01, 1.0) as f64;
+                let (cover_w, cover_h) = (width as f64 / fraction, height as f64 / fraction);
+                let scale = (cover_w / image.width() as f64).max(cover_h / image.height() as f64);
+                
+                let scaled_w = ((image.width() as f64 * scale).round() as u32).max(width);
+                let scaled_h = ((image.height() as f64 * scale).round() as u32).max(height);
+                let scaled = image.resize_exact(scaled_w, scaled_h, FilterType::CatmullRom).to_rgb8();
+                
+                let x = (scaled_w - width) / 2;
+                let y = (scaled_h - height) / 2;
+                image::imageops::crop_imm(&scaled, x, y, width, height).to_image()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn solid(r: u8, g: u8, b: u8) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(40, 30, image::Rgb([r, g, b])))
+    }
+    
+    #[test]
+    fn test_normalization_and_layout() {
+        let converter = TensorConverter::new(TensorConfig {
+            width: 4,
+            height: 2,
+            mean: [0.5; 3],
+            std: [0.5; 3],
+            ..TensorConfig::default()
+        });
+        let tensor = converter.from_image(&solid(255, 0, 255));
+        
+        assert_eq!(tensor.shape, vec![1, 3, 2, 4]);
+        assert!((tensor.data[0] - 1.0).abs() < 1e-6);   // R plane
+        assert!((tensor.data[8] + 1.0).abs() < 1e-6);   // G plane
+        
+        let hwc = TensorConverter::new(TensorConfig {
+            width: 4,
+            height: 2,
+            channel_order: ChannelOrder::Bgr,
+            layout: TensorLayout::Hwc,
+            batch_dim: false,
+            ..TensorConfig::default()
+        })
+        .from_image(&solid(255, 0, 0));
+        
+        assert_eq!(hwc.shape, vec![2, 4, 3]);
+        assert_eq!(&hwc.data[..3], &[0.0, 0.0, 1.0]);  // B, G, R
+    }
+    
+    #[test]
+    fn test_preset_crop() {
+        let tensor = TensorConverter::new(TensorPreset::ImageNet.config()).from_image(&solid(0, 0, 0));
+        assert_eq!(tensor.shape, vec![1, 3, 224, 224]);
+        assert!((tensor.data[0] - (-0.485 / 0.229)).abs() < 1e-5);
+    }
+    
+    #[test]
+    fn test_serialization() {
+        let tensor = Tensor { shape: vec![2, 3], data: vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0] };
+        
+        let npy = tensor.to_npy_bytes();
+        assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        assert!(std::str::from_utf8(&npy[10..10 + header_len]).unwrap().contains("'shape': (2, 3)"));
+        assert_eq!(npy.len(), 10 + header_len + 24);
+        
+        let st = tensor.to_safetensors_bytes("pixel_values");
+        let header_len = u64::from_le_bytes(st[..8].try_into().unwrap()) as usize;
+        let header: serde_json::Value = serde_json::from_slice(&st[8..8 + header_len]).unwrap();
+        assert_eq!(header["pixel_values"]["shape"], serde_json::json!([2, 3]));
+        assert_eq!(header["pixel_values"]["data_offsets"], serde_json::json!([0, 24]));
+        assert_eq!(st.len(), 8 + header_len + 24);
+    }
+}
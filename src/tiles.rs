@@ -0,0 +1,347 @@
+//! Deep-zoom tile pyramids
+//!
+//! Cuts very large images (gigapixel scans, full-resolution RAW renders) into
+//! a tiled pyramid for pan/zoom viewers such as OpenSeadragon. Two static
+//! layouts are written:
+//!
+//! - **DeepZoom**: `<name>.dzi` plus `<name>_files/<level>/<col>_<row>.<ext>`,
+//!   levels from 1x1 up to full size, tiles overlapping by `overlap` pixels
+//! - **IIIF level 0**: `<name>/info.json` plus Image API 3.0 paths
+//!   `<name>/<region>/<w>,<h>/0/default.<ext>`, servable from any static host
+//!
+//! The pyramid is built by halving the previous level, so only the current
+//! level and the next one are held in memory.
+//!
+//! ## Example
+//!
+//! ```rust,no_run
+//! use soma_media::{TileGenerator, TileConfig, TileLayout};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let generator = TileGenerator::new(TileConfig { layout: TileLayout::Iiif, ..TileConfig::default() });
+//! let report = generator.generate("scan.tif", "/var/www/tiles", "scan")?;
+//! println!("{} tiles in {} levels", report.tile_count, report.levels);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::color::TargetColorSpace;
+use crate::ffmpeg::FfmpegError;
+use crate::image::{resize_buffer, ImageConfig, ImageOutputFormat, ImagePreprocessor};
+use crate::raw::RawOptions;
+use crate::raw::RawDecode;
+use fast_image_resize::PixelType;
+use image::DynamicImage;
+use rayon::prelude::*;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// Static tile layout written by [`TileGenerator`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileLayout {
+    /// Microsoft DeepZoom (`.dzi`)
+    DeepZoom,
+    /// IIIF Image API 3.0, level 0 (pre-cut tiles, no image server)
+    Iiif,
+}
+
+impl TileLayout {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "dzi" | "deepzoom" => Some(TileLayout::DeepZoom),
+            "iiif" => Some(TileLayout::Iiif),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &str {
+        match self {
+            TileLayout::DeepZoom => "dzi",
+            TileLayout::Iiif => "iiif",
+        }
+    }
+}
+
+/// Settings for [`TileGenerator`]
+#[derive(Debug, Clone)]
+pub struct TileConfig {
+    pub layout: TileLayout,
+    /// Tile edge in pixels (excluding overlap)
+    pub tile_size: u32,
+    /// Pixels shared with neighbouring tiles (DeepZoom only; IIIF has none)
+    pub overlap: u32,
+    pub format: ImageOutputFormat,
+    pub quality: u8,
+    /// Output colour space; tiles are only tagged with a profile when not sRGB
+    pub color_space: TargetColorSpace,
+    /// How RAW files are decoded
+    pub raw_decode: RawDecode,
+    /// Public URL of the tile directory, used for the IIIF `id`
+    pub base_url: Option<String>,
+}
+
+impl Default for TileConfig {
+    fn default() -> Self {
+        Self {
+            layout: TileLayout::DeepZoom,
+            tile_size: 254,  // 254 + 2px overlap = 256, OpenSeadragon's default
+            overlap: 1,
+            format: ImageOutputFormat::Jpeg,
+            quality: 85,
+            color_space: TargetColorSpace::Srgb,
+            raw_decode: RawDecode::Full(RawOptions::default()),  // Embedded previews are too small to zoom into
+            base_url: None,
+        }
+    }
+}
+
+/// Result of [`TileGenerator::generate`]
+#[derive(Debug, Clone)]
+pub struct TileReport {
+    pub layout: TileLayout,
+    /// Full-resolution size
+    pub width: u32,
+    pub height: u32,
+    pub levels: u32,
+    pub tile_count: usize,
+    /// `.dzi` or `info.json`
+    pub manifest: PathBuf,
+    /// Directory holding the tiles
+    pub tiles_dir: PathBuf,
+}
+
+/// One pyramid level held in memory
+struct Level {
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+/// Tile pyramid writer
+pub struct TileGenerator {
+    config: TileConfig,
+}
+
+impl TileGenerator {
+    pub fn new(config: TileConfig) -> Self {
+        Self { config }
+    }
+    
+    /// Decode `input` at full resolution and write its pyramid to `output_dir` as `name`
+    pub fn generate(&self, input: impl AsRef<Path>, output_dir: impl AsRef<Path>, name: &str) -> Result<TileReport, FfmpegError> {
+        let loader = ImagePreprocessor::new(ImageConfig {
+            color_space: self.config.color_space,
+            ..ImageConfig::default()
+        });
+        let image = loader.load_source(input.as_ref(), &self.config.raw_decode)?;
+        self.generate_image(image, output_dir, name)
+    }
+    
+    /// Write the pyramid of a decoded image
+    pub fn generate_image(&self, image: DynamicImage, output_dir: impl AsRef<Path>, name: &str) -> Result<TileReport, FfmpegError> {
+        let output_dir = output_dir.as_ref();
+        let tile_size = self.config.tile_size.max(1);
+        let alpha = image.color().has_alpha() && !matches!(self.config.format, ImageOutputFormat::Jpeg);
+        let (width, height) = (image.width(), image.height());
+        let mut level = Level {
+            pixels: if alpha { image.into_rgba8().into_raw() } else { image.into_rgb8().into_raw() },
+            width,
+            height,
+        };
+        let channels = if alpha { 4 } else { 3 };
+        
+        // DeepZoom goes down to 1x1; IIIF stops once the image fits one tile
+        let mut max_level = 0;
+        while (1u64 << max_level) < width.max(height) as u64 {
+            max_level += 1;
+        }
+        let levels = match self.config.layout {
+            TileLayout::DeepZoom => max_level + 1,
+            TileLayout::Iiif => {
+                let mut count = 1;
+                while width.max(height).div_ceil(1 << (count - 1)) > tile_size {
+                    count += 1;
+                }
+                count
+            }
+        };
+        
+        let (manifest, tiles_dir) = match self.config.layout {
+            TileLayout::DeepZoom => (output_dir.join(format!("{}.dzi", name)), output_dir.join(format!("{}_files", name))),
+            TileLayout::Iiif => (output_dir.join(name).join("info.json"), output_dir.join(name)),
+        };
+        std::fs::create_dir_all(&tiles_dir)?;
+        
+        let icc = (self.config.color_space != TargetColorSpace::Srgb).then(|| self.config.color_space.icc_profile());
+        let encoder = ImagePreprocessor::encoder(ImageConfig {
+            format: self.config.format,
+            quality: self.config.quality,
+            color_space: self.config.color_space,
+            ..ImageConfig::default()
+        });
+        
+        let mut tile_count = 0;
+        for step in 0..levels {
+            let tiles = match self.config.layout {
+                TileLayout::DeepZoom => self.dzi_tiles(&level, &tiles_dir.join((max_level - step).to_string())),
+                TileLayout::Iiif => self.iiif_tiles(&level, &tiles_dir, 1 << step, width, height),
+            };
+            
+            tiles
+                .par_iter()
+                .map(|(x, y, w, h, path)| {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    let pixels = crop(&level, channels, *x, *y, *w, *h);
+                    encoder.encode_native(&pixels, *w, *h, alpha, path, icc.as_deref())
+                })
+                .collect::<Result<Vec<()>, FfmpegError>>()?;
+            tile_count += tiles.len();
+            
+            if step + 1 < levels {
+                let (next_width, next_height) = (level.width.div_ceil(2), level.height.div_ceil(2));
+                let pixel_type = if alpha { PixelType::U8x4 } else { PixelType::U8x3 };
+                level = Level {
+                    pixels: resize_buffer(&level.pixels, level.width, level.height, pixel_type, next_width, next_height, None)?,
+                    width: next_width,
+                    height: next_height,
+                };
+            }
+        }
+        
+        let manifest_body = match self.config.layout {
+            TileLayout::DeepZoom => format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <Image xmlns=\"http://schemas.microsoft.com/deepzoom/2008\" Format=\"{}\" Overlap=\"{}\" TileSize=\"{}\">\n  \
+                 <Size Width=\"{}\" Height=\"{}\"/>\n</Image>\n",
+                self.config.format.extension(), self.config.overlap, tile_size, width, height
+            ),
+            TileLayout::Iiif => serde_json::to_string_pretty(&self.iiif_info(name, width, height, levels))
+                .map_err(|e| FfmpegError::InvalidOutput(format!("Failed to write info.json: {}", e)))?,
+        };
+        std::fs::write(&manifest, manifest_body)?;
+        
+        Ok(TileReport {
+            layout: self.config.layout,
+            width,
+            height,
+            levels,
+            tile_count,
+            manifest,
+            tiles_dir,
+        })
+    }
+    
+    /// DeepZoom tiles of one level: `(x, y, w, h, path)` in level pixels
+    fn dzi_tiles(&self, level: &Level, dir: &Path) -> Vec<(u32, u32, u32, u32, PathBuf)> {
+        let (tile, overlap) = (self.config.tile_size.max(1), self.config.overlap);
+        let extension = self.config.format.extension();
+        let span = |index: u32, extent: u32| {
+            let start = (index * tile).saturating_sub(if index > 0 { overlap } else { 0 });
+            let end = ((index + 1) * tile + overlap).min(extent);
+            (start, end - start)
+        };
+        
+        let mut tiles = Vec::new();
+        for col in 0..level.width.div_ceil(tile) {
+            for row in 0..level.height.div_ceil(tile) {
+                let (x, w) = span(col, level.width);
+                let (y, h) = span(row, level.height);
+                tiles.push((x, y, w, h, dir.join(format!("{}_{}.{}", col, row, extension))));
+            }
+        }
+        tiles
+    }
+    
+    /// IIIF tiles at `scale`: regions in full-resolution pixels, cut from the scaled level
+    fn iiif_tiles(&self, level: &Level, dir: &Path, scale: u32, width: u32, height: u32) -> Vec<(u32, u32, u32, u32, PathBuf)> {
+        let tile = self.config.tile_size.max(1);
+        let region_size = tile * scale;
+        let file = format!("default.{}", self.config.format.extension());
+        
+        let mut tiles = Vec::new();
+        for col in 0..width.div_ceil(region_size) {
+            for row in 0..height.div_ceil(region_size) {
+                let (region_x, region_y) = (col * region_size, row * region_size);
+                let region_w = region_size.min(width - region_x);
+                let region_h = region_size.min(height - region_y);
+                let (w, h) = (region_w.div_ceil(scale), region_h.div_ceil(scale));
+                
+                let region = if region_w == width && region_h == height {
+                    "full".to_string()
+                } else {
+                    format!("{},{},{},{}", region_x, region_y, region_w, region_h)
+                };
+                let (w, h) = (w.min(level.width - col * tile), h.min(level.height - row * tile));
+                tiles.push((col * tile, row * tile, w, h, dir.join(region).join(format!("{},{}", w, h)).join("0").join(&file)));
+            }
+        }
+        tiles
+    }
+    
+    fn iiif_info(&self, name: &str, width: u32, height: u32, levels: u32) -> serde_json::Value {
+        let id = match &self.config.base_url {
+            Some(base) => format!("{}/{}", base.trim_end_matches('/'), name),
+            None => name.to_string(),
+        };
+        let smallest = 1u32 << (levels - 1);
+        
+        json!({
+            "@context": "http://iiif.io/api/image/3/context.json",
+            "id": id,
+            "type": "ImageService3",
+            "protocol": "http://iiif.io/api/image",
+            "profile": "level0",
+            "width": width,
+            "height": height,
+            "sizes": [{ "width": width.div_ceil(smallest), "height": height.div_ceil(smallest) }],
+            "tiles": [{
+                "width": self.config.tile_size.max(1),
+                "scaleFactors": (0..levels).map(|l| 1u32 << l).collect::<Vec<_>>()
+            }]
+        })
+    }
+}
+
+/// Copy a `w`x`h` rectangle out of an interleaved level buffer
+fn crop(level: &Level, channels: usize, x: u32, y: u32, w: u32, h: u32) -> Vec<u8> {
+    let stride = level.width as usize * channels;
+    let mut pixels = Vec::with_capacity(w as usize * h as usize * channels);
+    for row in y..y + h {
+        let start = row as usize * stride + x as usize * channels;
+        pixels.extend_from_slice(&level.pixels[start..start + w as usize * channels]);
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use image::{Rgb, RgbImage};
+    
+    #[test]
+    fn test_dzi_and_iiif_layouts() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(600, 300, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, 128])));
+        
+        let dzi = TileGenerator::new(TileConfig::default()).generate_image(image.clone(), dir, "scan").unwrap();
+        assert_eq!(dzi.levels, 11);
+        let edge = image::open(dir.join("scan_files/10/2_1.jpg")).unwrap();
+        assert_eq!((edge.width(), edge.height()), (93, 47));
+        let top = image::open(dir.join("scan_files/0/0_0.jpg")).unwrap();
+        assert_eq!((top.width(), top.height()), (1, 1));
+        assert!(std::fs::read_to_string(&dzi.manifest).unwrap().contains("Width=\"600\" Height=\"300\""));
+        
+        let config = TileConfig { layout: TileLayout::Iiif, tile_size: 256, overlap: 0, ..TileConfig::default() };
+        let iiif = TileGenerator::new(config).generate_image(image, dir, "scan").unwrap();
+        assert_eq!(iiif.levels, 3);
+        let info: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&iiif.manifest).unwrap()).unwrap();
+        assert_eq!(info["tiles"][0]["scaleFactors"], json!([1, 2, 4]));
+        assert!(dir.join("scan/512,256,88,44/88,44/0/default.jpg").exists());
+        assert!(dir.join("scan/full/150,75/0/default.jpg").exists());
+        assert_eq!(iiif.tile_count, 6 + 2 + 1);
+    }
+}
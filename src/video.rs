@@ -1,14 +1,40 @@
 //! Video preprocessing via FFmpeg
 
 use crate::ffmpeg::{FfmpegCommand, FfmpegError};
+use crate::placeholder::{self, Placeholders};
+use crate::geometry::{CropRect, ResizeMode};
+use crate::watermark::{self, Watermark};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/// Number of points across the clip sampled for black-bar detection
+pub(crate) const CROP_SAMPLE_POINTS: usize = 5;
+
+/// Mean luma at or below which a row/column counts as a black bar
+pub(crate) const CROP_LIMIT: u8 = 24;
+
+/// Frames analysed by `cropdetect` at each sample point
+const CROP_FRAMES_PER_SAMPLE: usize = 8;
 
 pub struct VideoConfig {
     pub fps: u8,
     pub width: u32,
     pub height: u32,
     pub max_frames: Option<usize>,
+    /// Keep the coded orientation instead of applying the rotate tag / display matrix
+    pub keep_raw_orientation: bool,
+    /// Tone-mapping operator for HDR (PQ/HLG) sources, None leaves HDR frames untouched
+    pub tone_map: Option<ToneMapOperator>,
+    /// Detect letterbox/pillarbox bars and crop to the active picture area
+    pub crop_detect: bool,
+    /// Drop sampled frames that are near-duplicates of the last kept frame
+    pub dedupe: Option<FrameDedupe>,
+    /// How frames are fitted to `width`x`height` (`Fit` pads with black)
+    pub resize: ResizeMode,
+    /// Compute BlurHash/ThumbHash placeholders for each kept frame
+    pub placeholders: bool,
 }
 
 impl Default for VideoConfig {
@@ -18,8 +44,271 @@ impl Default for VideoConfig {
             width: 336,       // CLIP ViT-H/14 input
             height: 336,
             max_frames: Some(10),  // Limit to 10 frames
+            keep_raw_orientation: false,  // Upright output for phone video
+            tone_map: Some(ToneMapOperator::Hable),  // HDR -> sRGB for embeddings
+            crop_detect: false,  // Opt-in (costs one short decode per sample point)
+            dedupe: None,  // Keep every sampled frame
+            resize: ResizeMode::Stretch,
+            placeholders: false,
+        }
+    }
+}
+
+/// FFmpeg `tonemap` operator used when converting HDR frames to SDR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMapOperator {
+    /// Filmic curve, preserves highlight and shadow detail (recommended)
+    Hable,
+    /// Smooth roll-off that keeps in-range colours accurate
+    Mobius,
+    /// Simple Reinhard curve
+    Reinhard,
+    /// Hard clip out-of-range values
+    Clip,
+    /// Linear stretch of the reference range
+    Linear,
+    /// Logarithmic gamma curve
+    Gamma,
+}
+
+impl ToneMapOperator {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ToneMapOperator::Hable => "hable",
+            ToneMapOperator::Mobius => "mobius",
+            ToneMapOperator::Reinhard => "reinhard",
+            ToneMapOperator::Clip => "clip",
+            ToneMapOperator::Linear => "linear",
+            ToneMapOperator::Gamma => "gamma",
         }
     }
+    
+    /// Parse an operator name (as accepted by FFmpeg's `tonemap` filter)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "hable" => Some(ToneMapOperator::Hable),
+            "mobius" => Some(ToneMapOperator::Mobius),
+            "reinhard" => Some(ToneMapOperator::Reinhard),
+            "clip" => Some(ToneMapOperator::Clip),
+            "linear" => Some(ToneMapOperator::Linear),
+            "gamma" => Some(ToneMapOperator::Gamma),
+            _ => None,
+        }
+    }
+}
+
+/// Source stream properties that drive the frame filter chain
+#[derive(Debug, Clone, Default)]
+pub struct VideoSourceInfo {
+    /// Display rotation in degrees clockwise
+    pub rotation: u16,
+    /// Transfer characteristics (e.g. "smpte2084", "arib-std-b67")
+    pub transfer: Option<String>,
+    /// Colour primaries (e.g. "bt2020")
+    pub primaries: Option<String>,
+    /// YUV matrix coefficients (e.g. "bt2020nc")
+    pub matrix: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<f64>,
+    /// Upright (display) width and height
+    pub dimensions: Option<(u32, u32)>,
+}
+
+impl VideoSourceInfo {
+    /// PQ (HDR10, Dolby Vision profile 8.1) or HLG transfer
+    pub fn is_hdr(&self) -> bool {
+        matches!(self.transfer.as_deref(), Some("smpte2084") | Some("arib-std-b67"))
+    }
+}
+
+/// Result of a frame extraction, including the corrections that were applied
+#[derive(Debug, Clone, Default)]
+pub struct FrameExtraction {
+    /// Extracted frame paths in presentation order
+    pub frames: Vec<PathBuf>,
+    /// Clockwise rotation applied to make frames upright
+    pub rotation: u16,
+    /// Tone-mapping operator applied to an HDR source
+    pub tone_mapping: Option<ToneMapOperator>,
+    /// Active picture area frames were cropped to (upright coordinates)
+    pub crop: Option<CropRect>,
+    /// Frames removed as near-duplicates (files are deleted)
+    pub dropped: Vec<DroppedFrame>,
+    /// `ResizeMode::Smart` window of each kept frame, in the frame as scaled
+    /// to cover `width`x`height` before cropping
+    pub smart_crops: Vec<CropRect>,
+    /// Placeholders of each kept frame when `placeholders` is set
+    pub placeholders: Vec<Placeholders>,
+}
+
+/// Output codec for [`VideoPreprocessor::transcode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoCodec {
+    H264,
+    H265,
+    Vp9,
+    Av1,
+}
+
+impl VideoCodec {
+    pub fn as_str(&self) -> &str {
+        match self {
+            VideoCodec::H264 => "h264",
+            VideoCodec::H265 => "h265",
+            VideoCodec::Vp9 => "vp9",
+            VideoCodec::Av1 => "av1",
+        }
+    }
+    
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "h264" | "avc" => Some(VideoCodec::H264),
+            "h265" | "hevc" => Some(VideoCodec::H265),
+            "vp9" => Some(VideoCodec::Vp9),
+            "av1" => Some(VideoCodec::Av1),
+            _ => None,
+        }
+    }
+    
+    /// FFmpeg encoder name
+    pub fn encoder(&self) -> &str {
+        match self {
+            VideoCodec::H264 => "libx264",
+            VideoCodec::H265 => "libx265",
+            VideoCodec::Vp9 => "libvpx-vp9",
+            VideoCodec::Av1 => "libsvtav1",
+        }
+    }
+    
+    /// Audio encoder paired with this codec's container
+    pub fn audio_encoder(&self) -> &str {
+        match self {
+            VideoCodec::Vp9 => "libopus",
+            _ => "aac",
+        }
+    }
+    
+    /// Container file extension
+    pub fn extension(&self) -> &str {
+        match self {
+            VideoCodec::Vp9 => "webm",
+            _ => "mp4",
+        }
+    }
+    
+    /// Highest CRF the encoder accepts
+    pub fn max_crf(&self) -> u8 {
+        match self {
+            VideoCodec::H264 | VideoCodec::H265 => 51,
+            VideoCodec::Vp9 | VideoCodec::Av1 => 63,
+        }
+    }
+}
+
+/// Encoding settings for [`VideoPreprocessor::transcode`]
+///
+/// Orientation, crop detection and HDR tone mapping follow the preprocessor's
+/// [`VideoConfig`]; the source frame rate is kept.
+#[derive(Debug, Clone)]
+pub struct TranscodeConfig {
+    pub codec: VideoCodec,
+    /// Constant rate factor (lower is higher quality)
+    pub crf: u8,
+    /// Encoder preset, None uses the encoder default
+    pub preset: Option<String>,
+    /// Longest side limit in pixels (never upscales)
+    pub max_dimension: Option<u32>,
+    /// Keep the audio track
+    pub audio: bool,
+    /// Logo or text burnt into every frame (FFmpeg `overlay`)
+    pub watermark: Option<Watermark>,
+}
+
+impl Default for TranscodeConfig {
+    fn default() -> Self {
+        Self {
+            codec: VideoCodec::H264,  // Plays everywhere
+            crf: 23,
+            preset: None,
+            max_dimension: Some(1920),
+            audio: true,
+            watermark: None,
+        }
+    }
+}
+
+/// Result of a transcode, including the corrections that were applied
+#[derive(Debug, Clone)]
+pub struct TranscodeReport {
+    pub output: PathBuf,
+    /// Clockwise rotation applied to make the picture upright
+    pub rotation: u16,
+    /// Tone-mapping operator applied to an HDR source
+    pub tone_mapping: Option<ToneMapOperator>,
+    /// Active picture area the video was cropped to
+    pub crop: Option<CropRect>,
+}
+
+/// Similarity measure used for near-duplicate frame suppression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupeMethod {
+    /// 64-bit DCT perceptual hash, distance in differing bits (0-64)
+    PerceptualHash,
+    /// RGB histogram, distance as half the L1 difference (0.0-1.0)
+    Histogram,
+}
+
+impl DedupeMethod {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DedupeMethod::PerceptualHash => "phash",
+            DedupeMethod::Histogram => "histogram",
+        }
+    }
+    
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "phash" => Some(DedupeMethod::PerceptualHash),
+            "histogram" => Some(DedupeMethod::Histogram),
+            _ => None,
+        }
+    }
+    
+    /// Distance at or below which frames count as duplicates
+    pub fn default_threshold(&self) -> f64 {
+        match self {
+            DedupeMethod::PerceptualHash => 6.0,
+            DedupeMethod::Histogram => 0.05,
+        }
+    }
+}
+
+/// Near-duplicate suppression settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameDedupe {
+    pub method: DedupeMethod,
+    /// Maximum distance to the last kept frame for a frame to be dropped
+    pub threshold: f64,
+}
+
+impl FrameDedupe {
+    /// Use the method's default threshold
+    pub fn new(method: DedupeMethod) -> Self {
+        Self { method, threshold: method.default_threshold() }
+    }
+}
+
+/// Frame removed by near-duplicate suppression
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DroppedFrame {
+    /// Path the frame was written to before removal
+    pub frame: PathBuf,
+    /// Kept frame it duplicated
+    pub duplicate_of: PathBuf,
+    /// Distance to the kept frame (units depend on the method)
+    pub distance: f64,
+    /// Method that flagged the frame ("phash" or "histogram")
+    pub method: String,
 }
 
 pub struct VideoFrame {
@@ -37,18 +326,64 @@ impl VideoPreprocessor {
         Self { config }
     }
     
+    /// Probe rotation and colour properties of the first video stream
+    pub fn probe(&self, video: impl AsRef<Path>) -> VideoSourceInfo {
+        let meta = match crate::metadata::extract_with_ffprobe(video.as_ref()) {
+            Ok(meta) => meta,
+            Err(_) => return VideoSourceInfo::default(),
+        };
+        let color = meta.color.unwrap_or_default();
+        
+        VideoSourceInfo {
+            rotation: meta.rotation.unwrap_or(0),
+            transfer: color.transfer_characteristics,
+            primaries: color.color_primaries,
+            matrix: color.color_space,
+            duration: meta.duration,
+            dimensions: meta.dimensions.map(|d| (d.width, d.height)),
+        }
+    }
+    
+    /// Rotation (degrees clockwise) applied to make output upright
+    ///
+    /// Returns 0 when `keep_raw_orientation` is set or the stream carries no
+    /// rotate tag / display matrix.
+    pub fn output_rotation(&self, video: impl AsRef<Path>) -> u16 {
+        if self.config.keep_raw_orientation {
+            return 0;
+        }
+        self.probe(video).rotation
+    }
+    
     /// Extract frames from video and save to directory
     pub fn extract_frames(&self, video: impl AsRef<Path>, output_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, FfmpegError> {
+        self.extract_frames_with_report(video, output_dir)
+            .map(|extraction| extraction.frames)
+    }
+    
+    /// Extract frames and report the orientation / tone-mapping / crop corrections applied
+    pub fn extract_frames_with_report(&self, video: impl AsRef<Path>, output_dir: impl AsRef<Path>) -> Result<FrameExtraction, FfmpegError> {
         let output_pattern = output_dir.as_ref().join("frame_%04d.jpg");
+        let source = self.probe(video.as_ref());
+        
+        let rotation = if self.config.keep_raw_orientation { 0 } else { source.rotation };
+        let tone_mapping = self.tone_mapping_for(&source);
+        let crop = if self.config.crop_detect {
+            self.detect_crop_with_source(video.as_ref(), &source)?
+        } else {
+            None
+        };
         
+        let mut filters = self.frame_filters(&source, crop, Some(self.config.fps), tone_mapping);
+        filters.push(self.scale_filter());
+        
+        // FFmpeg's autorotate behaviour differs between versions, so rotation
+        // is always disabled on input and applied explicitly
         let mut cmd = FfmpegCommand::new()
+            .args(&["-noautorotate"])
             .input(video)
             .args(&[
-                "-vf", &format!("fps={},scale={}:{}", 
-                    self.config.fps, 
-                    self.config.width, 
-                    self.config.height
-                ),
+                "-vf", &filters.join(","),
                 "-f", "image2",
             ]);
         
@@ -69,7 +404,300 @@ impl VideoPreprocessor {
             }
         }
         
-        Ok(frames)
+        let dropped = match self.config.dedupe {
+            Some(dedupe) => suppress_duplicates(&mut frames, dedupe)?,
+            None => Vec::new(),
+        };
+        
+        // Smart crop windows and placeholders depend on each frame's content, so
+        // they can't be FFmpeg filters; both come out of one decode per frame
+        let mut smart_crops = Vec::new();
+        let mut placeholders = Vec::new();
+        if self.config.resize == ResizeMode::Smart || self.config.placeholders {
+            for frame in &frames {
+                let mut image = open_frame(frame)?;
+                if self.config.resize == ResizeMode::Smart {
+                    let (cropped, rect) = self.smart_crop_frame(&image, frame)?;
+                    image = cropped;
+                    smart_crops.push(rect);
+                }
+                if self.config.placeholders {
+                    placeholders.push(placeholder::placeholders(&image));
+                }
+            }
+        }
+        
+        Ok(FrameExtraction {
+            frames,
+            rotation,
+            tone_mapping,
+            crop,
+            dropped,
+            smart_crops,
+            placeholders,
+        })
+    }
+    
+    /// Scale filter for `resize`; `Smart` scales to cover and crops afterwards
+    fn scale_filter(&self) -> String {
+        let (width, height) = (self.config.width, self.config.height);
+        match self.config.resize {
+            ResizeMode::Stretch => format!("scale={}:{}", width, height),
+            ResizeMode::Fit => format!(
+                "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2",
+                w = width,
+                h = height
+            ),
+            ResizeMode::Fill => format!("scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h}", w = width, h = height),
+            ResizeMode::Smart => format!("scale={}:{}:force_original_aspect_ratio=increase", width, height),
+        }
+    }
+    
+    /// Crop an extracted (cover-scaled) frame in place around its most salient region
+    fn smart_crop_frame(&self, image: &DynamicImage, frame: &Path) -> Result<(DynamicImage, CropRect), FfmpegError> {
+        let (cropped, rect) = crate::smartcrop::smart_crop(image, self.config.width, self.config.height, &[]);
+        
+        let file = std::io::BufWriter::new(std::fs::File::create(frame)?);
+        cropped.to_rgb8()
+            .write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(file, 95))
+            .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to write {}: {}", frame.display(), e)))?;
+        Ok((cropped, rect))
+    }
+    
+    /// Re-encode a video, applying orientation, crop and tone-mapping corrections
+    pub fn transcode(&self, video: impl AsRef<Path>, output: impl AsRef<Path>, options: &TranscodeConfig) -> Result<TranscodeReport, FfmpegError> {
+        let source = self.probe(video.as_ref());
+        
+        let rotation = if self.config.keep_raw_orientation { 0 } else { source.rotation };
+        let tone_mapping = self.tone_mapping_for(&source);
+        let crop = if self.config.crop_detect {
+            self.detect_crop_with_source(video.as_ref(), &source)?
+        } else {
+            None
+        };
+        
+        let filters = self.frame_filters(&source, crop, None, tone_mapping);
+        let mut output_filters = Vec::new();
+        if let Some(max) = options.max_dimension {
+            output_filters.push(format!(
+                "scale=w='min({max},iw)':h='min({max},ih)':force_original_aspect_ratio=decrease:force_divisible_by=2"
+            ));
+        }
+        output_filters.push("format=yuv420p".to_string());
+        
+        let mut cmd = FfmpegCommand::new()
+            .args(&["-y", "-noautorotate"])
+            .input(video.as_ref());
+        
+        // The overlay is drawn at the corrected frame size, before scaling
+        let overlay = match &options.watermark {
+            Some(mark) => {
+                let (width, height) = match (crop, source.dimensions) {
+                    (Some(rect), _) => (rect.width, rect.height),
+                    (None, Some((width, height))) if rotation % 180 == 90 => (height, width),
+                    (None, Some(dimensions)) => dimensions,
+                    (None, None) => return Err(FfmpegError::InvalidOutput(format!(
+                        "Cannot watermark {}: frame size unknown", video.as_ref().display()
+                    ))),
+                };
+                Some(mark.render()?.overlay_file(width, height)?)
+            }
+            None => None,
+        };
+        cmd = match &overlay {
+            Some(overlay) => cmd
+                .input(overlay.path())
+                .args(&[
+                    "-filter_complex", &watermark::overlay_graph(&filters, &output_filters),
+                    "-map", "[v]",
+                    "-map", "0:a?",
+                ]),
+            None => {
+                let filters: Vec<String> = filters.into_iter().chain(output_filters).collect();
+                cmd.args(&["-vf", &filters.join(",")])
+            }
+        };
+        
+        let crf = options.crf.to_string();
+        cmd = cmd.args(&["-c:v", options.codec.encoder(), "-crf", &crf]);
+        
+        if options.codec == VideoCodec::Vp9 {
+            cmd = cmd.args(&["-b:v", "0"]);  // Constant quality mode
+        }
+        if let Some(preset) = &options.preset {
+            cmd = cmd.args(&["-preset", preset]);
+        }
+        cmd = if options.audio {
+            cmd.args(&["-c:a", options.codec.audio_encoder()])
+        } else {
+            cmd.args(&["-an"])
+        };
+        if options.codec.extension() == "mp4" {
+            cmd = cmd.args(&["-movflags", "+faststart"]);
+        }
+        
+        cmd.output(output.as_ref()).execute()?;
+        
+        Ok(TranscodeReport {
+            output: output.as_ref().to_path_buf(),
+            rotation,
+            tone_mapping,
+            crop,
+        })
+    }
+    
+    /// Extract frames from many videos with at most `max_concurrent` running at once
+    ///
+    /// Each video's frames go to `output_root/<file stem>` (suffixed with the
+    /// input index when stems collide). Returns (index, result) tuples in input
+    /// order; one failing file does not stop the batch.
+    pub fn batch_extract_frames<P: AsRef<Path> + Sync>(
+        &self,
+        videos: &[P],
+        output_root: impl AsRef<Path>,
+        max_concurrent: usize,
+    ) -> Vec<(usize, Result<FrameExtraction, FfmpegError>)> {
+        let names = batch_output_names(videos);
+        let output_root = output_root.as_ref();
+        
+        run_bounded(videos.len(), max_concurrent, |i| {
+            let out_dir = output_root.join(&names[i]);
+            std::fs::create_dir_all(&out_dir)?;
+            self.extract_frames_with_report(videos[i].as_ref(), &out_dir)
+        })
+    }
+    
+    /// Transcode many videos with at most `max_concurrent` encoders running at once
+    ///
+    /// Outputs are written to `output_root/<file stem>.<ext>`. Returns (index,
+    /// result) tuples in input order.
+    pub fn batch_transcode<P: AsRef<Path> + Sync>(
+        &self,
+        videos: &[P],
+        output_root: impl AsRef<Path>,
+        options: &TranscodeConfig,
+        max_concurrent: usize,
+    ) -> Vec<(usize, Result<TranscodeReport, FfmpegError>)> {
+        let names = batch_output_names(videos);
+        let output_root = output_root.as_ref();
+        
+        run_bounded(videos.len(), max_concurrent, |i| {
+            std::fs::create_dir_all(output_root)?;
+            let output = output_root.join(format!("{}.{}", names[i], options.codec.extension()));
+            self.transcode(videos[i].as_ref(), output, options)
+        })
+    }
+    
+    /// Filters shared by every output path: orientation, crop, frame rate and
+    /// tone mapping (scaling is left to the caller, `fps` None keeps the source rate)
+    fn frame_filters(
+        &self,
+        source: &VideoSourceInfo,
+        crop: Option<CropRect>,
+        fps: Option<u8>,
+        tone_mapping: Option<ToneMapOperator>,
+    ) -> Vec<String> {
+        let rotation = if self.config.keep_raw_orientation { 0 } else { source.rotation };
+        
+        let mut filters = Vec::new();
+        if let Some(rotate) = rotation_filter(rotation) {
+            filters.push(rotate.to_string());
+        }
+        if let Some(rect) = crop {
+            filters.push(rect.to_filter());
+        }
+        if let Some(fps) = fps {
+            filters.push(format!("fps={}", fps));
+        }
+        if let Some(operator) = tone_mapping {
+            filters.push(tone_map_filter(source, operator));
+        }
+        filters
+    }
+    
+    /// Detect letterbox/pillarbox bars, aggregated over several sample points
+    ///
+    /// Runs `cropdetect` on a few frames at evenly spaced points and returns the
+    /// union of the detected areas, so dark scenes cannot shrink the crop.
+    /// Returns None when no bars were found. Coordinates are in upright
+    /// orientation unless `keep_raw_orientation` is set.
+    pub fn detect_crop(&self, video: impl AsRef<Path>) -> Result<Option<CropRect>, FfmpegError> {
+        let source = self.probe(video.as_ref());
+        self.detect_crop_with_source(video.as_ref(), &source)
+    }
+    
+    fn detect_crop_with_source(&self, video: &Path, source: &VideoSourceInfo) -> Result<Option<CropRect>, FfmpegError> {
+        let rotation = if self.config.keep_raw_orientation { 0 } else { source.rotation };
+        
+        let mut filters = Vec::new();
+        if let Some(rotate) = rotation_filter(rotation) {
+            filters.push(rotate.to_string());
+        }
+        filters.push(format!("cropdetect=limit={}:round=2:reset=0", CROP_LIMIT));
+        let filter = filters.join(",");
+        
+        // Sample the middle of N equal segments (skips fades at the very start/end)
+        let offsets: Vec<f64> = match source.duration {
+            Some(duration) if duration > 0.0 => (0..CROP_SAMPLE_POINTS)
+                .map(|i| duration * (i as f64 + 0.5) / CROP_SAMPLE_POINTS as f64)
+                .collect(),
+            _ => vec![0.0],
+        };
+        
+        let mut detected: Option<CropRect> = None;
+        for offset in offsets {
+            let output = FfmpegCommand::new()
+                .args(&["-ss", &format!("{:.3}", offset), "-noautorotate"])
+                .input(video)
+                .args(&[
+                    "-vf", &filter,
+                    "-frames:v", &CROP_FRAMES_PER_SAMPLE.to_string(),
+                    "-f", "null",
+                ])
+                .output("-")
+                .execute()?;
+            
+            if let Some(rect) = parse_cropdetect(&String::from_utf8_lossy(&output.stderr)) {
+                detected = Some(match detected {
+                    Some(acc) => acc.union(&rect),
+                    None => rect,
+                });
+            }
+        }
+        
+        // A crop covering the whole picture is no crop at all
+        let rect = match detected {
+            Some(rect) => rect,
+            None => return Ok(None),
+        };
+        if let Some((width, height)) = source.dimensions {
+            let (width, height) = if rotation == 0 && source.rotation % 180 == 90 {
+                (height, width)
+            } else {
+                (width, height)
+            };
+            if rect.width >= width && rect.height >= height {
+                return Ok(None);
+            }
+        }
+        
+        Ok(Some(rect))
+    }
+    
+    /// Tone-mapping operator to apply for a source, if any
+    ///
+    /// Requires an FFmpeg build with `zscale` (libzimg); without it HDR frames
+    /// are extracted unmapped and a warning is logged.
+    fn tone_mapping_for(&self, source: &VideoSourceInfo) -> Option<ToneMapOperator> {
+        let operator = self.config.tone_map?;
+        if !source.is_hdr() {
+            return None;
+        }
+        if !crate::ffmpeg::has_filter("zscale") {
+            tracing::warn!("HDR source detected but FFmpeg lacks zscale (libzimg), skipping tone mapping");
+            return None;
+        }
+        Some(operator)
     }
     
     /// Load extracted frames as VideoFrame objects
@@ -80,13 +708,304 @@ impl VideoPreprocessor {
             let image = image::open(path)
                 .map_err(|e| FfmpegError::InvalidOutput(e.to_string()))?;
             
+            // Sample index from the file name survives dedupe gaps
+            let frame_number = frame_index(path).unwrap_or(idx);
+            
             frames.push(VideoFrame {
                 image,
-                timestamp_ms: (idx as u64 * 1000) / self.config.fps as u64,
-                frame_number: idx,
+                timestamp_ms: (frame_number as u64 * 1000) / self.config.fps as u64,
+                frame_number,
             });
         }
         
         Ok(frames)
     }
 }
+
+/// Remove frames that are within `threshold` of the last kept frame
+///
+/// Dropped files are deleted; kept frames retain their original names so
+/// their sample index (and timestamp) is preserved.
+pub(crate) fn suppress_duplicates(frames: &mut Vec<PathBuf>, dedupe: FrameDedupe) -> Result<Vec<DroppedFrame>, FfmpegError> {
+    let mut kept: Vec<PathBuf> = Vec::with_capacity(frames.len());
+    let mut dropped = Vec::new();
+    let mut last: Option<FrameSignature> = None;
+    
+    for path in frames.drain(..) {
+        let image = image::open(&path)
+            .map_err(|e| FfmpegError::InvalidOutput(e.to_string()))?;
+        let signature = FrameSignature::compute(&image, dedupe.method);
+        
+        if let (Some(previous), Some(kept_path)) = (&last, kept.last()) {
+            let distance = previous.distance(&signature);
+            if distance <= dedupe.threshold {
+                std::fs::remove_file(&path)?;
+                dropped.push(DroppedFrame {
+                    frame: path,
+                    duplicate_of: kept_path.clone(),
+                    distance,
+                    method: dedupe.method.as_str().to_string(),
+                });
+                continue;
+            }
+        }
+        
+        last = Some(signature);
+        kept.push(path);
+    }
+    
+    *frames = kept;
+    Ok(dropped)
+}
+
+fn open_frame(frame: &Path) -> Result<DynamicImage, FfmpegError> {
+    image::open(frame)
+        .map_err(|e| FfmpegError::InvalidOutput(format!("Failed to read {}: {}", frame.display(), e)))
+}
+
+/// BlurHash/ThumbHash of each extracted frame file
+pub(crate) fn frame_placeholders(frames: &[PathBuf]) -> Result<Vec<Placeholders>, FfmpegError> {
+    use rayon::prelude::*;
+    
+    frames
+        .par_iter()
+        .map(|frame| open_frame(frame).map(|image| placeholder::placeholders(&image)))
+        .collect()
+}
+
+/// Default batch concurrency: half the available cores (FFmpeg is itself multi-threaded)
+pub fn default_batch_concurrency() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| (n.get() / 2).max(1))
+        .unwrap_or(2)
+}
+
+/// Run `job` for indices `0..count` on a pool of `max_concurrent` threads,
+/// returning results in index order
+fn run_bounded<T, F>(count: usize, max_concurrent: usize, job: F) -> Vec<(usize, Result<T, FfmpegError>)>
+where
+    T: Send,
+    F: Fn(usize) -> Result<T, FfmpegError> + Sync + Send,
+{
+    use rayon::prelude::*;
+    
+    match rayon::ThreadPoolBuilder::new().num_threads(max_concurrent.max(1)).build() {
+        Ok(pool) => pool.install(|| (0..count).into_par_iter().map(|i| (i, job(i))).collect()),
+        Err(e) => {
+            tracing::warn!("Failed to build batch thread pool ({}), running sequentially", e);
+            (0..count).map(|i| (i, job(i))).collect()
+        }
+    }
+}
+
+/// Per-input output names: the file stem, suffixed with the index on collision
+/// (and further suffixed until no other input's name matches)
+pub(crate) fn batch_output_names<P: AsRef<Path>>(videos: &[P]) -> Vec<String> {
+    let stems: Vec<String> = videos
+        .iter()
+        .map(|v| {
+            v.as_ref()
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "video".to_string())
+        })
+        .collect();
+    
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for stem in &stems {
+        *counts.entry(stem.as_str()).or_insert(0) += 1;
+    }
+    
+    let mut taken = HashSet::new();
+    stems
+        .iter()
+        .enumerate()
+        .map(|(i, stem)| {
+            let base = if counts[stem.as_str()] > 1 {
+                format!("{}_{}", stem, i)
+            } else {
+                stem.clone()
+            };
+            let mut name = base.clone();
+            let mut suffix = 1;
+            while !taken.insert(name.clone()) {
+                name = format!("{}_{}", base, suffix);
+                suffix += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+/// Per-frame signature compared during dedupe
+enum FrameSignature {
+    Hash(u64),
+    Histogram(Vec<f64>),
+}
+
+impl FrameSignature {
+    fn compute(image: &DynamicImage, method: DedupeMethod) -> Self {
+        match method {
+            DedupeMethod::PerceptualHash => FrameSignature::Hash(crate::phash::phash(image)),
+            DedupeMethod::Histogram => FrameSignature::Histogram(rgb_histogram(image)),
+        }
+    }
+    
+    fn distance(&self, other: &FrameSignature) -> f64 {
+        match (self, other) {
+            (FrameSignature::Hash(a), FrameSignature::Hash(b)) => crate::phash::hamming_distance(*a, *b) as f64,
+            (FrameSignature::Histogram(a), FrameSignature::Histogram(b)) => histogram_distance(a, b),
+            _ => f64::MAX,
+        }
+    }
+}
+
+/// Bins per channel in the dedupe histogram
+const HISTOGRAM_BINS: usize = 16;
+
+/// Normalised RGB histogram (16 bins per channel, each channel sums to 1)
+pub(crate) fn rgb_histogram(image: &DynamicImage) -> Vec<f64> {
+    let rgb = image.to_rgb8();
+    let mut bins = vec![0.0; HISTOGRAM_BINS * 3];
+    
+    for pixel in rgb.pixels() {
+        for (channel, &value) in pixel.0.iter().enumerate() {
+            bins[channel * HISTOGRAM_BINS + value as usize * HISTOGRAM_BINS / 256] += 1.0;
+        }
+    }
+    
+    let count = (rgb.width() * rgb.height()).max(1) as f64;
+    bins.iter_mut().for_each(|b| *b /= count);
+    bins
+}
+
+/// Half the L1 distance between two histograms, averaged over channels (0.0-1.0)
+pub(crate) fn histogram_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).abs()).sum::<f64>() / 6.0
+}
+
+/// Sample index from a `frame_NNNN.jpg` file name (zero-based)
+fn frame_index(path: &Path) -> Option<usize> {
+    let stem = path.file_stem()?.to_str()?;
+    let number: usize = stem.strip_prefix("frame_")?.parse().ok()?;
+    number.checked_sub(1)
+}
+
+/// FFmpeg filter that turns a frame rotated by `rotation` degrees clockwise upright
+pub(crate) fn rotation_filter(rotation: u16) -> Option<&'static str> {
+    match rotation {
+        90 => Some("transpose=clock"),
+        180 => Some("hflip,vflip"),
+        270 => Some("transpose=cclock"),
+        _ => None,
+    }
+}
+
+/// Parse the last `crop=W:H:X:Y` reported by FFmpeg's `cropdetect` filter
+///
+/// Entirely black frames produce negative sizes and are ignored.
+pub(crate) fn parse_cropdetect(stderr: &str) -> Option<CropRect> {
+    stderr.lines().rev().find_map(|line| {
+        let spec = line.split("crop=").nth(1)?.split_whitespace().next()?;
+        let values: Vec<i64> = spec.split(':').filter_map(|v| v.parse().ok()).collect();
+        match values.as_slice() {
+            [w, h, x, y] if *w > 0 && *h > 0 && *x >= 0 && *y >= 0 => Some(CropRect {
+                x: *x as u32,
+                y: *y as u32,
+                width: *w as u32,
+                height: *h as u32,
+            }),
+            _ => None,
+        }
+    })
+}
+
+/// zscale/tonemap chain converting a BT.2020 PQ/HLG source to sRGB
+///
+/// Input transfer, primaries and matrix are passed explicitly because many
+/// phone files only signal them in the bitstream, not the container.
+pub(crate) fn tone_map_filter(source: &VideoSourceInfo, operator: ToneMapOperator) -> String {
+    let transfer = source.transfer.as_deref().unwrap_or("smpte2084");
+    let primaries = source.primaries.as_deref().unwrap_or("bt2020");
+    let matrix = source.matrix.as_deref().unwrap_or("bt2020nc");
+    
+    format!(
+        "zscale=tin={}:pin={}:min={}:t=linear:npl=100,format=gbrpf32le,\
+         zscale=p=bt709,tonemap=tonemap={}:desat=0,\
+         zscale=t=iec61966-2-1:m=bt709:r=pc,format=rgb24",
+        transfer, primaries, matrix, operator.as_str()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_rotation_filter() {
+        assert_eq!(rotation_filter(0), None);
+        assert_eq!(rotation_filter(90), Some("transpose=clock"));
+        assert_eq!(rotation_filter(180), Some("hflip,vflip"));
+        assert_eq!(rotation_filter(270), Some("transpose=cclock"));
+    }
+    
+    #[test]
+    fn test_tone_map_filter() {
+        let hlg = VideoSourceInfo {
+            transfer: Some("arib-std-b67".to_string()),
+            primaries: Some("bt2020".to_string()),
+            ..Default::default()
+        };
+        assert!(hlg.is_hdr());
+        assert!(!VideoSourceInfo::default().is_hdr());
+        
+        let filter = tone_map_filter(&hlg, ToneMapOperator::Mobius);
+        assert!(filter.starts_with("zscale=tin=arib-std-b67:pin=bt2020:min=bt2020nc"));
+        assert!(filter.contains("tonemap=tonemap=mobius"));
+        assert!(filter.ends_with("format=rgb24"));
+        
+        assert_eq!(ToneMapOperator::parse("Hable"), Some(ToneMapOperator::Hable));
+        assert_eq!(ToneMapOperator::parse("none"), None);
+    }
+    
+    #[test]
+    fn test_histogram_distance() {
+        let dark = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(16, 16, image::Rgb([10, 10, 10])));
+        let light = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(16, 16, image::Rgb([240, 240, 240])));
+        
+        assert_eq!(histogram_distance(&rgb_histogram(&dark), &rgb_histogram(&dark)), 0.0);
+        assert!((histogram_distance(&rgb_histogram(&dark), &rgb_histogram(&light)) - 1.0).abs() < 1e-9);
+        assert_eq!(frame_index(Path::new("/tmp/frame_0003.jpg")), Some(2));
+    }
+    
+    #[test]
+    fn test_batch_output_names() {
+        let names = batch_output_names(&["/a/clip.mp4", "/b/clip.mov", "/c/other.mp4"]);
+        assert_eq!(names, vec!["clip_0", "clip_1", "other"]);
+        
+        // An input already named like a suffixed duplicate
+        let names = batch_output_names(&["/a/clip.mp4", "/b/clip.mov", "/c/clip_1.mp4"]);
+        assert_eq!(names, vec!["clip_0", "clip_1", "clip_1_1"]);
+    }
+    
+    #[test]
+    fn test_parse_cropdetect() {
+        let stderr = "\
+[Parsed_cropdetect_0 @ 0x55] x1:0 x2:1919 y1:140 y2:939 w:1920 h:800 x:0 y:140 pts:0 t:0.000000 limit:0.094118 crop=1920:800:0:140
+[Parsed_cropdetect_0 @ 0x55] x1:0 x2:1919 y1:138 y2:941 w:1920 h:804 x:0 y:138 pts:1 t:0.040000 limit:0.094118 crop=1920:804:0:138
+";
+        assert_eq!(
+            parse_cropdetect(stderr),
+            Some(CropRect { x: 0, y: 138, width: 1920, height: 804 })
+        );
+        
+        // Black frame
+        assert_eq!(parse_cropdetect("limit:0.094118 crop=-1904:-1072:1912:1080"), None);
+        assert_eq!(parse_cropdetect("no detections"), None);
+        
+        let a = CropRect { x: 0, y: 140, width: 1920, height: 800 };
+        let b = CropRect { x: 0, y: 130, width: 1920, height: 790 };
+        assert_eq!(a.union(&b), CropRect { x: 0, y: 130, width: 1920, height: 810 });
+        assert_eq!(a.to_filter(), "crop=1920:800:0:140");
+    }
+}
@@ -0,0 +1,536 @@
+//! Watermark and overlay compositing
+//!
+//! A [`Watermark`] is a PNG/SVG logo or a line of text placed on derivative
+//! outputs, so client proofs are marked while they are written rather than in
+//! a second pass over compressed files. The mark is rendered once
+//! ([`Watermark::render`]) and then scaled to each output:
+//!
+//! - PNG (and any format `image` decodes) is loaded directly
+//! - SVG is rasterised by FFmpeg (needs a build with librsvg)
+//! - Text is drawn by FFmpeg `drawtext` (fontconfig default font unless
+//!   `font` is given) and trimmed to its ink
+//!
+//! Stills are composited in process after resizing; videos get a full-frame
+//! overlay PNG composited by FFmpeg's `overlay` filter.
+
+use crate::ffmpeg::{temp_path, FfmpegCommand, FfmpegError};
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use std::path::{Path, PathBuf};
+
+/// Font size text marks are rendered at before scaling to the output
+const TEXT_RENDER_SIZE: u32 = 160;
+
+/// Width SVG marks are rasterised at before scaling to the output
+const SVG_RENDER_WIDTH: u32 = 2048;
+
+/// What is composited onto the output
+#[derive(Debug, Clone)]
+pub enum WatermarkSource {
+    /// Logo file: PNG (or anything `image` decodes) or SVG
+    Image(PathBuf),
+    /// Single line of text
+    Text {
+        text: String,
+        /// Fill colour (RGB)
+        color: [u8; 3],
+        /// TrueType/OpenType file, None uses the fontconfig default
+        font: Option<PathBuf>,
+    },
+}
+
+/// Where a single (untiled) mark is placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WatermarkAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    #[default]
+    BottomRight,
+}
+
+impl WatermarkAnchor {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace('_', "-").as_str() {
+            "top-left" => Some(Self::TopLeft),
+            "top" => Some(Self::Top),
+            "top-right" => Some(Self::TopRight),
+            "left" => Some(Self::Left),
+            "center" | "centre" => Some(Self::Center),
+            "right" => Some(Self::Right),
+            "bottom-left" => Some(Self::BottomLeft),
+            "bottom" => Some(Self::Bottom),
+            "bottom-right" => Some(Self::BottomRight),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::TopLeft => "top-left",
+            Self::Top => "top",
+            Self::TopRight => "top-right",
+            Self::Left => "left",
+            Self::Center => "center",
+            Self::Right => "right",
+            Self::BottomLeft => "bottom-left",
+            Self::Bottom => "bottom",
+            Self::BottomRight => "bottom-right",
+        }
+    }
+    
+    /// Horizontal and vertical alignment: 0 start, 1 centre, 2 end
+    fn alignment(&self) -> (u8, u8) {
+        match self {
+            Self::TopLeft => (0, 0),
+            Self::Top => (1, 0),
+            Self::TopRight => (2, 0),
+            Self::Left => (0, 1),
+            Self::Center => (1, 1),
+            Self::Right => (2, 1),
+            Self::BottomLeft => (0, 2),
+            Self::Bottom => (1, 2),
+            Self::BottomRight => (2, 2),
+        }
+    }
+}
+
+/// Overlay placed on every output of a stage
+///
+/// Sizes are relative to each output so one watermark suits every variant:
+/// `scale` is the mark width as a fraction of the output width (shrunk if the
+/// mark would not fit), `margin` a fraction of the output's shorter side.
+#[derive(Debug, Clone)]
+pub struct Watermark {
+    pub source: WatermarkSource,
+    /// Ignored when `tile` is set
+    pub anchor: WatermarkAnchor,
+    /// Distance from the anchored edges, and the gap between tiles
+    pub margin: f32,
+    /// 0 (invisible) to 1 (opaque), applied on top of the mark's own alpha
+    pub opacity: f32,
+    pub scale: f32,
+    /// Repeat the mark over the whole output in staggered rows
+    pub tile: bool,
+}
+
+impl Watermark {
+    /// Logo watermark with default placement
+    pub fn image(path: impl Into<PathBuf>) -> Self {
+        Self::with_source(WatermarkSource::Image(path.into()))
+    }
+    
+    /// White text watermark with default placement
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::with_source(WatermarkSource::Text { text: text.into(), color: [255, 255, 255], font: None })
+    }
+    
+    fn with_source(source: WatermarkSource) -> Self {
+        Self {
+            source,
+            anchor: WatermarkAnchor::BottomRight,
+            margin: 0.03,
+            opacity: 0.5,
+            scale: 0.2,
+            tile: false,
+        }
+    }
+    
+    /// Rasterise the mark once, ready to be composited onto any number of outputs
+    pub fn render(&self) -> Result<RenderedWatermark, FfmpegError> {
+        let mark = match &self.source {
+            WatermarkSource::Image(path) if is_svg(path) => render_svg(path)?,
+            WatermarkSource::Image(path) => image::open(path)
+                .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to load watermark {}: {}", path.display(), e)))?
+                .into_rgba8(),
+            WatermarkSource::Text { text, color, font } => render_text(text, *color, font.as_deref())?,
+        };
+        if mark.width() == 0 || mark.height() == 0 {
+            return Err(FfmpegError::InvalidOutput("Watermark rendered empty".to_string()));
+        }
+        
+        Ok(RenderedWatermark {
+            mark,
+            anchor: self.anchor,
+            margin: self.margin.max(0.0),
+            opacity: self.opacity.clamp(0.0, 1.0),
+            scale: self.scale.clamp(0.001, 1.0),
+            tile: self.tile,
+        })
+    }
+}
+
+/// A rasterised [`Watermark`]
+#[derive(Debug, Clone)]
+pub struct RenderedWatermark {
+    mark: RgbaImage,
+    anchor: WatermarkAnchor,
+    margin: f32,
+    opacity: f32,
+    scale: f32,
+    tile: bool,
+}
+
+impl RenderedWatermark {
+    /// Composite onto a decoded image
+    pub fn apply(&self, image: &mut DynamicImage) -> Result<(), FfmpegError> {
+        let (width, height) = (image.width(), image.height());
+        match image {
+            DynamicImage::ImageRgb8(rgb) => self.apply_buffer(rgb, width, height, 3),
+            DynamicImage::ImageRgba8(rgba) => self.apply_buffer(rgba, width, height, 4),
+            other => {
+                let mut rgba = other.to_rgba8();
+                self.apply_buffer(&mut rgba, width, height, 4)?;
+                *other = DynamicImage::ImageRgba8(rgba);
+                Ok(())
+            }
+        }
+    }
+    
+    /// Composite onto an interleaved 8-bit RGB (`channels` 3) or RGBA (4) buffer
+    pub(crate) fn apply_buffer(&self, pixels: &mut [u8], width: u32, height: u32, channels: usize) -> Result<(), FfmpegError> {
+        let (mark_width, mark_height, positions) = self.placements(width, height);
+        let mark = self.scaled_mark(mark_width, mark_height)?;
+        for (x, y) in positions {
+            blend(pixels, width, height, channels, &mark, mark_width, mark_height, x, y, self.opacity);
+        }
+        Ok(())
+    }
+    
+    /// Composite onto interleaved 16-bit RGB samples (16-bit RAW conversions)
+    pub(crate) fn apply_buffer_u16(&self, pixels: &mut [u16], width: u32, height: u32) -> Result<(), FfmpegError> {
+        let (mark_width, mark_height, positions) = self.placements(width, height);
+        let mark = self.scaled_mark(mark_width, mark_height)?;
+        for (x, y) in positions {
+            blend_u16(pixels, width, height, &mark, mark_width, mark_height, x, y, self.opacity);
+        }
+        Ok(())
+    }
+    
+    /// RGBA mark resized to `mark_width`x`mark_height`
+    fn scaled_mark(&self, mark_width: u32, mark_height: u32) -> Result<Vec<u8>, FfmpegError> {
+        crate::image::resize_buffer(
+            self.mark.as_raw(),
+            self.mark.width(),
+            self.mark.height(),
+            fast_image_resize::PixelType::U8x4,
+            mark_width,
+            mark_height,
+            None,
+        )
+    }
+    
+    /// Write the composited mark on a transparent `width`x`height` canvas, for
+    /// FFmpeg to overlay at 0:0 (removed when the returned file is dropped)
+    pub(crate) fn overlay_file(&self, width: u32, height: u32) -> Result<OverlayFile, FfmpegError> {
+        let mut canvas = RgbaImage::new(width, height);
+        self.apply_buffer(&mut canvas, width, height, 4)?;
+        
+        let file = OverlayFile(temp_path("watermark", "png"));
+        canvas
+            .save_with_format(&file.0, ImageFormat::Png)
+            .map_err(|e| FfmpegError::ExecutionFailed(format!("Failed to write watermark overlay: {}", e)))?;
+        Ok(file)
+    }
+    
+    /// Scaled mark size and the top-left corner of every copy on a `width`x`height` output
+    fn placements(&self, width: u32, height: u32) -> (u32, u32, Vec<(i64, i64)>) {
+        let margin = (self.margin * width.min(height) as f32).round() as i64;
+        let aspect = self.mark.height() as f32 / self.mark.width() as f32;
+        
+        // Shrink to fit inside the margins on both axes
+        let fit_width = (width as i64 - 2 * margin).max(1) as f32;
+        let fit_height = (height as i64 - 2 * margin).max(1) as f32;
+        let mark_width = (self.scale * width as f32).min(fit_width).min(fit_height / aspect);
+        let mark_height = ((mark_width * aspect).round() as u32).max(1);
+        let mark_width = (mark_width.round() as u32).max(1);
+        let (w, h) = (mark_width as i64, mark_height as i64);
+        
+        if !self.tile {
+            let place = |align: u8, size: i64, extent: u32| match align {
+                0 => margin,
+                1 => (extent as i64 - size) / 2,
+                _ => extent as i64 - size - margin,
+            };
+            let (horizontal, vertical) = self.anchor.alignment();
+            return (mark_width, mark_height, vec![(place(horizontal, w, width), place(vertical, h, height))]);
+        }
+        
+        // Staggered rows; odd rows start half a step to the left, copies are clipped at the edges
+        let (step_x, step_y) = (w + margin.max(1), h + margin.max(1));
+        let mut positions = Vec::new();
+        let mut y = margin;
+        let mut row = 0;
+        while y < height as i64 {
+            let mut x = if row % 2 == 1 { margin - step_x / 2 } else { margin };
+            while x < width as i64 {
+                positions.push((x, y));
+                x += step_x;
+            }
+            y += step_y;
+            row += 1;
+        }
+        (mark_width, mark_height, positions)
+    }
+}
+
+/// Temporary overlay PNG, deleted on drop
+pub(crate) struct OverlayFile(PathBuf);
+
+impl OverlayFile {
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for OverlayFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Filter graph compositing input 1 (a full-frame overlay) onto input 0's
+/// video between the `before` and `after` filters, labelled `[v]`
+pub(crate) fn overlay_graph(before: &[String], after: &[String]) -> String {
+    let before = if before.is_empty() { "null".to_string() } else { before.join(",") };
+    let mut overlay = "[base][1:v]overlay=0:0".to_string();
+    for filter in after {
+        overlay.push(',');
+        overlay.push_str(filter);
+    }
+    format!("[0:v]{}[base];{}[v]", before, overlay)
+}
+
+/// Alpha-composite an RGBA `mark` onto `pixels` with its top-left corner at (`x`, `y`)
+#[allow(clippy::too_many_arguments)]
+fn blend(
+    pixels: &mut [u8],
+    width: u32,
+    height: u32,
+    channels: usize,
+    mark: &[u8],
+    mark_width: u32,
+    mark_height: u32,
+    x: i64,
+    y: i64,
+    opacity: f32,
+) {
+    let x0 = x.max(0);
+    let x1 = (x + mark_width as i64).min(width as i64);
+    let y0 = y.max(0);
+    let y1 = (y + mark_height as i64).min(height as i64);
+    
+    for dy in y0..y1 {
+        for dx in x0..x1 {
+            let src = (((dy - y) as usize) * mark_width as usize + (dx - x) as usize) * 4;
+            let alpha = mark[src + 3] as f32 / 255.0 * opacity;
+            if alpha <= 0.0 {
+                continue;
+            }
+            let dst = ((dy as usize) * width as usize + dx as usize) * channels;
+            
+            if channels == 4 {
+                // Source-over onto a possibly transparent pixel
+                let below = pixels[dst + 3] as f32 / 255.0;
+                let out = alpha + below * (1.0 - alpha);
+                for c in 0..3 {
+                    let value = (mark[src + c] as f32 * alpha + pixels[dst + c] as f32 * below * (1.0 - alpha)) / out;
+                    pixels[dst + c] = value.round().clamp(0.0, 255.0) as u8;
+                }
+                pixels[dst + 3] = (out * 255.0).round() as u8;
+            } else {
+                for c in 0..3 {
+                    let value = mark[src + c] as f32 * alpha + pixels[dst + c] as f32 * (1.0 - alpha);
+                    pixels[dst + c] = value.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// `blend` for opaque 16-bit RGB, with the 8-bit mark widened to 16 bits
+#[allow(clippy::too_many_arguments)]
+fn blend_u16(
+    pixels: &mut [u16],
+    width: u32,
+    height: u32,
+    mark: &[u8],
+    mark_width: u32,
+    mark_height: u32,
+    x: i64,
+    y: i64,
+    opacity: f32,
+) {
+    let x0 = x.max(0);
+    let x1 = (x + mark_width as i64).min(width as i64);
+    let y0 = y.max(0);
+    let y1 = (y + mark_height as i64).min(height as i64);
+    
+    for dy in y0..y1 {
+        for dx in x0..x1 {
+            let src = (((dy - y) as usize) * mark_width as usize + (dx - x) as usize) * 4;
+            let alpha = mark[src + 3] as f32 / 255.0 * opacity;
+            if alpha <= 0.0 {
+                continue;
+            }
+            let dst = ((dy as usize) * width as usize + dx as usize) * 3;
+            for c in 0..3 {
+                let value = mark[src + c] as f32 * 257.0 * alpha + pixels[dst + c] as f32 * (1.0 - alpha);
+                pixels[dst + c] = value.round().clamp(0.0, 65535.0) as u16;
+            }
+        }
+    }
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("svg"))
+}
+
+/// Rasterise an SVG with FFmpeg's librsvg decoder
+fn render_svg(path: &Path) -> Result<RgbaImage, FfmpegError> {
+    let output = FfmpegCommand::new()
+        .args(&["-width", &SVG_RENDER_WIDTH.to_string(), "-keep_ar", "1"])
+        .input(path)
+        .args(&["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png"])
+        .output("-")
+        .execute()
+        .map_err(|e| FfmpegError::ExecutionFailed(format!("SVG watermarks need FFmpeg with librsvg: {}", e)))?;
+    decode_png(&output.stdout)
+}
+
+/// Draw `text` with FFmpeg `drawtext` on a transparent canvas, trimmed to its ink
+fn render_text(text: &str, color: [u8; 3], font: Option<&Path>) -> Result<RgbaImage, FfmpegError> {
+    let text = text.lines().next().unwrap_or_default();
+    if text.trim().is_empty() {
+        return Err(FfmpegError::InvalidOutput("Watermark text is empty".to_string()));
+    }
+    
+    // Read from a file so the text needs no filter escaping
+    let text_file = OverlayFile(temp_path("watermark", "txt"));
+    std::fs::write(text_file.path(), text)?;
+    
+    let size = TEXT_RENDER_SIZE;
+    let canvas = format!(
+        "color=c=black@0.0:s={}x{},format=rgba",
+        (text.chars().count() as u32 + 1) * size,
+        size * 2
+    );
+    let mut drawtext = format!(
+        "drawtext=textfile={}:expansion=none:fontsize={}:fontcolor=0x{:02x}{:02x}{:02x}:x={}:y={}",
+        filter_path(text_file.path()),
+        size,
+        color[0],
+        color[1],
+        color[2],
+        size / 2,
+        size / 2
+    );
+    if let Some(font) = font {
+        drawtext.push_str(&format!(":fontfile={}", filter_path(font)));
+    }
+    
+    let output = FfmpegCommand::new()
+        .args(&["-f", "lavfi"])
+        .input(canvas)
+        .args(&["-vf", &drawtext, "-frames:v", "1", "-f", "image2pipe", "-vcodec", "png"])
+        .output("-")
+        .execute()?;
+    trim_transparent(decode_png(&output.stdout)?)
+        .ok_or_else(|| FfmpegError::InvalidOutput("Watermark text rendered no glyphs".to_string()))
+}
+
+fn decode_png(bytes: &[u8]) -> Result<RgbaImage, FfmpegError> {
+    image::load_from_memory_with_format(bytes, ImageFormat::Png)
+        .map(DynamicImage::into_rgba8)
+        .map_err(|e| FfmpegError::InvalidOutput(format!("Failed to decode rendered watermark: {}", e)))
+}
+
+/// Crop to the bounding box of non-transparent pixels (None if there are none)
+fn trim_transparent(image: RgbaImage) -> Option<RgbaImage> {
+    let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel[3] > 0 {
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+    }
+    if x0 > x1 {
+        return None;
+    }
+    Some(image::imageops::crop_imm(&image, x0, y0, x1 - x0 + 1, y1 - y0 + 1).to_image())
+}
+
+/// Path as a filter option value, escaped for the option and filter graph levels
+fn filter_path(path: &Path) -> String {
+    let option = path.display().to_string().replace('\\', "\\\\").replace('\'', "\\'").replace(':', "\\:");
+    let mut escaped = String::with_capacity(option.len());
+    for c in option.chars() {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn rendered(mark: RgbaImage, anchor: WatermarkAnchor, tile: bool) -> RenderedWatermark {
+        RenderedWatermark { mark, anchor, margin: 0.1, opacity: 1.0, scale: 0.25, tile }
+    }
+    
+    #[test]
+    fn test_anchored_placement() {
+        let mark = RgbaImage::from_pixel(40, 20, image::Rgba([255, 255, 255, 255]));
+        
+        // 400x200 output: mark 100x50, margin 20
+        let (w, h, positions) = rendered(mark.clone(), WatermarkAnchor::BottomRight, false).placements(400, 200);
+        assert_eq!((w, h), (100, 50));
+        assert_eq!(positions, [(280, 130)]);
+        let (_, _, positions) = rendered(mark.clone(), WatermarkAnchor::Center, false).placements(400, 200);
+        assert_eq!(positions, [(150, 75)]);
+        
+        // Tiles cover the whole output
+        let (_, _, tiles) = rendered(mark, WatermarkAnchor::Center, true).placements(400, 200);
+        assert!(tiles.len() > 4);
+        assert!(tiles.iter().any(|&(x, _)| x < 0));
+    }
+    
+    #[test]
+    fn test_composite_opacity() {
+        let mut watermark = rendered(RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 255])), WatermarkAnchor::TopLeft, false);
+        watermark.opacity = 0.5;
+        watermark.margin = 0.0;
+        watermark.scale = 0.5;
+        
+        let mut image = DynamicImage::ImageRgb8(image::RgbImage::new(8, 8));
+        watermark.apply(&mut image).unwrap();
+        let rgb = image.to_rgb8();
+        assert_eq!(rgb.get_pixel(0, 0)[0], 128);
+        assert_eq!(rgb.get_pixel(7, 7)[0], 0);
+        
+        // On a transparent canvas the mark keeps its colour at reduced alpha
+        let mut canvas = vec![0u8; 8 * 8 * 4];
+        watermark.apply_buffer(&mut canvas, 8, 8, 4).unwrap();
+        assert_eq!(&canvas[..4], &[255, 255, 255, 128]);
+    }
+    
+    #[test]
+    fn test_filter_escaping() {
+        assert_eq!(filter_path(Path::new("/tmp/a.txt")), "/tmp/a.txt");
+        assert_eq!(filter_path(Path::new("C:/fonts/it's.ttf")), "C\\\\:/fonts/it\\\\\\'s.ttf");
+        assert_eq!(
+            overlay_graph(&["crop=10:10:0:0".to_string()], &["format=yuv420p".to_string()]),
+            "[0:v]crop=10:10:0:0[base];[base][1:v]overlay=0:0,format=yuv420p[v]"
+        );
+        assert_eq!(WatermarkAnchor::parse("bottom_left"), Some(WatermarkAnchor::BottomLeft));
+    }
+}